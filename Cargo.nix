@@ -56,6 +56,28 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "f26201604c87b1e01bd3d98f8d5d9a8fcbb815e8cedb41ffccbeb4bf593a35fe"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.3" = overridableMkRustCrate (profileName: rec {
+    name = "ahash";
+    version = "0.8.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "2c99f64d1e06488f620f932677e24bc6e2897582980441ae90a671415bd7ec2f"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "compile-time-rng")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "const-random")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "getrandom")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "runtime-rng")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "cfg_if" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "const_random" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".const-random."0.1.15" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "getrandom" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".getrandom."0.2.8" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && (!((hostPlatform.parsed.cpu.name == "armv6l" || hostPlatform.parsed.cpu.name == "armv7l") && hostPlatform.parsed.kernel.name == "none")) then "once_cell" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".once_cell."1.17.1" { inherit profileName; };
+    };
+    buildDependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "version_check" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".version_check."0.9.4" { profileName = "__noProfile"; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".aho-corasick."0.7.20" = overridableMkRustCrate (profileName: rec {
     name = "aho-corasick";
     version = "0.7.20";
@@ -80,6 +102,16 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".anes."0.1.6" = overridableMkRustCrate (profileName: rec {
+    name = "anes";
+    version = "0.1.6";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "4b46cbb362ab8752921c97e041f5e366ee6297bd428a31275b9fcf1e380f7299"; };
+    features = builtins.concatLists [
+      [ "default" ]
+    ];
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".anyhow."1.0.69" = overridableMkRustCrate (profileName: rec {
     name = "anyhow";
     version = "1.0.69";
@@ -170,17 +202,24 @@ in
     version = "0.1.0";
     registry = "unknown";
     src = fetchCrateLocal (workspaceSrc + "/sdk");
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-sdk/json") "json")
+      [ "testing" ]
+    ];
     dependencies = {
       anyhow = rustPackages."registry+https://github.com/rust-lang/crates.io-index".anyhow."1.0.69" { inherit profileName; };
       apibara_core = rustPackages."unknown".apibara-core."0.1.0" { inherit profileName; };
       async_stream = rustPackages."registry+https://github.com/rust-lang/crates.io-index".async-stream."0.3.4" { inherit profileName; };
       async_trait = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".async-trait."0.1.66" { profileName = "__noProfile"; };
+      crossbeam_channel = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-channel."0.5.7" { inherit profileName; };
       futures = rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures."0.3.27" { inherit profileName; };
       futures_util = rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.27" { inherit profileName; };
       hex = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hex."0.4.3" { inherit profileName; };
       hyper = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper."0.14.25" { inherit profileName; };
       pin_project = rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project."1.0.12" { inherit profileName; };
       prost = rustPackages."registry+https://github.com/rust-lang/crates.io-index".prost."0.11.8" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-sdk/json" then "serde" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-sdk/json" then "serde_json" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.94" { inherit profileName; };
       thiserror = rustPackages."registry+https://github.com/rust-lang/crates.io-index".thiserror."1.0.39" { inherit profileName; };
       tokio = rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.26.0" { inherit profileName; };
       tokio_stream = rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio-stream."0.1.12" { inherit profileName; };
@@ -195,10 +234,15 @@ in
     version = "0.1.0";
     registry = "unknown";
     src = fetchCrateLocal (workspaceSrc + "/starknet");
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parquet")
+    ];
     dependencies = {
       anyhow = rustPackages."registry+https://github.com/rust-lang/crates.io-index".anyhow."1.0.69" { inherit profileName; };
       apibara_core = rustPackages."unknown".apibara-core."0.1.0" { inherit profileName; };
       apibara_node = rustPackages."unknown".apibara-node."0.1.0" { inherit profileName; };
+      apibara_sdk = rustPackages."unknown".apibara-sdk."0.1.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow."35.0.0" { inherit profileName; };
       backoff = rustPackages."registry+https://github.com/rust-lang/crates.io-index".backoff."0.4.0" { inherit profileName; };
       bloomfilter = rustPackages."registry+https://github.com/rust-lang/crates.io-index".bloomfilter."1.0.9" { inherit profileName; };
       byte_unit = rustPackages."registry+https://github.com/rust-lang/crates.io-index".byte-unit."4.0.19" { inherit profileName; };
@@ -210,6 +254,7 @@ in
       hex = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hex."0.4.3" { inherit profileName; };
       hyper = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper."0.14.25" { inherit profileName; };
       lazy_static = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lazy_static."1.4.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "parquet" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".parquet."35.0.0" { inherit profileName; };
       pbjson_types = rustPackages."registry+https://github.com/rust-lang/crates.io-index".pbjson-types."0.5.1" { inherit profileName; };
       pin_project = rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project."1.0.12" { inherit profileName; };
       prost = rustPackages."registry+https://github.com/rust-lang/crates.io-index".prost."0.11.8" { inherit profileName; };
@@ -229,7 +274,9 @@ in
       url = rustPackages."registry+https://github.com/rust-lang/crates.io-index".url."2.3.1" { inherit profileName; };
     };
     devDependencies = {
+      apibara_sdk = rustPackages."unknown".apibara-sdk."0.1.0" { inherit profileName; };
       assert_matches = rustPackages."registry+https://github.com/rust-lang/crates.io-index".assert_matches."1.5.0" { inherit profileName; };
+      criterion = rustPackages."registry+https://github.com/rust-lang/crates.io-index".criterion."0.4.0" { inherit profileName; };
       env_logger = rustPackages."registry+https://github.com/rust-lang/crates.io-index".env_logger."0.9.3" { inherit profileName; };
       quickcheck = rustPackages."registry+https://github.com/rust-lang/crates.io-index".quickcheck."1.0.3" { inherit profileName; };
       quickcheck_macros = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".quickcheck_macros."1.0.0" { profileName = "__noProfile"; };
@@ -318,6 +365,238 @@ in
     ];
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".arrow."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "73bdeeaf5bbeeb40c6e14849520b379cd22d8f605433e7c12a1d550adf8c4a06"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-csv")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-ipc")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-json")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "csv")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "default")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "ipc")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "json")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && (!(hostPlatform.parsed.cpu.name == "wasm32") || hostPlatform.parsed.cpu.name == "wasm32") then "ahash" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_arith" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-arith."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_cast" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_csv" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-csv."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_ipc" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-ipc."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_json" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-json."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_ord" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-ord."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_row" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-row."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_select" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_string" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-string."35.0.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-arith."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-arith";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "24a945eab89f800ab870b848a6105b464638271565d4ac80c439a349f6dae349"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-array";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "43489bbff475545b78b0e20bde1d22abd6c99e54499839f9e815a2fa5134a51b"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && (!(hostPlatform.parsed.cpu.name == "wasm32") || hostPlatform.parsed.cpu.name == "wasm32") then "ahash" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "hashbrown" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-buffer";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "a3759e4a52c593281184787af5435671dc8b1e78333e5a30242b2e2d6e3c9d1f"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-cast";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "b30c01f06172d3e8306fcc885ee97dff55ba8d48dbc2ef5fee2e75b55b8170c4"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_select" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_core" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-core."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-csv."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-csv";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "86059a1270d5fe268283447af53bda33f4226fbe9e3f3d109a26011dc97fdcf7"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_cast" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "csv" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".csv."1.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "csv_core" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".csv-core."0.1.10" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lazy_static" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lazy_static."1.4.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_core" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-core."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "regex" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".regex."1.7.1" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-data";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "19c7787c6cdbf9539b1ffb860bfc18c5848926ec3d62cbd52dc3b1ea35c874fd"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-ipc."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-ipc";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "690167cd0ad8c4444c7bbb573066b94982acb52da554db7b7b2837c0e2dce036"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_cast" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "flatbuffers" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".flatbuffers."23.1.21" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-json."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-json";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "28c47c78411bc7b77ab0b931b9f8acc8b1c6d7d9977e5895dbaa4632651e5824"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_cast" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "indexmap" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".indexmap."1.9.2" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_core" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-core."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "serde_json" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.94" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-ord."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-ord";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "d3945e160dc92c11c4108a13251940ffce418c221eed260c53d9bec4075aa52c"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_select" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-row."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-row";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "1951a64d60c37931ee85198e1728ad07e59cda50b9280367911c7d299dfe4bf7"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && (!(hostPlatform.parsed.cpu.name == "wasm32") || hostPlatform.parsed.cpu.name == "wasm32") then "ahash" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "half" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "hashbrown" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-schema";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "bf6b26f6a6f8410e3b9531cbd1886399b99842701da77d4b4cf2013f7708f20f"; };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-select";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "83deb30a09afdf654d346092ef03e965f9c05d9b0753164d531f41d290d553f4"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "default")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".arrow-string."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "arrow-string";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "b7529ba37a8bdc86cb69e00236064d122b3ac80e221270c7abc147fea63a9cee"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_select" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "regex" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".regex."1.7.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "regex_syntax" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".regex-syntax."0.6.28" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".assert_matches."1.5.0" = overridableMkRustCrate (profileName: rec {
     name = "assert_matches";
     version = "1.5.0";
@@ -666,6 +945,13 @@ in
     ];
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".cast."0.3.0" = overridableMkRustCrate (profileName: rec {
+    name = "cast";
+    version = "0.3.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "37b2a672a2cb129a2e41c10b1224bb368f9f37a2b16b612598138befd7b37eb5"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".cc."1.0.79" = overridableMkRustCrate (profileName: rec {
     name = "cc";
     version = "1.0.79";
@@ -728,6 +1014,44 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".ciborium."0.2.0" = overridableMkRustCrate (profileName: rec {
+    name = "ciborium";
+    version = "0.2.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "b0c137568cc60b904a7724001b35ce2630fd00d5d84805fbb608ab89509d788f"; };
+    features = builtins.concatLists [
+      [ "default" ]
+      [ "std" ]
+    ];
+    dependencies = {
+      ciborium_io = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ciborium-io."0.2.0" { inherit profileName; };
+      ciborium_ll = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ciborium-ll."0.2.0" { inherit profileName; };
+      serde = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".ciborium-io."0.2.0" = overridableMkRustCrate (profileName: rec {
+    name = "ciborium-io";
+    version = "0.2.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "346de753af073cc87b52b2083a506b38ac176a44cfb05497b622e27be899b369"; };
+    features = builtins.concatLists [
+      [ "alloc" ]
+      [ "std" ]
+    ];
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".ciborium-ll."0.2.0" = overridableMkRustCrate (profileName: rec {
+    name = "ciborium-ll";
+    version = "0.2.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "213030a2b5a4e0c0892b6652260cf6ccac84827b83a85a534e178e3906c4cf1b"; };
+    dependencies = {
+      ciborium_io = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ciborium-io."0.2.0" { inherit profileName; };
+      half = rustPackages."registry+https://github.com/rust-lang/crates.io-index".half."1.8.2" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".clang-sys."1.6.0" = overridableMkRustCrate (profileName: rec {
     name = "clang-sys";
     version = "1.6.0";
@@ -877,6 +1201,30 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".const-random."0.1.15" = overridableMkRustCrate (profileName: rec {
+    name = "const-random";
+    version = "0.1.15";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "368a7a772ead6ce7e1de82bfb04c485f3db8ec744f72925af5735e29a22cc18e"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "const_random_macro" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".const-random-macro."0.1.15" { profileName = "__noProfile"; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "proc_macro_hack" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".proc-macro-hack."0.5.20+deprecated" { profileName = "__noProfile"; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".const-random-macro."0.1.15" = overridableMkRustCrate (profileName: rec {
+    name = "const-random-macro";
+    version = "0.1.15";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "9d7d6ab3c3a2282db210df5f02c4dab6e0a7057af0fb7ebd4070f30fe05c0ddb"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "getrandom" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".getrandom."0.2.8" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "once_cell" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".once_cell."1.17.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "proc_macro_hack" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".proc-macro-hack."0.5.20+deprecated" { profileName = "__noProfile"; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "tiny_keccak" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".tiny-keccak."2.0.2" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".convert_case."0.4.0" = overridableMkRustCrate (profileName: rec {
     name = "convert_case";
     version = "0.4.0";
@@ -926,6 +1274,50 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".criterion."0.4.0" = overridableMkRustCrate (profileName: rec {
+    name = "criterion";
+    version = "0.4.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "e7c76e09c1aae2bc52b3d2f29e13c6572553b30c4aa1b8a49fd70de6412654cb"; };
+    features = builtins.concatLists [
+      [ "cargo_bench_support" ]
+      [ "default" ]
+      [ "plotters" ]
+      [ "rayon" ]
+    ];
+    dependencies = {
+      anes = rustPackages."registry+https://github.com/rust-lang/crates.io-index".anes."0.1.6" { inherit profileName; };
+      atty = rustPackages."registry+https://github.com/rust-lang/crates.io-index".atty."0.2.14" { inherit profileName; };
+      cast = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cast."0.3.0" { inherit profileName; };
+      ciborium = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ciborium."0.2.0" { inherit profileName; };
+      clap = rustPackages."registry+https://github.com/rust-lang/crates.io-index".clap."3.2.23" { inherit profileName; };
+      criterion_plot = rustPackages."registry+https://github.com/rust-lang/crates.io-index".criterion-plot."0.5.0" { inherit profileName; };
+      itertools = rustPackages."registry+https://github.com/rust-lang/crates.io-index".itertools."0.10.5" { inherit profileName; };
+      lazy_static = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lazy_static."1.4.0" { inherit profileName; };
+      num_traits = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+      oorandom = rustPackages."registry+https://github.com/rust-lang/crates.io-index".oorandom."11.1.3" { inherit profileName; };
+      plotters = rustPackages."registry+https://github.com/rust-lang/crates.io-index".plotters."0.3.4" { inherit profileName; };
+      rayon = rustPackages."registry+https://github.com/rust-lang/crates.io-index".rayon."1.7.0" { inherit profileName; };
+      regex = rustPackages."registry+https://github.com/rust-lang/crates.io-index".regex."1.7.1" { inherit profileName; };
+      serde = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" { inherit profileName; };
+      serde_derive = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_derive."1.0.156" { profileName = "__noProfile"; };
+      serde_json = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.94" { inherit profileName; };
+      tinytemplate = rustPackages."registry+https://github.com/rust-lang/crates.io-index".tinytemplate."1.2.1" { inherit profileName; };
+      walkdir = rustPackages."registry+https://github.com/rust-lang/crates.io-index".walkdir."2.3.3" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".criterion-plot."0.5.0" = overridableMkRustCrate (profileName: rec {
+    name = "criterion-plot";
+    version = "0.5.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "6b50826342786a51a89e2da3a28f1c32b06e387201bc2d19791f622c673706b1"; };
+    dependencies = {
+      cast = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cast."0.3.0" { inherit profileName; };
+      itertools = rustPackages."registry+https://github.com/rust-lang/crates.io-index".itertools."0.10.5" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".crossbeam-channel."0.5.7" = overridableMkRustCrate (profileName: rec {
     name = "crossbeam-channel";
     version = "0.5.7";
@@ -954,6 +1346,24 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".crossbeam-deque."0.8.3" = overridableMkRustCrate (profileName: rec {
+    name = "crossbeam-deque";
+    version = "0.8.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "ce6fd6f855243022dcecf8702fef0c297d4338e226845fe067f6341ad9fa0cef"; };
+    features = builtins.concatLists [
+      [ "crossbeam-epoch" ]
+      [ "crossbeam-utils" ]
+      [ "default" ]
+      [ "std" ]
+    ];
+    dependencies = {
+      cfg_if = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.0" { inherit profileName; };
+      crossbeam_epoch = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-epoch."0.9.14" { inherit profileName; };
+      crossbeam_utils = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-utils."0.8.15" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".crossbeam-epoch."0.8.2" = overridableMkRustCrate (profileName: rec {
     name = "crossbeam-epoch";
     version = "0.8.2";
@@ -977,6 +1387,26 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".crossbeam-epoch."0.9.14" = overridableMkRustCrate (profileName: rec {
+    name = "crossbeam-epoch";
+    version = "0.9.14";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "46bd5f3f85273295a9d14aedfb86f6aadbff6d8f5295c4a9edb08e819dcf5695"; };
+    features = builtins.concatLists [
+      [ "alloc" ]
+      [ "std" ]
+    ];
+    dependencies = {
+      cfg_if = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.0" { inherit profileName; };
+      crossbeam_utils = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-utils."0.8.15" { inherit profileName; };
+      memoffset = rustPackages."registry+https://github.com/rust-lang/crates.io-index".memoffset."0.8.0" { inherit profileName; };
+      scopeguard = rustPackages."registry+https://github.com/rust-lang/crates.io-index".scopeguard."1.1.0" { inherit profileName; };
+    };
+    buildDependencies = {
+      autocfg = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".autocfg."1.1.0" { profileName = "__noProfile"; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".crossbeam-queue."0.2.3" = overridableMkRustCrate (profileName: rec {
     name = "crossbeam-queue";
     version = "0.2.3";
@@ -1018,6 +1448,7 @@ in
     registry = "registry+https://github.com/rust-lang/crates.io-index";
     src = fetchCratesIo { inherit name version; sha256 = "3c063cd8cc95f5c377ed0d4b49a4b21f632396ff690e8470c29b3359b346984b"; };
     features = builtins.concatLists [
+      [ "default" ]
       [ "std" ]
     ];
     dependencies = {
@@ -1068,6 +1499,32 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".csv."1.2.1" = overridableMkRustCrate (profileName: rec {
+    name = "csv";
+    version = "1.2.1";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "0b015497079b9a9d69c02ad25de6c0a6edef051ea6360a327d0bd05802ef64ad"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "csv_core" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".csv-core."0.1.10" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "itoa" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".itoa."1.0.6" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "ryu" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ryu."1.0.13" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "serde" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".csv-core."0.1.10" = overridableMkRustCrate (profileName: rec {
+    name = "csv-core";
+    version = "0.1.10";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "2b2466559f260f48ad25fe6317b3c8dac77b5bdb5763ac7d9d6103530663bc90"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "default")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "memchr" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".memchr."2.5.0" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".ctrlc."3.2.5" = overridableMkRustCrate (profileName: rec {
     name = "ctrlc";
     version = "3.2.5";
@@ -1493,6 +1950,19 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "0ce7134b9999ecaf8bcd65542e436736ef32ddca1b3e06094cb6ec5755203b80"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".flatbuffers."23.1.21" = overridableMkRustCrate (profileName: rec {
+    name = "flatbuffers";
+    version = "23.1.21";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "77f5399c2c9c50ae9418e522842ad362f61ee48b346ac106807bd355a8a7c619"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "bitflags" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".bitflags."1.3.2" { inherit profileName; };
+    };
+    buildDependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "rustc_version" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".rustc_version."0.4.0" { profileName = "__noProfile"; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".flate2."1.0.25" = overridableMkRustCrate (profileName: rec {
     name = "flate2";
     version = "1.0.25";
@@ -1788,6 +2258,27 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".half."1.8.2" = overridableMkRustCrate (profileName: rec {
+    name = "half";
+    version = "1.8.2";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "eabb4a44450da02c90444cf74558da904edde8fb4e9035a9a6a4e15445af0bd7"; };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".half."2.2.1" = overridableMkRustCrate (profileName: rec {
+    name = "half";
+    version = "2.2.1";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "02b4af3693f1b705df946e9fe5631932443781d0aabb423b62fcd4d73f6d2fd0"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "num-traits")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && hostPlatform.parsed.cpu.name == "spirv" then "crunchy" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crunchy."0.2.2" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.12.3" = overridableMkRustCrate (profileName: rec {
     name = "hashbrown";
     version = "0.12.3";
@@ -1798,6 +2289,13 @@ in
     ];
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" = overridableMkRustCrate (profileName: rec {
+    name = "hashbrown";
+    version = "0.13.2";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "43a3c133739dddd0d2990f9a4bdf8eb4b21ef50e4851ca85ab661199821d510e"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".heck."0.4.1" = overridableMkRustCrate (profileName: rec {
     name = "heck";
     version = "0.4.1";
@@ -2117,6 +2615,13 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".integer-encoding."3.0.4" = overridableMkRustCrate (profileName: rec {
+    name = "integer-encoding";
+    version = "3.0.4";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "8bb03732005da905c88227371639bf1ad885cc712789c011c31c5fb3ab3ccf02"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".io-lifetimes."1.0.6" = overridableMkRustCrate (profileName: rec {
     name = "io-lifetimes";
     version = "1.0.6";
@@ -2236,6 +2741,100 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "830d08ce1d1d941e6b30645f1a0eb5643013d835ce3779a5fc208261dbe10f55"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-core."0.8.5" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-core";
+    version = "0.8.5";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "2cde5de06e8d4c2faabc400238f9ae1c74d5412d03a7bd067645ccbc47070e46"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "integers")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "lexical-parse-float")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "lexical-parse-integer")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "lexical-write-float")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "lexical-write-integer")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse-floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse-integers")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write-floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write-integers")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_parse_float" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-parse-float."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_parse_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-parse-integer."0.8.6" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_write_float" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-write-float."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_write_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-write-integer."0.8.5" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-parse-float."0.8.5" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-parse-float";
+    version = "0.8.5";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "683b3a5ebd0130b8fb52ba0bdc718cc56815b6a097e28ae5a6997d0ad17dc05f"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_parse_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-parse-integer."0.8.6" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-parse-integer."0.8.6" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-parse-integer";
+    version = "0.8.6";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "6d0994485ed0c312f6d965766754ea177d07f9c00c9b82a5ee62ed5b47945ee9"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-util";
+    version = "0.8.5";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "5255b9ff16ff898710eb9eb63cb39248ea8a5bb036bea8085b1a767ff6c4e3fc"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "integers")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse-floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "parse-integers")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write-floats")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "write-integers")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-write-float."0.8.5" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-write-float";
+    version = "0.8.5";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "accabaa1c4581f05a3923d1b4cfd124c329352288b7b9da09e766b0668116862"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_write_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-write-integer."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".lexical-write-integer."0.8.5" = overridableMkRustCrate (profileName: rec {
+    name = "lexical-write-integer";
+    version = "0.8.5";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "e1b6f3d1f4422866b68192d62f77bc5c700bee84f3069f2469d7bc8c77852446"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "lexical_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".lexical-util."0.8.5" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".libc."0.2.140" = overridableMkRustCrate (profileName: rec {
     name = "libc";
     version = "0.2.140";
@@ -2259,6 +2858,16 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".libm."0.2.6" = overridableMkRustCrate (profileName: rec {
+    name = "libm";
+    version = "0.2.6";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "348108ab3fba42ec82ff6e9564fc4ca0247bdccdc68dd8af9764bbc79c3c8ffb"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "default")
+    ];
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".libmdbx."0.1.12" = overridableMkRustCrate (profileName: rec {
     name = "libmdbx";
     version = "0.1.12";
@@ -2403,6 +3012,19 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".memoffset."0.8.0" = overridableMkRustCrate (profileName: rec {
+    name = "memoffset";
+    version = "0.8.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "d61c719bcfbcf5d62b3a09efa6088de8c54bc0bfcd3ea7ae39fcc186108b8de1"; };
+    features = builtins.concatLists [
+      [ "default" ]
+    ];
+    buildDependencies = {
+      autocfg = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".autocfg."1.1.0" { profileName = "__noProfile"; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".mime."0.3.16" = overridableMkRustCrate (profileName: rec {
     name = "mime";
     version = "0.3.16";
@@ -2568,6 +3190,25 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" = overridableMkRustCrate (profileName: rec {
+    name = "num";
+    version = "0.4.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "43db66d1170d347f9a065114077f7dccb00c1b9478c89384490a3425279a4606"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "num-bigint")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "std")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_bigint" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-bigint."0.4.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_complex" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-complex."0.4.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-integer."0.1.45" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_iter" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-iter."0.1.43" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_rational" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-rational."0.4.1" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".num-bigint."0.4.3" = overridableMkRustCrate (profileName: rec {
     name = "num-bigint";
     version = "0.4.3";
@@ -2586,6 +3227,19 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".num-complex."0.4.3" = overridableMkRustCrate (profileName: rec {
+    name = "num-complex";
+    version = "0.4.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "02e0d21255c828d6f128a1e41534206671e8c3ea0c62f32291e808dc82cff17d"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "std")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".num-integer."0.1.45" = overridableMkRustCrate (profileName: rec {
     name = "num-integer";
     version = "0.1.45";
@@ -2604,6 +3258,44 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".num-iter."0.1.43" = overridableMkRustCrate (profileName: rec {
+    name = "num-iter";
+    version = "0.1.43";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "7d03e6c028c5dc5cac6e2dec0efda81fc887605bb3d884578bb6d6bf7514e252"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "i128")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "std")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-integer."0.1.45" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+    buildDependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "autocfg" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".autocfg."1.1.0" { profileName = "__noProfile"; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".num-rational."0.4.1" = overridableMkRustCrate (profileName: rec {
+    name = "num-rational";
+    version = "0.4.1";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "0638a1c9d0a3c0914158145bc76cff373a75a627e6ecbfb71cbe6f453a5a19b0"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "num-bigint")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "num-bigint-std")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "std")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_bigint" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-bigint."0.4.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_integer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-integer."0.1.45" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+    buildDependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "autocfg" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".autocfg."1.1.0" { profileName = "__noProfile"; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" = overridableMkRustCrate (profileName: rec {
     name = "num-traits";
     version = "0.2.15";
@@ -2612,8 +3304,12 @@ in
     features = builtins.concatLists [
       [ "default" ]
       [ "i128" ]
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "libm")
       [ "std" ]
     ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "libm" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".libm."0.2.6" { inherit profileName; };
+    };
     buildDependencies = {
       autocfg = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".autocfg."1.1.0" { profileName = "__noProfile"; };
     };
@@ -2640,9 +3336,17 @@ in
       [ "default" ]
       [ "race" ]
       [ "std" ]
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "unstable")
     ];
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".oorandom."11.1.3" = overridableMkRustCrate (profileName: rec {
+    name = "oorandom";
+    version = "11.1.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "0ab1bc2a289d34bd04a330323ac98a1b4bc82c9d9fcb1e66b63caa84da26b575"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".openssl-probe."0.1.5" = overridableMkRustCrate (profileName: rec {
     name = "openssl-probe";
     version = "0.1.5";
@@ -2783,6 +3487,20 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".ordered-float."2.10.0" = overridableMkRustCrate (profileName: rec {
+    name = "ordered-float";
+    version = "2.10.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "7940cf2ca942593318d07fcf2596cdca60a85c9e7fab408a5e21a4f9dcd40d87"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "default")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "std")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_traits" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".os_str_bytes."6.4.1" = overridableMkRustCrate (profileName: rec {
     name = "os_str_bytes";
     version = "6.4.1";
@@ -2902,6 +3620,44 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".parquet."35.0.0" = overridableMkRustCrate (profileName: rec {
+    name = "parquet";
+    version = "35.0.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "695dc9993375343d09c61b555825da484a7e6be0ab4d3f1de9a918850ebec922"; };
+    features = builtins.concatLists [
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-array")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-buffer")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-cast")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-data")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-ipc")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-schema")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "arrow-select")
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "base64")
+    ];
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" && (!(hostPlatform.parsed.cpu.name == "wasm32") || hostPlatform.parsed.cpu.name == "wasm32") then "ahash" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_array" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-array."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_buffer" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-buffer."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_cast" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-cast."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_data" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-data."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_ipc" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-ipc."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_schema" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-schema."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "arrow_select" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".arrow-select."35.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "base64" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".base64."0.21.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "bytes" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.4.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "chrono" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.24" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "hashbrown" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num."0.4.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "num_bigint" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-bigint."0.4.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "paste" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".paste."1.0.12" { profileName = "__noProfile"; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "seq_macro" else null } = buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".seq-macro."0.3.3" { profileName = "__noProfile"; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "thrift" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".thrift."0.17.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "twox_hash" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".twox-hash."1.6.3" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".paste."1.0.12" = overridableMkRustCrate (profileName: rec {
     name = "paste";
     version = "1.0.12";
@@ -3032,6 +3788,43 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "8b870d8c151b6f2fb93e84a13146138f05d02ed11c7e7c54f8826aaaf7c9f184"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".plotters."0.3.4" = overridableMkRustCrate (profileName: rec {
+    name = "plotters";
+    version = "0.3.4";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "2538b639e642295546c50fcd545198c9d64ee2a38620a628724a3b266d5fbf97"; };
+    features = builtins.concatLists [
+      [ "area_series" ]
+      [ "line_series" ]
+      [ "plotters-svg" ]
+      [ "svg_backend" ]
+    ];
+    dependencies = {
+      num_traits = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num-traits."0.2.15" { inherit profileName; };
+      plotters_backend = rustPackages."registry+https://github.com/rust-lang/crates.io-index".plotters-backend."0.3.4" { inherit profileName; };
+      plotters_svg = rustPackages."registry+https://github.com/rust-lang/crates.io-index".plotters-svg."0.3.3" { inherit profileName; };
+      ${ if hostPlatform.parsed.cpu.name == "wasm32" && !(hostPlatform.parsed.kernel.name == "wasi") then "wasm_bindgen" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen."0.2.84" { inherit profileName; };
+      ${ if hostPlatform.parsed.cpu.name == "wasm32" && !(hostPlatform.parsed.kernel.name == "wasi") then "web_sys" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".web-sys."0.3.61" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".plotters-backend."0.3.4" = overridableMkRustCrate (profileName: rec {
+    name = "plotters-backend";
+    version = "0.3.4";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "193228616381fecdc1224c62e96946dfbc73ff4384fba576e052ff8c1bea8142"; };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".plotters-svg."0.3.3" = overridableMkRustCrate (profileName: rec {
+    name = "plotters-svg";
+    version = "0.3.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "f9a81d2759aae1dae668f783c308bc5c8ebd191ff4184aaa1b37f65a6ae5a56f"; };
+    dependencies = {
+      plotters_backend = rustPackages."registry+https://github.com/rust-lang/crates.io-index".plotters-backend."0.3.4" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".ppv-lite86."0.2.17" = overridableMkRustCrate (profileName: rec {
     name = "ppv-lite86";
     version = "0.2.17";
@@ -3124,6 +3917,13 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".proc-macro-hack."0.5.20+deprecated" = overridableMkRustCrate (profileName: rec {
+    name = "proc-macro-hack";
+    version = "0.5.20+deprecated";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "dc375e1527247fe1a97d8b7156678dfe7c1af2fc075c9a4db3690ecd2a148068"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".proc-macro2."1.0.52" = overridableMkRustCrate (profileName: rec {
     name = "proc-macro2";
     version = "1.0.52";
@@ -3351,6 +4151,30 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".rayon."1.7.0" = overridableMkRustCrate (profileName: rec {
+    name = "rayon";
+    version = "1.7.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "1d2df5196e37bcc87abebc0053e20787d73847bb33134a69841207dd0a47f03b"; };
+    dependencies = {
+      either = rustPackages."registry+https://github.com/rust-lang/crates.io-index".either."1.8.1" { inherit profileName; };
+      rayon_core = rustPackages."registry+https://github.com/rust-lang/crates.io-index".rayon-core."1.11.0" { inherit profileName; };
+    };
+  });
+  
+  "registry+https://github.com/rust-lang/crates.io-index".rayon-core."1.11.0" = overridableMkRustCrate (profileName: rec {
+    name = "rayon-core";
+    version = "1.11.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "4b8f95bd6966f5c87776639160a66bd8ab9895d9d4ab01ddba9fc60661aebe8d"; };
+    dependencies = {
+      crossbeam_channel = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-channel."0.5.7" { inherit profileName; };
+      crossbeam_deque = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-deque."0.8.3" { inherit profileName; };
+      crossbeam_utils = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crossbeam-utils."0.8.15" { inherit profileName; };
+      num_cpus = rustPackages."registry+https://github.com/rust-lang/crates.io-index".num_cpus."1.15.0" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".rdrand."0.4.0" = overridableMkRustCrate (profileName: rec {
     name = "rdrand";
     version = "0.4.0";
@@ -3699,6 +4523,16 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "f91339c0467de62360649f8d3e185ca8de4224ff281f66000de5eb2a77a79041"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".same-file."1.0.6" = overridableMkRustCrate (profileName: rec {
+    name = "same-file";
+    version = "1.0.6";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "93fc1dc3aaa9bfed95e02e6eadabb4baf7e3078b0bd1b4d7b6b0b68378900502"; };
+    dependencies = {
+      ${ if hostPlatform.isWindows then "winapi_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".winapi-util."0.1.5" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".schannel."0.1.21" = overridableMkRustCrate (profileName: rec {
     name = "schannel";
     version = "0.1.21";
@@ -3820,6 +4654,13 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".seq-macro."0.3.3" = overridableMkRustCrate (profileName: rec {
+    name = "seq-macro";
+    version = "0.3.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "e6b44e8fc93a14e66336d230954dda83d18b4605ccace8fe09bc7514a71ad0bc"; };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" = overridableMkRustCrate (profileName: rec {
     name = "serde";
     version = "1.0.156";
@@ -4441,6 +5282,18 @@ in
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".thrift."0.17.0" = overridableMkRustCrate (profileName: rec {
+    name = "thrift";
+    version = "0.17.0";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "7e54bc85fc7faa8bc175c4bab5b92ba8d9a3ce893d0e9f42cc455c8ab16a9e09"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "byteorder" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".byteorder."1.4.3" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "integer_encoding" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".integer-encoding."3.0.4" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "ordered_float" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".ordered-float."2.10.0" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".time."0.1.45" = overridableMkRustCrate (profileName: rec {
     name = "time";
     version = "0.1.45";
@@ -4504,12 +5357,24 @@ in
     features = builtins.concatLists [
       [ "default" ]
       [ "keccak" ]
+      (lib.optional (rootFeatures' ? "apibara-starknet/parquet") "shake")
     ];
     dependencies = {
       crunchy = rustPackages."registry+https://github.com/rust-lang/crates.io-index".crunchy."0.2.2" { inherit profileName; };
     };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".tinytemplate."1.2.1" = overridableMkRustCrate (profileName: rec {
+    name = "tinytemplate";
+    version = "1.2.1";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "be4d6b5f19ff7664e8c98d03e2139cb510db9b0a60b55f8e8709b689d939b6bc"; };
+    dependencies = {
+      serde = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.156" { inherit profileName; };
+      serde_json = rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.94" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".tinyvec."1.6.0" = overridableMkRustCrate (profileName: rec {
     name = "tinyvec";
     version = "1.6.0";
@@ -5285,6 +6150,17 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "3528ecfd12c466c6f163363caf2d02a71161dd5e1cc6ae7b34207ea2d42d81ed"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".twox-hash."1.6.3" = overridableMkRustCrate (profileName: rec {
+    name = "twox-hash";
+    version = "1.6.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "97fee6b57c6a41524a810daee9286c02d7752c4253064d0b05472833a438f675"; };
+    dependencies = {
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "cfg_if" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.0" { inherit profileName; };
+      ${ if rootFeatures' ? "apibara-starknet/parquet" then "static_assertions" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".static_assertions."1.1.0" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".typenum."1.16.0" = overridableMkRustCrate (profileName: rec {
     name = "typenum";
     version = "1.16.0";
@@ -5428,6 +6304,17 @@ in
     src = fetchCratesIo { inherit name version; sha256 = "49874b5167b65d7193b8aba1567f5c7d93d001cafc34600cee003eda787e483f"; };
   });
   
+  "registry+https://github.com/rust-lang/crates.io-index".walkdir."2.3.3" = overridableMkRustCrate (profileName: rec {
+    name = "walkdir";
+    version = "2.3.3";
+    registry = "registry+https://github.com/rust-lang/crates.io-index";
+    src = fetchCratesIo { inherit name version; sha256 = "36df944cda56c7d8d8b7496af378e6b16de9284591917d307c9b4d313c44e698"; };
+    dependencies = {
+      same_file = rustPackages."registry+https://github.com/rust-lang/crates.io-index".same-file."1.0.6" { inherit profileName; };
+      ${ if hostPlatform.isWindows then "winapi_util" else null } = rustPackages."registry+https://github.com/rust-lang/crates.io-index".winapi-util."0.1.5" { inherit profileName; };
+    };
+  });
+  
   "registry+https://github.com/rust-lang/crates.io-index".want."0.3.0" = overridableMkRustCrate (profileName: rec {
     name = "want";
     version = "0.3.0";
@@ -5555,13 +6442,21 @@ in
     features = builtins.concatLists [
       [ "Blob" ]
       [ "BlobPropertyBag" ]
+      [ "CanvasRenderingContext2d" ]
       [ "Crypto" ]
+      [ "Document" ]
+      [ "DomRect" ]
+      [ "DomRectReadOnly" ]
+      [ "Element" ]
       [ "Event" ]
       [ "EventTarget" ]
       [ "File" ]
       [ "FormData" ]
       [ "Headers" ]
+      [ "HtmlCanvasElement" ]
+      [ "HtmlElement" ]
       [ "MessageEvent" ]
+      [ "Node" ]
       [ "ReadableStream" ]
       [ "Request" ]
       [ "RequestCredentials" ]
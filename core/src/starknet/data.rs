@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     fmt::Display,
     hash::{Hash, Hasher},
 };
@@ -125,6 +126,21 @@ impl Hash for FieldElement {
     }
 }
 
+impl Eq for FieldElement {}
+
+impl PartialOrd for FieldElement {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Field elements are ordered by their numeric value.
+impl Ord for FieldElement {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
 impl Serialize for FieldElement {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
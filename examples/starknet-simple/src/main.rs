@@ -73,6 +73,7 @@ async fn main() -> Result<()> {
                 end_cursor,
                 finality,
                 batch,
+                ..
             } => {
                 // cursor that generated the batch. if cursor = `None`, then it's the start of the
                 // chain (includes genesis block).
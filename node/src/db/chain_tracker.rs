@@ -6,7 +6,7 @@ use apibara_core::stream::MessageData;
 use byteorder::{BigEndian, ReadBytesExt};
 use prost::Message;
 
-use super::{table::KeyDecodeError, Table, TableKey};
+use super::{table::KeyDecodeError, ProstCodec, Table, TableKey};
 
 /// A block's hash.
 pub trait BlockHash:
@@ -55,6 +55,7 @@ where
 {
     type Key = u64;
     type Value = CanonicalBlock;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "CanonicalBlock"
@@ -67,6 +68,7 @@ where
 {
     type Key = (u64, B::Hash);
     type Value = B;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "Block"
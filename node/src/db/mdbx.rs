@@ -1,14 +1,14 @@
-use std::{marker::PhantomData, ops::Range, path::Path};
+use std::{borrow::Cow, marker::PhantomData, ops::Range, path::Path};
 
 use apibara_core::stream::{MessageData, RawMessageData};
 use libmdbx::{
-    Cursor, Database, DatabaseFlags, Environment, EnvironmentBuilder, EnvironmentKind,
-    Error as MdbxError, Geometry, TableObject, Transaction, TransactionKind, WriteFlags, RW,
+    Cursor, Database, DatabaseFlags, Environment, EnvironmentBuilder, EnvironmentFlags,
+    EnvironmentKind, Error as MdbxError, Geometry, Mode, SyncMode, TableObject, Transaction,
+    TransactionKind, WriteFlags, RW,
 };
-use prost::Message;
 
 use super::{
-    table::{Table, TableKey},
+    table::{Table, TableCodec, TableKey},
     DupSortTable,
 };
 
@@ -42,6 +42,35 @@ pub struct MdbxEnvironmentBuilder<E: EnvironmentKind> {
     env: EnvironmentBuilder<E>,
     max_dbs: usize,
     geometry: Geometry<Range<usize>>,
+    read_only: bool,
+    durability: Durability,
+}
+
+/// How commits are flushed to disk, see [MdbxEnvironmentBuilder::with_durability].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Flush data and metadata to disk on every commit.
+    ///
+    /// A committed transaction survives a crash of the process or of the system.
+    #[default]
+    Sync,
+    /// Don't flush to disk on commit, let the operating system write the data back.
+    ///
+    /// Much faster for write-heavy workloads such as the initial sync. A crash of the
+    /// process loses nothing, since the data is in the page cache. A crash of the system
+    /// (power loss, kernel panic) loses the transactions committed since the last flush,
+    /// but the database is never corrupted: it opens at the last flushed commit, so the
+    /// consumer must be able to write the lost blocks again.
+    SafeNoSync,
+}
+
+impl From<Durability> for SyncMode {
+    fn from(durability: Durability) -> Self {
+        match durability {
+            Durability::Sync => SyncMode::Durable,
+            Durability::SafeNoSync => SyncMode::SafeNoSync,
+        }
+    }
 }
 
 /// Extension methods over mdbx environment.
@@ -62,18 +91,46 @@ pub trait MdbxTransactionExt<K: TransactionKind, E: EnvironmentKind> {
     ///
     /// Cannot use `cursor` as name since it's a method on transaction.
     fn open_cursor<T: Table>(&self) -> MdbxResult<TableCursor<'_, T, K>>;
+
+    /// Same as `open_table`, for the table inside `namespace`.
+    ///
+    /// See [namespaced_table_name].
+    fn open_table_in<T: Table>(&self, namespace: &str) -> MdbxResult<MdbxTable<'_, T, K, E>>;
+
+    /// Same as `open_cursor`, for the table inside `namespace`.
+    fn open_cursor_in<T: Table>(&self, namespace: &str) -> MdbxResult<TableCursor<'_, T, K>>;
 }
 
 /// Extension methods over mdbx RW transactions.
 pub trait MdbxRWTransactionExt {
     /// Ensure the given table database exists. Creates it if it doesn't.
     fn ensure_table<T: Table>(&self, flags: Option<DatabaseFlags>) -> MdbxResult<()>;
+
+    /// Same as `ensure_table`, for the table inside `namespace`.
+    fn ensure_table_in<T: Table>(
+        &self,
+        namespace: &str,
+        flags: Option<DatabaseFlags>,
+    ) -> MdbxResult<()>;
+}
+
+/// Returns the name of the database of table `T` inside `namespace`.
+///
+/// Namespaces let multiple sets of the same tables live in one environment, for example
+/// one per chain. The empty namespace is the default and uses the table name unchanged,
+/// others prefix it with `<namespace>.`.
+pub fn namespaced_table_name<T: Table>(namespace: &str) -> Cow<'static, str> {
+    if namespace.is_empty() {
+        Cow::Borrowed(T::db_name())
+    } else {
+        Cow::Owned(format!("{}.{}", namespace, T::db_name()))
+    }
 }
 
 impl<E: EnvironmentKind> MdbxEnvironmentExt<E> for Environment<E> {
     fn open(path: &Path) -> MdbxResult<Environment<E>> {
         let mut builder = Environment::new();
-        builder.set_max_dbs(16);
+        builder.set_max_dbs(32);
         builder.open(path)
     }
 
@@ -100,6 +157,8 @@ impl<E: EnvironmentKind> MdbxEnvironmentBuilder<E> {
             env,
             max_dbs: 100,
             geometry,
+            read_only: false,
+            durability: Durability::default(),
         }
     }
 
@@ -118,12 +177,45 @@ impl<E: EnvironmentKind> MdbxEnvironmentBuilder<E> {
         self
     }
 
+    /// Change the maximum number of databases (tables) in the environment.
+    pub fn with_max_dbs(mut self, max_dbs: usize) -> Self {
+        self.max_dbs = max_dbs;
+        self
+    }
+
+    /// Open an existing environment in read-only mode.
+    ///
+    /// The geometry is ignored, since it's set by the writer.
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Change how commits are flushed to disk, defaults to [Durability::Sync].
+    ///
+    /// Ignored in read-only mode.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
     /// Open the environment.
     pub fn open(mut self, path: &Path) -> MdbxResult<Environment<E>> {
-        self.env
-            .set_geometry(self.geometry)
-            .set_max_dbs(self.max_dbs)
-            .open(path)
+        if self.read_only {
+            self.env.set_flags(EnvironmentFlags {
+                mode: Mode::ReadOnly,
+                ..EnvironmentFlags::default()
+            });
+        } else {
+            self.env.set_flags(EnvironmentFlags {
+                mode: Mode::ReadWrite {
+                    sync_mode: self.durability.into(),
+                },
+                ..EnvironmentFlags::default()
+            });
+            self.env.set_geometry(self.geometry);
+        }
+        self.env.set_max_dbs(self.max_dbs).open(path)
     }
 }
 
@@ -139,7 +231,16 @@ where
     E: EnvironmentKind,
 {
     fn open_table<T: Table>(&self) -> MdbxResult<MdbxTable<'_, T, K, E>> {
-        let database = self.open_db(Some(T::db_name()))?;
+        self.open_table_in::<T>("")
+    }
+
+    fn open_cursor<T: Table>(&self) -> MdbxResult<TableCursor<'_, T, K>> {
+        self.open_table::<T>()?.cursor()
+    }
+
+    fn open_table_in<T: Table>(&self, namespace: &str) -> MdbxResult<MdbxTable<'_, T, K, E>> {
+        let name = namespaced_table_name::<T>(namespace);
+        let database = self.open_db(Some(&name))?;
         Ok(MdbxTable {
             txn: self,
             db: database,
@@ -147,32 +248,39 @@ where
         })
     }
 
-    fn open_cursor<T: Table>(&self) -> MdbxResult<TableCursor<'_, T, K>> {
-        self.open_table::<T>()?.cursor()
+    fn open_cursor_in<T: Table>(&self, namespace: &str) -> MdbxResult<TableCursor<'_, T, K>> {
+        self.open_table_in::<T>(namespace)?.cursor()
     }
 }
 
 impl<'env, E: EnvironmentKind> MdbxRWTransactionExt for Transaction<'env, RW, E> {
     fn ensure_table<T: Table>(&self, flags: Option<DatabaseFlags>) -> MdbxResult<()> {
+        self.ensure_table_in::<T>("", flags)
+    }
+
+    fn ensure_table_in<T: Table>(
+        &self,
+        namespace: &str,
+        flags: Option<DatabaseFlags>,
+    ) -> MdbxResult<()> {
         let flags = flags.unwrap_or_default();
-        let name = T::db_name();
-        self.create_db(Some(name), flags)?;
+        let name = namespaced_table_name::<T>(namespace);
+        self.create_db(Some(&name), flags)?;
         Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
-struct TableObjectWrapper<T>(T);
+struct TableObjectWrapper<T: Table>(T::Value);
 
 impl<'txn, T> TableObject<'txn> for TableObjectWrapper<T>
 where
-    T: Message + Default + Clone,
+    T: Table,
 {
     fn decode(data_val: &[u8]) -> MdbxResult<Self>
     where
         Self: Sized,
     {
-        T::decode(data_val)
+        T::Codec::decode(data_val)
             .map_err(|err| MdbxError::DecodeError(Box::new(err)))
             .map(Self)
     }
@@ -229,7 +337,7 @@ where
     pub fn get(&self, key: &T::Key) -> MdbxResult<Option<T::Value>> {
         let data = self
             .txn
-            .get::<TableObjectWrapper<_>>(&self.db, key.encode().as_ref())?;
+            .get::<TableObjectWrapper<T>>(&self.db, key.encode().as_ref())?;
         Ok(data.map(|d| d.0))
     }
 }
@@ -293,7 +401,7 @@ where
     pub fn first_dup(&mut self) -> MdbxResult<Option<T::Value>> {
         Ok(self
             .cursor
-            .first_dup::<TableObjectWrapper<_>>()?
+            .first_dup::<TableObjectWrapper<T>>()?
             .map(|d| d.0))
     }
 
@@ -301,7 +409,7 @@ where
     pub fn last_dup(&mut self) -> MdbxResult<Option<T::Value>> {
         Ok(self
             .cursor
-            .last_dup::<TableObjectWrapper<_>>()?
+            .last_dup::<TableObjectWrapper<T>>()?
             .map(|d| d.0))
     }
 
@@ -331,7 +439,7 @@ where
     T: Table,
 {
     pub fn put(&mut self, key: &T::Key, value: &T::Value) -> MdbxResult<()> {
-        let data = T::Value::encode_to_vec(value);
+        let data = T::Codec::encode(value);
         self.cursor
             .put(key.encode().as_ref(), &data, WriteFlags::default())?;
         Ok(())
@@ -348,7 +456,7 @@ where
     T: DupSortTable,
 {
     pub fn append_dup(&mut self, key: &T::Key, value: &T::Value) -> MdbxResult<()> {
-        let data = T::Value::encode_to_vec(value);
+        let data = T::Codec::encode(value);
         self.cursor
             .put(key.encode().as_ref(), &data, WriteFlags::APPEND_DUP)?;
         Ok(())
@@ -357,7 +465,7 @@ where
 
 #[allow(clippy::type_complexity)]
 fn map_kv_result<T>(
-    t: MdbxResult<Option<(TableKeyWrapper<T::Key>, TableObjectWrapper<T::Value>)>>,
+    t: MdbxResult<Option<(TableKeyWrapper<T::Key>, TableObjectWrapper<T>)>>,
 ) -> MdbxResult<Option<(T::Key, T::Value)>>
 where
     T: Table,
@@ -5,7 +5,7 @@ use std::marker::PhantomData;
 use apibara_core::stream::Sequence;
 use prost::Message;
 
-use super::Table;
+use super::{ProstCodec, Table};
 
 /// Table with messages by sequence.
 #[derive(Debug, Clone, Copy, Default)]
@@ -19,6 +19,7 @@ where
 {
     type Key = Sequence;
     type Value = M;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "Message"
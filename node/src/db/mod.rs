@@ -10,10 +10,13 @@ mod table;
 
 pub use self::cli::default_data_dir;
 pub use self::mdbx::{
-    MdbxEnvironmentExt, MdbxErrorExt, MdbxRWTransactionExt, MdbxTable, MdbxTransactionExt,
-    TableCursor,
+    namespaced_table_name, Durability, MdbxEnvironmentExt, MdbxErrorExt, MdbxRWTransactionExt,
+    MdbxTable, MdbxTransactionExt, TableCursor,
+};
+pub use self::table::{
+    ByteVec, DupSortTable, KeyDecodeError, ProstCodec, Table, TableCodec, TableKey,
+    ValueDecodeError,
 };
-pub use self::table::{ByteVec, DupSortTable, KeyDecodeError, Table, TableKey};
 
 pub mod tables {
     pub use super::chain_tracker::{
@@ -3,7 +3,7 @@
 use apibara_core::stream::{Sequence, StreamId};
 use prost::Message;
 
-use super::Table;
+use super::{ProstCodec, Table};
 
 /// Table with the state of each input sequence, together with the respective
 /// output range.
@@ -29,6 +29,7 @@ pub struct SequencerState {
 impl Table for SequencerStateTable {
     type Key = (StreamId, Sequence);
     type Value = SequencerState;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "SequencerState"
@@ -49,6 +50,7 @@ pub struct StreamState {
 impl Table for StreamStateTable {
     type Key = StreamId;
     type Value = StreamState;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "StreamState"
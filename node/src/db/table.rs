@@ -18,6 +18,15 @@ pub enum KeyDecodeError {
     Other(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
 
+/// Error related to decoding values.
+#[derive(Debug, thiserror::Error)]
+pub enum ValueDecodeError {
+    #[error("error decoding protobuf value")]
+    Prost(#[from] prost::DecodeError),
+    #[error("Other type of error")]
+    Other(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
 /// A fixed-capacity vector of bytes.
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ByteVec<const CAP: usize>(ArrayVec<u8, CAP>);
@@ -31,11 +40,45 @@ pub trait TableKey: Send + Sync + Sized {
 
 pub trait Table: Send + Sync {
     type Key: TableKey;
+    /// The table values.
+    ///
+    /// Values must be prost messages even if the table has a custom [Table::Codec]: values
+    /// that are read back raw are returned as [apibara_core::stream::RawMessageData],
+    /// which is only defined for messages.
     type Value: Message + Default + Clone;
+    /// How values are stored on disk, usually [ProstCodec].
+    type Codec: TableCodec<Self::Value>;
 
     fn db_name() -> &'static str;
 }
 
+/// Encodes and decodes the values of a table.
+///
+/// Tables store their values prost-encoded with [ProstCodec]. Implement this trait to store
+/// a different representation, for example only the fields that are needed. Values that
+/// are read back raw (without decoding) are returned as stored by the codec.
+pub trait TableCodec<V>: Send + Sync {
+    fn encode(value: &V) -> Vec<u8>;
+    fn decode(b: &[u8]) -> Result<V, ValueDecodeError>;
+}
+
+/// Stores values prost-encoded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProstCodec;
+
+impl<V> TableCodec<V> for ProstCodec
+where
+    V: Message + Default,
+{
+    fn encode(value: &V) -> Vec<u8> {
+        value.encode_to_vec()
+    }
+
+    fn decode(b: &[u8]) -> Result<V, ValueDecodeError> {
+        Ok(V::decode(b)?)
+    }
+}
+
 pub trait DupSortTable: Table {}
 
 impl<const CAP: usize> AsRef<[u8]> for ByteVec<CAP> {
@@ -154,3 +197,91 @@ impl TableKey for u64 {
             .map_err(KeyDecodeError::ReadError)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use libmdbx::{Environment, Error as MdbxError, NoWriteMap};
+    use prost::Message;
+    use tempfile::tempdir;
+
+    use crate::db::{MdbxEnvironmentExt, MdbxRWTransactionExt, MdbxTransactionExt};
+
+    use super::{ProstCodec, Table, TableCodec, ValueDecodeError};
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Counter {
+        #[prost(uint64, tag = "1")]
+        value: u64,
+    }
+
+    /// Stores counters as 8 bytes, big endian.
+    struct CounterCodec;
+
+    impl TableCodec<Counter> for CounterCodec {
+        fn encode(value: &Counter) -> Vec<u8> {
+            value.value.to_be_bytes().to_vec()
+        }
+
+        fn decode(b: &[u8]) -> Result<Counter, ValueDecodeError> {
+            let bytes: [u8; 8] = b
+                .try_into()
+                .map_err(|err| ValueDecodeError::Other(Box::new(err)))?;
+            Ok(Counter {
+                value: u64::from_be_bytes(bytes),
+            })
+        }
+    }
+
+    struct CounterTable;
+
+    impl Table for CounterTable {
+        type Key = u64;
+        type Value = Counter;
+        type Codec = CounterCodec;
+
+        fn db_name() -> &'static str {
+            "Counter"
+        }
+    }
+
+    /// Same table as [CounterTable], with the values prost-encoded.
+    struct ProstCounterTable;
+
+    impl Table for ProstCounterTable {
+        type Key = u64;
+        type Value = Counter;
+        type Codec = ProstCodec;
+
+        fn db_name() -> &'static str {
+            "Counter"
+        }
+    }
+
+    #[test]
+    fn test_custom_codec() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<CounterTable>(None).unwrap();
+        let mut cursor = txn.open_cursor::<CounterTable>().unwrap();
+        cursor.put(&0, &Counter { value: 300 }).unwrap();
+        let mut cursor = txn.open_cursor::<ProstCounterTable>().unwrap();
+        cursor.put(&1, &Counter { value: 300 }).unwrap();
+        txn.commit().unwrap();
+
+        let txn = db.begin_ro_txn().unwrap();
+        let mut cursor = txn.open_cursor::<CounterTable>().unwrap();
+        let (_, value) = cursor.seek_exact(&0).unwrap().unwrap();
+        assert_eq!(value, Counter { value: 300 });
+        // the prost-encoded value is not 8 bytes long.
+        match cursor.seek_exact(&1) {
+            Err(MdbxError::DecodeError(err)) => {
+                let err = err.downcast_ref::<ValueDecodeError>().unwrap();
+                assert!(matches!(err, ValueDecodeError::Other(_)));
+            }
+            other => panic!("expected a decode error, got {other:?}"),
+        }
+        txn.commit().unwrap();
+    }
+}
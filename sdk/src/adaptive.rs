@@ -0,0 +1,275 @@
+//! Adapt the batch size to the distance from the chain tip.
+
+use apibara_core::node::v1alpha2::{Cursor, DataFinality};
+use prost::Message;
+use tracing::debug;
+
+use crate::{Configuration, DataStream, DataStreamError, StreamStatus};
+
+/// Configuration of adaptive batching.
+///
+/// The stream starts with a large batch size to backfill quickly. Once the stream is close
+/// to the chain tip, it's reconfigured with a smaller batch size to reduce latency.
+///
+/// The stream is considered close to the tip after receiving `tip_after` consecutive
+/// batches that either finish before covering the requested number of blocks, or contain
+/// data that's not finalized yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdaptiveConfig {
+    /// Batch size used while backfilling.
+    pub backfill_batch_size: u64,
+    /// Batch size used close to the chain tip.
+    pub tip_batch_size: u64,
+    /// Number of consecutive batches close to the tip needed to switch batch size.
+    pub tip_after: usize,
+}
+
+impl AdaptiveConfig {
+    /// Set the batch size used while backfilling.
+    pub fn with_backfill_batch_size(mut self, batch_size: u64) -> Self {
+        self.backfill_batch_size = batch_size;
+        self
+    }
+
+    /// Set the batch size used close to the chain tip.
+    pub fn with_tip_batch_size(mut self, batch_size: u64) -> Self {
+        self.tip_batch_size = batch_size;
+        self
+    }
+
+    /// Set the number of consecutive batches close to the tip needed to switch batch size.
+    pub fn with_tip_after(mut self, tip_after: usize) -> Self {
+        self.tip_after = tip_after;
+        self
+    }
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        AdaptiveConfig {
+            backfill_batch_size: 100,
+            tip_batch_size: 1,
+            tip_after: 3,
+        }
+    }
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Returns the batch size to request for a new configuration.
+    pub(crate) fn adaptive_batch_size(&mut self, configuration: &Configuration<F>) -> u64 {
+        // the catch-up batch size is adaptive batching for this configuration only.
+        self.catchup_batching = configuration
+            .catchup_batch_size
+            .filter(|catchup_batch_size| *catchup_batch_size > configuration.batch_size)
+            .map(|catchup_batch_size| AdaptiveConfig {
+                backfill_batch_size: catchup_batch_size,
+                tip_batch_size: configuration.batch_size,
+                ..AdaptiveConfig::default()
+            });
+        let batch_size = match self.active_adaptive_batching() {
+            None => return configuration.batch_size,
+            Some(adaptive) => adaptive.backfill_batch_size,
+        };
+        self.tip_batches = 0;
+        batch_size
+    }
+
+    /// Returns the adaptive batching of the current configuration, if any.
+    fn active_adaptive_batching(&self) -> Option<&AdaptiveConfig> {
+        self.catchup_batching
+            .as_ref()
+            .or(self.adaptive_batching.as_ref())
+    }
+
+    /// Switches to the tip batch size if the stream is close to the tip.
+    ///
+    /// The new request restarts the stream after `end_cursor`, data for the previous
+    /// request is ignored. The request counts towards
+    /// [crate::ClientBuilder::with_max_in_flight_requests] like configuration changes do.
+    pub(crate) fn adapt_batch_size(
+        &mut self,
+        cursor: Option<&Cursor>,
+        end_cursor: &Cursor,
+        finality: DataFinality,
+    ) -> Result<(), DataStreamError> {
+        let adaptive = match self.active_adaptive_batching() {
+            None => return Ok(()),
+            Some(adaptive) => adaptive.clone(),
+        };
+        let mut request = match &self.last_request {
+            None => return Ok(()),
+            Some(request) => request.clone(),
+        };
+        let batch_size = request.batch_size.unwrap_or_default();
+        if batch_size <= adaptive.tip_batch_size {
+            return Ok(());
+        }
+
+        // without starting cursor, the batch starts at genesis.
+        let start = cursor.map(|c| c.order_key + 1).unwrap_or_default();
+        let blocks = (end_cursor.order_key + 1).saturating_sub(start);
+        let is_unfinalized = matches!(
+            finality,
+            DataFinality::DataStatusPending | DataFinality::DataStatusAccepted
+        );
+        if blocks >= batch_size && !is_unfinalized {
+            self.tip_batches = 0;
+            return Ok(());
+        }
+
+        self.tip_batches += 1;
+        if self.tip_batches < adaptive.tip_after {
+            return Ok(());
+        }
+        // the switch is retried on the next batch once the server catches up.
+        if !self.can_send_request() {
+            return Ok(());
+        }
+
+        debug!(
+            batch_size = adaptive.tip_batch_size,
+            "stream close to tip, reducing batch size"
+        );
+        self.stream_id += 1;
+        request.stream_id = Some(self.stream_id);
+        request.batch_size = Some(adaptive.tip_batch_size);
+        request.starting_cursor = Some(end_cursor.clone());
+        self.inner_tx.try_send(request.clone())?;
+        self.last_request = Some(request);
+        self.status = StreamStatus::Reconnecting;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::ReceiverStream;
+
+    use crate::{
+        test_utils::{batch_response, cursor, new_test_stream, new_test_stream_with},
+        Configuration, DataMessage,
+    };
+
+    use super::AdaptiveConfig;
+
+    #[tokio::test]
+    async fn test_adaptive_batching_shrinks_near_tip() {
+        let responses = vec![
+            // full batches.
+            batch_response(1, 0, 10),
+            // short batch, the stream is close to the tip.
+            batch_response(1, 10, 13),
+            batch_response(1, 13, 14),
+            // data with the new batch size.
+            batch_response(2, 13, 14),
+        ];
+        let (mut stream, configuration_tx, mut inner_rx) = new_test_stream(responses);
+        stream.adaptive_batching = Some(
+            AdaptiveConfig::default()
+                .with_backfill_batch_size(10)
+                .with_tip_batch_size(1)
+                .with_tip_after(1),
+        );
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let mut is_first = Vec::default();
+        while let Some(message) = stream.next().await {
+            match message.unwrap() {
+                DataMessage::Data {
+                    is_first_after_configure,
+                    ..
+                } => is_first.push(is_first_after_configure),
+                DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+            }
+        }
+        // the batch after the switch is for the old stream and it's skipped.
+        // the new request keeps the configuration, so its data is not the first.
+        assert_eq!(is_first, vec![true, false, false]);
+
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.stream_id, Some(1));
+        assert_eq!(request.batch_size, Some(10));
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.stream_id, Some(2));
+        assert_eq!(request.batch_size, Some(1));
+        assert_eq!(request.starting_cursor, Some(cursor(13)));
+        assert!(inner_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_batching_counts_as_in_flight_request() {
+        let (response_tx, response_rx) = mpsc::channel(128);
+        let inner = ReceiverStream::new(response_rx);
+        let (mut stream, configuration_tx, mut inner_rx) = new_test_stream_with(Box::pin(inner));
+        stream.max_in_flight_requests = Some(1);
+        stream.adaptive_batching = Some(
+            AdaptiveConfig::default()
+                .with_backfill_batch_size(10)
+                .with_tip_after(1),
+        );
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        assert!(futures::poll!(stream.next()).is_pending());
+
+        // the short batch switches the batch size, then the user reconfigures.
+        response_tx.send(Ok(batch_response(1, 0, 3))).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        assert!(futures::poll!(stream.next()).is_pending());
+        assert_eq!(inner_rx.try_recv().unwrap().stream_id, Some(1));
+        assert_eq!(inner_rx.try_recv().unwrap().stream_id, Some(2));
+        // the configuration waits for the batch size switch to be acknowledged.
+        assert!(inner_rx.try_recv().is_err());
+
+        response_tx.send(Ok(batch_response(2, 3, 4))).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(futures::poll!(stream.next()).is_pending());
+        assert_eq!(inner_rx.try_recv().unwrap().stream_id, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_catchup_batch_size() {
+        let responses = vec![
+            batch_response(1, 0, 10),
+            // the default configuration needs 3 short batches to switch.
+            batch_response(1, 10, 13),
+            batch_response(1, 13, 14),
+            batch_response(1, 14, 15),
+        ];
+        let (mut stream, configuration_tx, mut inner_rx) = new_test_stream(responses);
+        let configuration = Configuration::default()
+            .with_batch_size(2)
+            .with_catchup_batch_size(10);
+        configuration_tx.send(configuration).await.unwrap();
+
+        let mut received = 0;
+        while let Some(message) = stream.next().await {
+            message.unwrap();
+            received += 1;
+        }
+        assert_eq!(received, 4);
+
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.stream_id, Some(1));
+        assert_eq!(request.batch_size, Some(10));
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.stream_id, Some(2));
+        assert_eq!(request.batch_size, Some(2));
+        assert_eq!(request.starting_cursor, Some(cursor(15)));
+        assert!(inner_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! Handle consumers slower than the server.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use apibara_core::node::v1alpha2::{stream_data_response, StreamDataResponse};
+use futures::{Stream, StreamExt};
+use prost::Message;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{DataStream, ResponseStream};
+
+/// Number of responses buffered before [BackpressureStrategy] applies.
+pub const BACKPRESSURE_BUFFER_SIZE: usize = 128;
+
+/// What the stream does when the consumer doesn't poll it as fast as the server sends data.
+///
+/// Set it with [crate::ClientBuilder::with_backpressure].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackpressureStrategy {
+    /// Read from the server only when the stream is polled.
+    ///
+    /// The HTTP/2 flow control window fills up and the server waits for the consumer (see
+    /// [crate::ClientBuilder::with_initial_stream_window_size]). Nothing is lost.
+    #[default]
+    Block,
+    /// Read from the server in a background task, and drop the oldest batch once
+    /// [BACKPRESSURE_BUFFER_SIZE] responses are buffered.
+    ///
+    /// Dropped batches are lost: the stream continues with the following batches, whose
+    /// cursors are past the dropped data. A consumer storing the cursors, for example with
+    /// [crate::ClientBuilder::with_checkpoint_store], never receives the dropped data again,
+    /// so only use it when the most recent data matters more than completeness. Invalidate
+    /// messages are never dropped: if the buffer only holds invalidate messages and errors,
+    /// the background task stops reading from the server until the consumer catches up,
+    /// like [BackpressureStrategy::Block].
+    DropOldest,
+    /// Read from the server in a background task, and fail with
+    /// [crate::DataStreamError::Overloaded] once [BACKPRESSURE_BUFFER_SIZE] responses are
+    /// buffered.
+    ///
+    /// The buffered responses are discarded and the stream ends after the error. Reconnect
+    /// from the last cursor handled to resume.
+    Error,
+}
+
+/// The responses read by the background task, waiting for the consumer.
+#[derive(Default)]
+struct Buffer {
+    responses: VecDeque<Result<StreamDataResponse, tonic::Status>>,
+    ended: bool,
+    waker: Option<Waker>,
+}
+
+/// The backpressure state shared by the background task and the stream.
+#[derive(Debug)]
+pub(crate) struct BackpressureState {
+    /// Set when the responses stopped because the stream is overloaded.
+    overloaded: AtomicBool,
+    /// Number of responses in the buffer.
+    buffered: AtomicUsize,
+    capacity: usize,
+    /// Notified when the consumer takes a response from the buffer.
+    space: Notify,
+}
+
+impl BackpressureState {
+    fn new(capacity: usize) -> Self {
+        BackpressureState {
+            overloaded: AtomicBool::new(false),
+            buffered: AtomicUsize::new(0),
+            capacity,
+            space: Notify::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.buffered.load(Ordering::Acquire) >= self.capacity
+    }
+}
+
+/// A [ResponseStream] that reads the server responses in a background task.
+struct BufferedResponses {
+    buffer: Arc<Mutex<Buffer>>,
+    state: Arc<BackpressureState>,
+    task: JoinHandle<()>,
+}
+
+/// Returns `inner` with the given backpressure strategy, and its state.
+///
+/// With [BackpressureStrategy::Block], `inner` is returned unchanged.
+pub(crate) fn with_backpressure(
+    inner: ResponseStream,
+    strategy: BackpressureStrategy,
+    capacity: usize,
+) -> (ResponseStream, Option<Arc<BackpressureState>>) {
+    if strategy == BackpressureStrategy::Block {
+        return (inner, None);
+    }
+    let buffer = Arc::new(Mutex::new(Buffer::default()));
+    let state = Arc::new(BackpressureState::new(capacity));
+    let task = tokio::spawn(read_responses(
+        inner,
+        strategy,
+        buffer.clone(),
+        state.clone(),
+    ));
+    let stream = BufferedResponses {
+        buffer,
+        state: state.clone(),
+        task,
+    };
+    (Box::pin(stream), Some(state))
+}
+
+/// Moves the responses from `inner` to `buffer`, applying `strategy` once it's full.
+async fn read_responses(
+    mut inner: ResponseStream,
+    strategy: BackpressureStrategy,
+    buffer: Arc<Mutex<Buffer>>,
+    state: Arc<BackpressureState>,
+) {
+    'read: while let Some(mut response) = inner.next().await {
+        loop {
+            match push_response(&buffer, response, strategy, &state) {
+                Pushed::Buffered => break,
+                Pushed::Overloaded => break 'read,
+                Pushed::Full(rejected) => {
+                    response = rejected;
+                    state.space.notified().await;
+                }
+            }
+        }
+    }
+
+    let mut buffered = lock(&buffer);
+    buffered.ended = true;
+    if let Some(waker) = buffered.waker.take() {
+        waker.wake();
+    }
+}
+
+/// The outcome of [push_response].
+enum Pushed {
+    /// The response was added to the buffer.
+    Buffered,
+    /// The buffer is full and nothing can be dropped, the response is returned.
+    Full(Result<StreamDataResponse, tonic::Status>),
+    /// The buffer was full and the stream is overloaded.
+    Overloaded,
+}
+
+/// Adds `response` to `buffer`, applying `strategy` if it's full.
+fn push_response(
+    buffer: &Mutex<Buffer>,
+    response: Result<StreamDataResponse, tonic::Status>,
+    strategy: BackpressureStrategy,
+    state: &BackpressureState,
+) -> Pushed {
+    let mut buffered = lock(buffer);
+    if buffered.responses.len() >= state.capacity {
+        match strategy {
+            BackpressureStrategy::Block => {}
+            BackpressureStrategy::DropOldest => {
+                match buffered.responses.iter().position(is_droppable) {
+                    None => return Pushed::Full(response),
+                    Some(index) => {
+                        buffered.responses.remove(index);
+                    }
+                }
+            }
+            BackpressureStrategy::Error => {
+                state.overloaded.store(true, Ordering::Release);
+                state.buffered.store(0, Ordering::Release);
+                buffered.responses.clear();
+                return Pushed::Overloaded;
+            }
+        }
+    }
+    buffered.responses.push_back(response);
+    state
+        .buffered
+        .store(buffered.responses.len(), Ordering::Release);
+    if let Some(waker) = buffered.waker.take() {
+        waker.wake();
+    }
+    Pushed::Buffered
+}
+
+/// Returns true if dropping `response` only loses data, and not the stream state.
+fn is_droppable(response: &Result<StreamDataResponse, tonic::Status>) -> bool {
+    matches!(
+        response,
+        Ok(StreamDataResponse {
+            message: Some(
+                stream_data_response::Message::Data(_)
+                    | stream_data_response::Message::Heartbeat(_)
+            ),
+            ..
+        })
+    )
+}
+
+fn lock(buffer: &Mutex<Buffer>) -> MutexGuard<'_, Buffer> {
+    // the buffer is always valid, even if a thread panicked while holding the lock.
+    buffer.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+impl Stream for BufferedResponses {
+    type Item = Result<StreamDataResponse, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut buffered = lock(&self.buffer);
+        if let Some(response) = buffered.responses.pop_front() {
+            self.state
+                .buffered
+                .store(buffered.responses.len(), Ordering::Release);
+            self.state.space.notify_one();
+            return Poll::Ready(Some(response));
+        }
+        if buffered.ended {
+            return Poll::Ready(None);
+        }
+        buffered.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for BufferedResponses {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Returns true, once, if the responses stopped because the stream is overloaded.
+    pub(crate) fn take_overloaded(&self) -> bool {
+        match &self.backpressure {
+            None => false,
+            Some(state) => state.overloaded.swap(false, Ordering::AcqRel),
+        }
+    }
+
+    /// Returns true if the buffer is full because the consumer is behind the server.
+    pub(crate) fn is_backpressure_full(&self) -> bool {
+        match &self.backpressure {
+            None => false,
+            Some(state) => state.is_full(),
+        }
+    }
+
+    /// Returns the number of responses read from the server and waiting in the buffer.
+    pub(crate) fn backpressure_buffered(&self) -> usize {
+        match &self.backpressure {
+            None => 0,
+            Some(state) => state.buffered.load(Ordering::Acquire),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use apibara_core::node::v1alpha2::{stream_data_response, Invalidate, StreamDataResponse};
+    use futures::StreamExt;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream_with},
+        Configuration,
+    };
+
+    use super::{with_backpressure, BackpressureStrategy};
+
+    fn invalidate_response() -> StreamDataResponse {
+        StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Invalidate(
+                Invalidate::default(),
+            )),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let responses = vec![
+            Ok(invalidate_response()),
+            Ok(data_response(1, 1, Vec::default())),
+            Ok(data_response(1, 2, Vec::default())),
+            Ok(data_response(1, 3, Vec::default())),
+        ];
+        let inner = Box::pin(futures::stream::iter(responses));
+        let (stream, state) = with_backpressure(inner, BackpressureStrategy::DropOldest, 3);
+        let state = state.unwrap();
+        // let the background task fill the buffer.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let responses = stream.map(|r| r.unwrap()).collect::<Vec<_>>().await;
+        assert_eq!(
+            responses,
+            vec![
+                invalidate_response(),
+                data_response(1, 2, Vec::default()),
+                data_response(1, 3, Vec::default())
+            ]
+        );
+        assert!(!state.overloaded.load(Ordering::Acquire));
+        assert_eq!(state.buffered.load(Ordering::Acquire), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_waits_when_nothing_can_be_dropped() {
+        let responses = vec![
+            Ok(invalidate_response()),
+            Ok(invalidate_response()),
+            Ok(data_response(1, 1, Vec::default())),
+            Ok(data_response(1, 2, Vec::default())),
+        ];
+        let read = Arc::new(AtomicUsize::new(0));
+        let inner = futures::stream::iter(responses).inspect({
+            let read = read.clone();
+            move |_| {
+                read.fetch_add(1, Ordering::AcqRel);
+            }
+        });
+        let (stream, state) =
+            with_backpressure(Box::pin(inner), BackpressureStrategy::DropOldest, 2);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // the third response waits for the consumer instead of growing the buffer.
+        assert_eq!(read.load(Ordering::Acquire), 3);
+        assert!(state.unwrap().is_full());
+        let responses = stream.map(|r| r.unwrap()).collect::<Vec<_>>().await;
+        assert_eq!(
+            responses,
+            vec![
+                invalidate_response(),
+                invalidate_response(),
+                data_response(1, 1, Vec::default()),
+                data_response(1, 2, Vec::default())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_when_overloaded() {
+        let responses = (0..4)
+            .map(|n| Ok(data_response(1, n, Vec::default())))
+            .collect::<Vec<_>>();
+        let inner = Box::pin(futures::stream::iter(responses));
+        let (stream, state) = with_backpressure(inner, BackpressureStrategy::Error, 3);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(stream.count().await, 0);
+        assert!(state.unwrap().overloaded.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_pending_responses() {
+        let responses = vec![
+            Ok(data_response(1, 1, Vec::default())),
+            Ok(data_response(1, 2, Vec::default())),
+        ];
+        let inner = futures::stream::iter(responses).chain(futures::stream::pending());
+        let (inner, backpressure) =
+            with_backpressure(Box::pin(inner), BackpressureStrategy::DropOldest, 3);
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(inner);
+        stream.backpressure = backpressure;
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(stream.pending_responses(), 2);
+
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(stream.pending_responses(), 1);
+    }
+}
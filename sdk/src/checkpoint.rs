@@ -0,0 +1,143 @@
+//! Persist the stream cursor.
+
+use std::sync::{Arc, Mutex};
+
+use apibara_core::node::v1alpha2::{Cursor, DataFinality};
+use prost::Message;
+
+use crate::{DataStream, DataStreamError, RawDataMessage};
+
+/// Error returned by a [CheckpointStore].
+pub type CheckpointError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Stores the cursor to resume the stream from.
+///
+/// Enable it with [crate::ClientBuilder::with_checkpoint_store]. When connecting, the
+/// stream starts from the stored cursor. While streaming, the end cursor of each finalized
+/// batch is stored once the batch has been handled, that is when the stream is polled for
+/// the next message.
+pub trait CheckpointStore: Send + Sync {
+    /// Returns the stored cursor, if any.
+    fn load(&self) -> Result<Option<Cursor>, CheckpointError>;
+
+    /// Stores the given cursor, replacing the previous one.
+    fn save(&self, cursor: &Cursor) -> Result<(), CheckpointError>;
+}
+
+/// A [CheckpointStore] that keeps the cursor in memory.
+///
+/// Clones share the same cursor.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCheckpointStore {
+    cursor: Arc<Mutex<Option<Cursor>>>,
+}
+
+impl CheckpointStore for InMemoryCheckpointStore {
+    fn load(&self) -> Result<Option<Cursor>, CheckpointError> {
+        Ok(self.cursor.lock().map_err(|_| "poisoned lock")?.clone())
+    }
+
+    fn save(&self, cursor: &Cursor) -> Result<(), CheckpointError> {
+        *self.cursor.lock().map_err(|_| "poisoned lock")? = Some(cursor.clone());
+        Ok(())
+    }
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Stores the end cursor of the last batch received, even if it's not finalized.
+    ///
+    /// Call it after handling the last message, for example on shutdown, so that the stream
+    /// restarts from the latest position. Does nothing if no checkpoint store is configured
+    /// or no data was received yet. If the last batch was split and not all parts were
+    /// returned, stores the batch starting cursor instead.
+    pub fn flush_checkpoint(&self) -> Result<(), DataStreamError> {
+        let cursor = match &self.pending_split {
+            Some(RawDataMessage::Data { cursor, .. }) => cursor,
+            _ => &self.last_end_cursor,
+        };
+        match (&self.checkpoint_store, cursor) {
+            (Some(store), Some(cursor)) => store.save(cursor).map_err(DataStreamError::Checkpoint),
+            _ => Ok(()),
+        }
+    }
+
+    /// Stores the cursor of the finalized batch returned by the previous poll.
+    pub(crate) fn save_pending_checkpoint(&mut self) -> Result<(), DataStreamError> {
+        let cursor = match self.pending_checkpoint.take() {
+            None => return Ok(()),
+            Some(cursor) => cursor,
+        };
+        match &self.checkpoint_store {
+            None => Ok(()),
+            Some(store) => store.save(&cursor).map_err(DataStreamError::Checkpoint),
+        }
+    }
+
+    /// Marks the batch as handled once the stream is polled again, if it's finalized.
+    pub(crate) fn track_checkpoint(&mut self, end_cursor: &Cursor, finality: DataFinality) {
+        if self.checkpoint_store.is_some() && finality == DataFinality::DataStatusFinalized {
+            self.pending_checkpoint = Some(end_cursor.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::node::v1alpha2::{stream_data_response, DataFinality, StreamDataResponse};
+    use futures_util::StreamExt;
+
+    use crate::{
+        test_utils::{cursor, data_response, new_test_stream},
+        Configuration,
+    };
+
+    use super::{CheckpointStore, InMemoryCheckpointStore};
+
+    fn with_finality(
+        mut response: StreamDataResponse,
+        finality: DataFinality,
+    ) -> StreamDataResponse {
+        if let Some(stream_data_response::Message::Data(data)) = response.message.as_mut() {
+            data.finality = finality as i32;
+        }
+        response
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_finalized_batches() {
+        let responses = vec![
+            with_finality(
+                data_response(1, 1, Vec::default()),
+                DataFinality::DataStatusFinalized,
+            ),
+            with_finality(
+                data_response(1, 2, Vec::default()),
+                DataFinality::DataStatusAccepted,
+            ),
+        ];
+        let (mut stream, configuration_tx, _requests) = new_test_stream(responses);
+        let store = InMemoryCheckpointStore::default();
+        stream.checkpoint_store = Some(Arc::new(store.clone()));
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        stream.next().await.unwrap().unwrap();
+        // not stored until the batch is handled.
+        assert_eq!(store.load().unwrap(), None);
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(store.load().unwrap(), Some(cursor(1)));
+        // accepted data is only stored on flush.
+        assert!(stream.next().await.is_none());
+        assert_eq!(store.load().unwrap(), Some(cursor(1)));
+        stream.flush_checkpoint().unwrap();
+        assert_eq!(store.load().unwrap(), Some(cursor(2)));
+    }
+}
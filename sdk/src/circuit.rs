@@ -0,0 +1,183 @@
+//! Stop connecting to a failing server for a while.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Configuration of a [CircuitBreaker].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Number of failed connections within `window` that opens the breaker.
+    pub max_failures: usize,
+    /// Time window over which failures are counted.
+    pub window: Duration,
+    /// How long the breaker stays open before allowing a new connection.
+    pub cooldown: Duration,
+}
+
+impl CircuitBreakerConfig {
+    /// Set the number of failed connections that opens the breaker.
+    pub fn with_max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// Set the time window over which failures are counted.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set how long the breaker stays open.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            max_failures: 5,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Fails connections fast after repeated failures.
+///
+/// After `max_failures` failed connections within `window`, the breaker opens and
+/// connections fail immediately with [crate::ClientBuilderError::CircuitOpen], without
+/// contacting the server, until `cooldown` has passed. The next connection is then allowed
+/// through: if it fails, the breaker opens again right away, if it succeeds, the failures
+/// are forgotten.
+///
+/// The state is shared between clones, so reuse the same breaker for every connection
+/// attempt to the same server, see [crate::ClientBuilder::with_circuit_breaker].
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Arc<Mutex<CircuitState>>,
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    failures: VecDeque<Instant>,
+    open_until: Option<Instant>,
+    half_open: bool,
+}
+
+impl CircuitBreaker {
+    /// Create a new, closed, circuit breaker.
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            config,
+            state: Arc::new(Mutex::new(CircuitState::default())),
+        }
+    }
+
+    /// Returns true if connections currently fail fast.
+    pub fn is_open(&self) -> bool {
+        let state = self.lock();
+        matches!(state.open_until, Some(open_until) if Instant::now() < open_until)
+    }
+
+    /// Returns true if a connection can be attempted.
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.lock();
+        match state.open_until {
+            None => true,
+            Some(open_until) if Instant::now() < open_until => false,
+            Some(_) => {
+                state.open_until = None;
+                state.half_open = true;
+                true
+            }
+        }
+    }
+
+    /// Records a failed connection.
+    pub(crate) fn record_failure(&self) {
+        let mut state = self.lock();
+        let now = Instant::now();
+        while let Some(first) = state.failures.front() {
+            if now.duration_since(*first) <= self.config.window {
+                break;
+            }
+            state.failures.pop_front();
+        }
+        state.failures.push_back(now);
+
+        if state.half_open || state.failures.len() >= self.config.max_failures {
+            warn!(
+                failures = state.failures.len(),
+                cooldown = ?self.config.cooldown,
+                "too many failed connections, opening circuit breaker"
+            );
+            state.failures.clear();
+            state.half_open = false;
+            state.open_until = Some(now + self.config.cooldown);
+        }
+    }
+
+    /// Opens the breaker for `duration`, for example when the server asked to wait.
+    pub(crate) fn open_for(&self, duration: Duration) {
+        let mut state = self.lock();
+        state.failures.clear();
+        state.half_open = false;
+        state.open_until = Some(Instant::now() + duration);
+    }
+
+    /// Records a successful connection.
+    pub(crate) fn record_success(&self) {
+        let mut state = self.lock();
+        state.failures.clear();
+        state.half_open = false;
+        state.open_until = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CircuitState> {
+        // the state is always valid, even if a thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{CircuitBreaker, CircuitBreakerConfig};
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let config = CircuitBreakerConfig::default()
+            .with_max_failures(2)
+            .with_cooldown(Duration::from_millis(10));
+        let breaker = CircuitBreaker::new(config);
+
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+        breaker.record_failure();
+        assert!(breaker.is_open());
+        assert!(!breaker.allow());
+
+        // after the cooldown, one failure is enough to open it again.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(breaker.is_open());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(breaker.allow());
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+    }
+}
@@ -0,0 +1,64 @@
+//! Close the stream cleanly.
+
+use prost::Message;
+use tokio::sync::mpsc;
+
+use crate::{DataStream, DataStreamError};
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Closes the stream, after which it only returns `None`.
+    ///
+    /// The protocol has no close message, so closing ends the requests half of the gRPC
+    /// stream instead: the server sees that the client is done and can release the stream
+    /// resources right away, rather than when the connection times out. The configuration
+    /// channel is closed too, so sending a configuration through any [crate::DataStreamClient]
+    /// fails from now on.
+    ///
+    /// Call it after handling the last message: like polling the stream again, it stores
+    /// the checkpoint of that message if the stream has a checkpoint store.
+    pub fn close(&mut self) -> Result<(), DataStreamError> {
+        self.terminated = true;
+        self.pending_split = None;
+        self.configuration_rx.close();
+        // the stream never sends requests after closing, replace the sender to drop it now.
+        let (closed_tx, _) = mpsc::channel(1);
+        drop(std::mem::replace(&mut self.inner_tx, closed_tx));
+        self.save_pending_checkpoint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration,
+    };
+
+    #[tokio::test]
+    async fn test_close() {
+        let response = data_response(1, 0, Vec::default());
+        let (mut stream, configuration_tx, mut inner_rx) =
+            new_test_stream(vec![response.clone(), response]);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+
+        stream.close().unwrap();
+        assert!(stream.next().await.is_none());
+        assert!(configuration_tx
+            .send(Configuration::default())
+            .await
+            .is_err());
+        // the request stream ends after the requests already sent.
+        assert!(inner_rx.recv().await.is_some());
+        assert!(inner_rx.recv().await.is_none());
+    }
+}
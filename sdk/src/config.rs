@@ -1,11 +1,20 @@
-use apibara_core::node::v1alpha2::{Cursor, DataFinality};
+use apibara_core::{
+    node::v1alpha2::{Cursor, DataFinality},
+    starknet::v1alpha2::{Filter, HeaderFilter, StateUpdateFilter},
+};
 use prost::Message;
 
+use crate::cursor::cursor_is_after;
+
 /// Data stream configuration.
 #[derive(Debug, Clone)]
 pub struct Configuration<F: Message + Default> {
     /// Number of blocks per batch.
     pub batch_size: u64,
+    /// Number of blocks per batch until the stream is close to the chain tip.
+    ///
+    /// See [Configuration::with_catchup_batch_size].
+    pub catchup_batch_size: Option<u64>,
     /// Starting cursor.
     pub starting_cursor: Option<Cursor>,
     /// Data finality.
@@ -27,6 +36,7 @@ where
     ) -> Self {
         Self {
             batch_size,
+            catchup_batch_size: None,
             starting_cursor,
             finality,
             filter,
@@ -39,6 +49,22 @@ where
         self
     }
 
+    /// Set the batch size used to catch up with the chain tip.
+    ///
+    /// The stream starts with `catchup_batch_size` blocks per batch, then switches to the
+    /// batch size of [Configuration::with_batch_size] once it's close to the tip, without
+    /// sending a new configuration. The stream is close to the tip after receiving
+    /// [crate::AdaptiveConfig::default] `tip_after` consecutive batches that either cover
+    /// fewer blocks than requested, or contain data that's not finalized yet. On switching,
+    /// the stream restarts after the last batch received, so no data is sent twice.
+    ///
+    /// It's ignored if it's not larger than the batch size. It replaces the batch sizes of
+    /// [crate::ClientBuilder::with_adaptive_batching] for this configuration.
+    pub fn with_catchup_batch_size(mut self, catchup_batch_size: u64) -> Self {
+        self.catchup_batch_size = Some(catchup_batch_size);
+        self
+    }
+
     /// Set the starting cursor to start at the given block.
     pub fn with_starting_cursor(mut self, cursor: Cursor) -> Self {
         self.starting_cursor = Some(cursor);
@@ -60,6 +86,27 @@ where
         self
     }
 
+    /// Request pending data, that is data from blocks that are not accepted yet.
+    ///
+    /// Pending data is highly ephemeral: the pending block changes until it's accepted, so
+    /// pending batches are frequently followed by an invalidate message. Use
+    /// [crate::DataMessage::is_pending] to tell pending batches apart.
+    pub fn with_pending(self) -> Self {
+        self.with_finality(DataFinality::DataStatusPending)
+    }
+
+    /// Returns true if streaming with this configuration doesn't rewind the stream to before
+    /// the `previous` cursor.
+    ///
+    /// Use the end cursor of the last batch received as `previous`. A configuration without
+    /// starting cursor streams from the genesis block, so it's never forward only.
+    pub fn is_forward_only_from(&self, previous: &Cursor) -> bool {
+        match &self.starting_cursor {
+            None => false,
+            Some(cursor) => !cursor_is_after(previous, cursor),
+        }
+    }
+
     /// Configure the data filter.
     pub fn with_filter<G>(mut self, filter_closure: G) -> Self
     where
@@ -70,6 +117,44 @@ where
     }
 }
 
+impl Configuration<Filter> {
+    /// Request only the state updates, together with the header of their block.
+    ///
+    /// Replaces the filter with one that matches all storage diffs, declared and deployed
+    /// contracts and nonce updates, and no transactions, events or messages. The server
+    /// only sends the data matched by the filter, so batches contain blocks whose
+    /// `transactions`, `events` and `l2_to_l1_messages` are empty. Blocks without state
+    /// update are not sent at all. Narrow the state update filter with
+    /// [Configuration::with_filter] to track specific contracts only.
+    pub fn state_updates_only(self) -> Self {
+        self.with_filter(|mut filter| {
+            let state_update = StateUpdateFilter::default()
+                .add_storage_diff(|diff| diff)
+                .add_declared_contract(|declared| declared)
+                .add_deployed_contract(|deployed| deployed)
+                .add_nonce_update(|nonce| nonce);
+            filter
+                .with_header(HeaderFilter::weak())
+                .with_state_update(state_update)
+                .build()
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F> Configuration<F>
+where
+    F: Message + Default + serde::Serialize,
+{
+    /// Returns the filter encoded as JSON.
+    ///
+    /// The filter is sent to the server as protobuf, use this to log the filter when
+    /// debugging a stream. Requires the `json` feature.
+    pub fn filter_as_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.filter)
+    }
+}
+
 impl<F> Default for Configuration<F>
 where
     F: Message + Default,
@@ -77,6 +162,7 @@ where
     fn default() -> Self {
         Self {
             batch_size: 1,
+            catchup_batch_size: None,
             starting_cursor: None,
             finality: None,
             filter: F::default(),
@@ -89,7 +175,7 @@ mod tests {
     use std::collections::HashMap;
 
     use apibara_core::{
-        node::v1alpha2::DataFinality,
+        node::v1alpha2::{Cursor, DataFinality},
         starknet::v1alpha2::{FieldElement, Filter, HeaderFilter},
     };
 
@@ -132,6 +218,39 @@ mod tests {
         assert_eq!(true, config.filter.header.unwrap().weak);
     }
 
+    #[test]
+    fn test_config_with_pending() {
+        let config = Configuration::<Filter>::default().with_pending();
+        assert_eq!(Some(DataFinality::DataStatusPending), config.finality);
+    }
+
+    #[test]
+    fn test_config_state_updates_only() {
+        let config = Configuration::<Filter>::default().state_updates_only();
+        assert!(config.filter.header.unwrap().weak);
+        assert!(config.filter.transactions.is_empty());
+        assert!(config.filter.events.is_empty());
+        let state_update = config.filter.state_update.unwrap();
+        assert_eq!(state_update.storage_diffs.len(), 1);
+        assert_eq!(state_update.nonces.len(), 1);
+    }
+
+    #[test]
+    fn test_config_is_forward_only() {
+        let previous = Cursor {
+            order_key: 100,
+            unique_key: vec![],
+        };
+        let config = Configuration::<Filter>::default();
+        assert!(!config.is_forward_only_from(&previous));
+        let config = config.with_starting_block(99);
+        assert!(!config.is_forward_only_from(&previous));
+        let config = config.with_starting_block(100);
+        assert!(config.is_forward_only_from(&previous));
+        let config = config.with_starting_block(101);
+        assert!(config.is_forward_only_from(&previous));
+    }
+
     #[test]
     fn test_method_can_be_chained() {
         let mut first: HashMap<String, String> = HashMap::new();
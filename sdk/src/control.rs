@@ -0,0 +1,25 @@
+//! Control messages sent to the server.
+
+use apibara_core::node::v1alpha2::Cursor;
+use prost::Message;
+
+use crate::{DataStreamClient, DataStreamError};
+
+/// Control a data stream, in addition to changing its configuration.
+pub trait DataStreamControl {
+    /// Acknowledge that all data up to and including `cursor` was processed.
+    ///
+    /// The current protocol has no acknowledgement message, so this doesn't send anything to
+    /// the server and always succeeds. Call it after processing each batch anyway, so that
+    /// the server can use it for flow control once the protocol supports it.
+    fn ack(&self, cursor: Cursor) -> Result<(), DataStreamError>;
+}
+
+impl<F> DataStreamControl for DataStreamClient<F>
+where
+    F: Message + Default,
+{
+    fn ack(&self, _cursor: Cursor) -> Result<(), DataStreamError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! Compare cursors.
+
+use std::cmp::Ordering;
+
+use apibara_core::node::v1alpha2::Cursor;
+
+/// Compares two cursors by block number, then by block hash.
+///
+/// This is a total order, so it can be used to sort and deduplicate cursors. Cursors for
+/// different blocks at the same height are siblings on different forks: their order is
+/// the byte order of their hashes, which is consistent but says nothing about which block
+/// came first. A cursor without hash (empty `unique_key`) comes before all the cursors with
+/// hash at the same height.
+///
+/// Use [cursor_is_after] to check if a cursor is further along the chain.
+pub fn cursor_cmp(a: &Cursor, b: &Cursor) -> Ordering {
+    a.order_key
+        .cmp(&b.order_key)
+        .then_with(|| a.unique_key.cmp(&b.unique_key))
+}
+
+/// Returns true if `a` points to a block strictly higher than `b`.
+///
+/// Only the block numbers are compared: two cursors at the same height are never after
+/// each other, whether their hashes are equal, different or missing.
+pub fn cursor_is_after(a: &Cursor, b: &Cursor) -> bool {
+    a.order_key > b.order_key
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::node::v1alpha2::Cursor;
+
+    use super::{cursor_cmp, cursor_is_after};
+
+    fn cursor(order_key: u64, unique_key: &[u8]) -> Cursor {
+        Cursor {
+            order_key,
+            unique_key: unique_key.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_cursor_cmp() {
+        assert!(cursor_cmp(&cursor(1, b"b"), &cursor(2, b"a")).is_lt());
+        assert!(cursor_cmp(&cursor(1, b"a"), &cursor(1, b"a")).is_eq());
+        assert!(cursor_cmp(&cursor(1, b""), &cursor(1, b"a")).is_lt());
+        assert!(cursor_cmp(&cursor(1, b"b"), &cursor(1, b"a")).is_gt());
+
+        assert!(cursor_is_after(&cursor(2, b""), &cursor(1, b"a")));
+        assert!(!cursor_is_after(&cursor(1, b"b"), &cursor(1, b"a")));
+        assert!(!cursor_is_after(&cursor(1, b"a"), &cursor(2, b"")));
+    }
+}
@@ -0,0 +1,140 @@
+//! Add and remove contracts from a running stream.
+
+use apibara_core::{
+    node::v1alpha2::Cursor,
+    starknet::v1alpha2::{EventFilter, FieldElement, Filter, HeaderFilter},
+};
+
+use crate::{Configuration, DataStreamClient, DataStreamError};
+
+/// A [DataStreamClient] that keeps track of the current configuration, so that contracts can
+/// be added to or removed from the filter without building the whole filter again.
+///
+/// The protocol only supports replacing the whole filter, so each change sends the full
+/// updated configuration to the server.
+#[derive(Debug, Clone)]
+pub struct DynamicFilterClient {
+    client: DataStreamClient<Filter>,
+    configuration: Configuration<Filter>,
+}
+
+impl DynamicFilterClient {
+    /// Creates a new client, `configuration` must be the configuration currently used by the
+    /// stream.
+    pub fn new(client: DataStreamClient<Filter>, configuration: Configuration<Filter>) -> Self {
+        DynamicFilterClient {
+            client,
+            configuration,
+        }
+    }
+
+    /// Returns the current configuration.
+    pub fn configuration(&self) -> &Configuration<Filter> {
+        &self.configuration
+    }
+
+    /// Include the events emitted by the contract at `address`, starting from `cursor`.
+    ///
+    /// Use the end cursor of the last batch processed as `cursor`, so that the stream
+    /// restarts where it left off. Does nothing if the contract is already included.
+    pub async fn add_filter_contract(
+        &mut self,
+        address: FieldElement,
+        cursor: Cursor,
+    ) -> Result<(), DataStreamError> {
+        if self.has_contract(&address) {
+            return Ok(());
+        }
+        self.configuration
+            .filter
+            .events
+            .push(EventFilter::default().with_from_address(address));
+        self.send_configuration(cursor).await
+    }
+
+    /// Stop including the events emitted by the contract at `address`, starting from `cursor`.
+    ///
+    /// Does nothing if the contract is not included.
+    pub async fn remove_filter_contract(
+        &mut self,
+        address: &FieldElement,
+        cursor: Cursor,
+    ) -> Result<(), DataStreamError> {
+        if !self.has_contract(address) {
+            return Ok(());
+        }
+        self.configuration
+            .filter
+            .events
+            .retain(|event| event.from_address.as_ref() != Some(address));
+        self.send_configuration(cursor).await
+    }
+
+    fn has_contract(&self, address: &FieldElement) -> bool {
+        self.configuration
+            .filter
+            .events
+            .iter()
+            .any(|event| event.from_address.as_ref() == Some(address))
+    }
+
+    async fn send_configuration(&mut self, cursor: Cursor) -> Result<(), DataStreamError> {
+        // same as `Filter::build`, the server needs a header filter to stream data.
+        if self.configuration.filter.header.is_none() {
+            self.configuration.filter.with_header(HeaderFilter::weak());
+        }
+        self.configuration.starting_cursor = Some(cursor);
+        self.client.send(self.configuration.clone()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::{FieldElement, Filter};
+    use tokio::sync::mpsc;
+
+    use crate::{test_utils::cursor, Configuration};
+
+    use super::DynamicFilterClient;
+
+    #[tokio::test]
+    async fn test_add_and_remove_contracts() {
+        let (tx, mut rx) = mpsc::channel(128);
+        let mut client = DynamicFilterClient::new(tx, Configuration::<Filter>::default());
+        let first = FieldElement::from_u64(1);
+        let second = FieldElement::from_u64(2);
+
+        client
+            .add_filter_contract(first.clone(), cursor(1))
+            .await
+            .unwrap();
+        client
+            .add_filter_contract(second.clone(), cursor(2))
+            .await
+            .unwrap();
+        // already included, nothing is sent.
+        client
+            .add_filter_contract(first.clone(), cursor(3))
+            .await
+            .unwrap();
+        client
+            .remove_filter_contract(&first, cursor(4))
+            .await
+            .unwrap();
+
+        let configuration = rx.recv().await.unwrap();
+        assert_eq!(configuration.filter.events.len(), 1);
+        assert_eq!(configuration.starting_cursor, Some(cursor(1)));
+
+        let configuration = rx.recv().await.unwrap();
+        assert_eq!(configuration.filter.events.len(), 2);
+
+        let configuration = rx.recv().await.unwrap();
+        assert_eq!(configuration.starting_cursor, Some(cursor(4)));
+        assert_eq!(configuration.filter.events.len(), 1);
+        assert_eq!(configuration.filter.events[0].from_address, Some(second));
+
+        assert!(rx.try_recv().is_err());
+    }
+}
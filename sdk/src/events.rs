@@ -0,0 +1,265 @@
+//! Stream the events emitted by a single contract.
+//!
+//! This is a shortcut for the most common use case: the filter is built for you and events are
+//! flattened out of blocks.
+//!
+//! ```no_run
+//! use apibara_core::starknet::v1alpha2::{Block, FieldElement, Filter};
+//! use apibara_sdk::{ClientBuilder, EventMessage, Uri};
+//! use futures::TryStreamExt;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let address = FieldElement::from_hex(
+//!     "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+//! )?;
+//!
+//! let (mut events, _client) = ClientBuilder::<Filter, Block>::default()
+//!     .events_from(address)
+//!     .connect(Uri::from_static("https://mainnet.starknet.a5a.ch"))
+//!     .await?;
+//!
+//! while let Some(message) = events.try_next().await? {
+//!     match message {
+//!         EventMessage::Event { event, .. } => {
+//!             println!("event in block {}: {:?}", event.block_number(), event.item.event)
+//!         }
+//!         EventMessage::Invalidate { cursor } => println!("invalidate: {:?}", cursor),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use apibara_core::{
+    node::v1alpha2::Cursor,
+    starknet::v1alpha2::{Block, EventWithTransaction, FieldElement, Filter, HeaderFilter},
+};
+use futures::Stream;
+use pin_project::pin_project;
+
+use crate::{
+    with_block::block_cursor, ClientBuilder, ClientBuilderError, DataMessage, DataStream,
+    DataStreamClient, DataStreamError, Uri, WithBlock,
+};
+
+/// A message generated by [EventStream].
+#[derive(Debug)]
+pub enum EventMessage {
+    /// An event emitted by the contract.
+    Event {
+        /// The end cursor of the batch that contains the event.
+        ///
+        /// Resume from this cursor only after all events with the same cursor were handled.
+        end_cursor: Cursor,
+        /// The event, together with its transaction and receipt, and the block that
+        /// emitted it.
+        event: WithBlock<EventWithTransaction>,
+    },
+    /// Invalidate all events received after the given cursor.
+    Invalidate {
+        /// The cursor.
+        cursor: Option<Cursor>,
+    },
+}
+
+/// Builder for an [EventStream].
+///
+/// Created with [ClientBuilder::events_from].
+pub struct EventStreamBuilder {
+    inner: ClientBuilder<Filter, Block>,
+    address: FieldElement,
+}
+
+/// A stream of events emitted by a single contract.
+#[pin_project]
+#[derive(Debug)]
+pub struct EventStream {
+    #[pin]
+    inner: DataStream<Filter, Block>,
+    pending: VecDeque<EventMessage>,
+}
+
+impl ClientBuilder<Filter, Block> {
+    /// Stream the events emitted by the contract at `address`.
+    ///
+    /// The starting cursor, finality and batch size of the configuration, if any, are kept,
+    /// while the filter is replaced.
+    pub fn events_from(self, address: FieldElement) -> EventStreamBuilder {
+        EventStreamBuilder {
+            inner: self,
+            address,
+        }
+    }
+}
+
+impl EventStreamBuilder {
+    /// Create and connect to the stream at the given url.
+    pub async fn connect(
+        self,
+        url: Uri,
+    ) -> Result<(EventStream, DataStreamClient<Filter>), ClientBuilderError> {
+        let address = self.address;
+        let mut inner = self.inner;
+        let configuration = inner.configuration.take().unwrap_or_default();
+        let configuration = configuration.with_filter(|mut filter| {
+            // the header identifies the block of each event.
+            filter
+                .with_header(HeaderFilter::weak())
+                .add_event(|event| event.with_from_address(address.clone()))
+                .build()
+        });
+
+        let (inner, client) = inner.with_configuration(configuration).connect(url).await?;
+        Ok((EventStream::new(inner), client))
+    }
+}
+
+impl EventStream {
+    fn new(inner: DataStream<Filter, Block>) -> Self {
+        EventStream {
+            inner,
+            pending: VecDeque::default(),
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<EventMessage, DataStreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(message) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(message)));
+            }
+
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(DataMessage::Invalidate { cursor }))) => {
+                    return Poll::Ready(Some(Ok(EventMessage::Invalidate { cursor })));
+                }
+                Poll::Ready(Some(Ok(DataMessage::Data {
+                    end_cursor,
+                    finality,
+                    batch,
+                    ..
+                }))) => {
+                    // batches without events are skipped by polling again.
+                    for block in batch {
+                        // the filter always requests the header, so it's a server error.
+                        let block_cursor = match block_cursor(block.header.as_ref()) {
+                            None => {
+                                this.pending.clear();
+                                let err = DataStreamError::MissingBlockHeader;
+                                return Poll::Ready(Some(Err(err)));
+                            }
+                            Some(block_cursor) => block_cursor,
+                        };
+                        this.pending.extend(block.events.into_iter().map(|event| {
+                            EventMessage::Event {
+                                end_cursor: end_cursor.clone(),
+                                event: WithBlock {
+                                    block: block_cursor.clone(),
+                                    finality,
+                                    item: event,
+                                },
+                            }
+                        }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::{
+        Block, BlockHeader, Event, EventWithTransaction, FieldElement,
+    };
+    use futures_util::StreamExt;
+    use prost::Message;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration, DataStreamError,
+    };
+
+    use super::{EventMessage, EventStream};
+
+    fn block_with_events(number: u64, count: u64) -> Vec<u8> {
+        let events = (0..count)
+            .map(|i| EventWithTransaction {
+                event: Some(Event {
+                    from_address: Some(FieldElement::from_u64(i)),
+                    ..Event::default()
+                }),
+                ..EventWithTransaction::default()
+            })
+            .collect();
+        Block {
+            header: Some(BlockHeader {
+                block_number: number,
+                ..BlockHeader::default()
+            }),
+            events,
+            ..Block::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_events_are_flattened() {
+        let data = vec![
+            block_with_events(1, 2),
+            block_with_events(2, 0),
+            block_with_events(3, 1),
+        ];
+        let (inner, configuration_tx, _requests) = new_test_stream(vec![data_response(1, 0, data)]);
+        let mut stream = EventStream::new(inner);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let mut addresses = Vec::default();
+        while let Some(message) = stream.next().await {
+            match message.unwrap() {
+                EventMessage::Event { event, .. } => {
+                    let block = event.block_number();
+                    let address = event.item.event.unwrap().from_address.unwrap().hi_hi;
+                    addresses.push((block, address))
+                }
+                EventMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+            }
+        }
+        assert_eq!(addresses, vec![(1, 0), (1, 1), (3, 0)]);
+    }
+
+    #[tokio::test]
+    async fn test_block_without_header_is_an_error() {
+        let block = Block {
+            events: vec![EventWithTransaction::default()],
+            ..Block::default()
+        };
+        let data = vec![block_with_events(1, 1), block.encode_to_vec()];
+        let (inner, configuration_tx, _requests) = new_test_stream(vec![data_response(1, 0, data)]);
+        let mut stream = EventStream::new(inner);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        // the events of the batch are not returned.
+        let message = stream.next().await.unwrap();
+        assert!(matches!(message, Err(DataStreamError::MissingBlockHeader)));
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Fill the gaps in the data already stored before streaming.
+
+use apibara_core::node::v1alpha2::{Cursor, StreamDataRequest};
+use prost::Message;
+
+use crate::{DataStream, DataStreamError, StreamStatus};
+
+/// Error returned by a [GapSource].
+pub type GapError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Reports the block ranges missing from the data already stored by the consumer.
+///
+/// Used by [DataStream::with_gap_refill].
+pub trait GapSource {
+    /// Returns the missing block ranges, inclusive and sorted by number.
+    fn missing_ranges(&self) -> Result<Vec<(u64, u64)>, GapError>;
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Stream the block ranges missing from `source` before the configured data.
+    ///
+    /// Use it when restarting a consumer whose storage may have holes, for example after a
+    /// crash between non-contiguous writes. The missing ranges are read once, here, so call
+    /// it before polling the stream.
+    ///
+    /// Each configuration is first sent once per missing range, starting at the range
+    /// start, and then as configured once the stream reaches the end of the last range. A
+    /// range is done when a batch ends at or after its end, so the last batch of a range may
+    /// contain blocks that are already stored. Configuration changes while refilling
+    /// replace the configuration streamed at the end, and restart the current range.
+    pub fn with_gap_refill<G: GapSource>(mut self, source: &G) -> Result<Self, GapError> {
+        self.gap_ranges = source.missing_ranges()?.into();
+        Ok(self)
+    }
+
+    /// Returns the request for the first missing range instead of `request`, if any range
+    /// is left.
+    pub(crate) fn gap_refill_request(&mut self, request: StreamDataRequest) -> StreamDataRequest {
+        let (from, _) = match self.gap_ranges.front() {
+            None => return request,
+            Some(range) => *range,
+        };
+        let gap_request = range_request(&request, from);
+        self.gap_resume = Some(request);
+        gap_request
+    }
+
+    /// Marks the current range as done if the batch ending at `end_cursor` reached its end.
+    pub(crate) fn track_gap_refill(&mut self, end_cursor: &Cursor) {
+        if self.gap_resume.is_none() {
+            return;
+        }
+        if let Some((_, to)) = self.gap_ranges.front() {
+            if end_cursor.order_key >= *to {
+                self.gap_ranges.pop_front();
+                self.gap_range_done = true;
+            }
+        }
+    }
+
+    /// Sends the request for the next range, or the configured request after the last one.
+    pub(crate) fn advance_gap_refill(&mut self) -> Result<(), DataStreamError> {
+        // the rest of a split batch belongs to the current range.
+        if !self.gap_range_done || self.pending_split.is_some() {
+            return Ok(());
+        }
+        self.gap_range_done = false;
+        let mut request = match (self.gap_ranges.front(), self.gap_resume.take()) {
+            (_, None) => return Ok(()),
+            (None, Some(resume)) => resume,
+            (Some((from, _)), Some(resume)) => {
+                let request = range_request(&resume, *from);
+                self.gap_resume = Some(resume);
+                request
+            }
+        };
+
+        self.stream_id += 1;
+        request.stream_id = Some(self.stream_id);
+        self.inner_tx.try_send(request.clone())?;
+        self.last_request = Some(request);
+        self.status = StreamStatus::Reconnecting;
+        self.reset_batch_deadline();
+        Ok(())
+    }
+}
+
+/// Returns `request` changed to start at block `from`.
+fn range_request(request: &StreamDataRequest, from: u64) -> StreamDataRequest {
+    let starting_cursor = from.checked_sub(1).map(|order_key| Cursor {
+        order_key,
+        unique_key: Vec::default(),
+    });
+    StreamDataRequest {
+        starting_cursor,
+        ..request.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use crate::{
+        test_utils::{cursor, data_response, new_test_stream},
+        Configuration, DataMessage,
+    };
+
+    use super::{GapError, GapSource};
+
+    struct TestGaps;
+
+    impl GapSource for TestGaps {
+        fn missing_ranges(&self) -> Result<Vec<(u64, u64)>, GapError> {
+            Ok(vec![(5, 6)])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gap_refill() {
+        let responses = vec![
+            data_response(1, 6, Vec::default()),
+            data_response(2, 11, Vec::default()),
+        ];
+        let (stream, configuration_tx, mut inner_rx) = new_test_stream(responses);
+        let mut stream = stream.with_gap_refill(&TestGaps).unwrap();
+        let starting_cursor = cursor(10);
+        configuration_tx
+            .send(Configuration::default().with_starting_cursor(starting_cursor.clone()))
+            .await
+            .unwrap();
+
+        let message = stream.next().await.unwrap().unwrap();
+        assert!(
+            matches!(message, DataMessage::Data { end_cursor, .. } if end_cursor.order_key == 6)
+        );
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.starting_cursor.unwrap().order_key, 4);
+
+        let message = stream.next().await.unwrap().unwrap();
+        assert!(
+            matches!(message, DataMessage::Data { end_cursor, .. } if end_cursor.order_key == 11)
+        );
+        let request = inner_rx.recv().await.unwrap();
+        assert_eq!(request.stream_id, Some(2));
+        assert_eq!(request.starting_cursor, Some(starting_cursor));
+    }
+}
@@ -1,31 +1,83 @@
+mod adaptive;
+mod backpressure;
+mod checkpoint;
+mod circuit;
+mod close;
 pub mod config;
+mod control;
+mod cursor;
+mod dynamic_filter;
+mod events;
+mod gap;
+mod metrics;
+mod pool;
+mod raw;
+mod rechunk;
+mod retry;
+mod split;
+mod status;
+mod sync_channel;
+mod tee;
+#[cfg(test)]
+mod test_utils;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod two_phase;
+mod with_block;
 
 use std::{
+    collections::VecDeque,
     marker::PhantomData,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::{Duration, SystemTime},
 };
 
 use apibara_core::node::v1alpha2::{
     stream_client::StreamClient, stream_data_response, Cursor, DataFinality, StreamDataRequest,
     StreamDataResponse,
 };
-use futures::Stream;
+use futures::{stream::FusedStream, Future, Stream};
 use pin_project::pin_project;
 use prost::Message;
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::{
+    sync::mpsc::{
+        self,
+        error::{SendError, TrySendError},
+        Receiver, Sender,
+    },
+    time::{Instant, Sleep},
+};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{
-    metadata::{errors::InvalidMetadataValue, MetadataValue},
+    metadata::{errors::InvalidMetadataValue, Ascii, MetadataValue},
     transport::Channel,
-    Streaming,
 };
-use tracing::debug;
+use tracing::{debug, warn};
 
 // Re-export tonic Uri
 pub use tonic::transport::Uri;
 
+pub use crate::adaptive::AdaptiveConfig;
+pub use crate::backpressure::{BackpressureStrategy, BACKPRESSURE_BUFFER_SIZE};
+pub use crate::checkpoint::{CheckpointError, CheckpointStore, InMemoryCheckpointStore};
+pub use crate::circuit::{CircuitBreaker, CircuitBreakerConfig};
 pub use crate::config::Configuration;
+pub use crate::control::DataStreamControl;
+pub use crate::cursor::{cursor_cmp, cursor_is_after};
+pub use crate::dynamic_filter::DynamicFilterClient;
+pub use crate::events::{EventMessage, EventStream, EventStreamBuilder};
+pub use crate::gap::{GapError, GapSource};
+pub use crate::metrics::StreamMetrics;
+pub use crate::pool::PooledDataMessage;
+pub use crate::raw::{RawDataMessage, RawDataStream};
+pub use crate::rechunk::{ChunkMessage, RechunkedDataStream};
+pub use crate::retry::retry_after;
+pub use crate::status::StreamStatus;
+pub use crate::tee::TeeMessage;
+pub use crate::two_phase::TwoPhaseDataStream;
+pub use crate::with_block::WithBlock;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ClientBuilderError {
@@ -34,15 +86,57 @@ pub enum ClientBuilderError {
     #[error(transparent)]
     TonicError(#[from] tonic::transport::Error),
     #[error(transparent)]
-    FailedToConfigureStream(Box<dyn std::error::Error>),
+    FailedToConfigureStream(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error(transparent)]
     InvalidMetadata(#[from] InvalidMetadataValue),
     #[error(transparent)]
     StreamError(#[from] tonic::Status),
+    #[error("configuration channel closed")]
+    ConfigurationChannelClosed,
+    #[error("circuit breaker is open after repeated connection failures")]
+    CircuitOpen,
+    #[error("rate limited by the server, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+}
+
+/// Error generated by [DataStream].
+///
+/// This error is `Send + Sync`, so the stream can be moved to another task.
+#[derive(Debug, thiserror::Error)]
+pub enum DataStreamError {
+    #[error("failed to send request to the server")]
+    SendRequest(#[from] TrySendError<StreamDataRequest>),
+    #[error(transparent)]
+    StreamError(#[from] tonic::Status),
+    #[error("configuration channel closed")]
+    ConfigurationChannelClosed,
+    #[error("server sent data without end cursor")]
+    MissingEndCursor,
+    #[error("server sent a block without header")]
+    MissingBlockHeader,
+    #[error("no data received within the batch timeout")]
+    BatchTimeout,
+    #[error("server sent data with unknown finality {0}")]
+    UnknownFinality(i32),
+    #[error("failed to store checkpoint")]
+    Checkpoint(#[source] CheckpointError),
+    #[error("the consumer is too slow, the stream is overloaded")]
+    Overloaded,
+}
+
+/// Sending a configuration through a [DataStreamClient] fails only after the stream is
+/// dropped or closed with [DataStream::close].
+impl<F> From<SendError<Configuration<F>>> for DataStreamError
+where
+    F: Message + Default,
+{
+    fn from(_: SendError<Configuration<F>>) -> Self {
+        DataStreamError::ConfigurationChannelClosed
+    }
 }
 
 /// A message generated by [DataStream].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DataMessage<D: Message + Default> {
     /// A new batch of data.
     Data {
@@ -56,6 +150,23 @@ pub enum DataMessage<D: Message + Default> {
         finality: DataFinality,
         /// The batch of data.
         batch: Vec<D>,
+        /// True if this is not the last part of a batch split by
+        /// [ClientBuilder::with_max_batch_items].
+        ///
+        /// The end cursor of a partial batch is not safe to resume the stream from, use
+        /// [DataMessage::next_cursor] instead.
+        is_partial: bool,
+        /// True if this is the first batch since the stream was (re)configured with a
+        /// new [Configuration].
+        ///
+        /// Use it to reset any state tied to the previous configuration, such as decoders
+        /// or aggregators. Requests sent by the stream itself, for example by adaptive
+        /// batching, keep the configuration and don't set it.
+        is_first_after_configure: bool,
+        /// When the batch was received by the client.
+        ///
+        /// Compare it with the block timestamp to measure ingestion latency.
+        received_at: SystemTime,
     },
     /// Invalidate all data received after the given cursor.
     Invalidate {
@@ -64,6 +175,81 @@ pub enum DataMessage<D: Message + Default> {
     },
 }
 
+impl<D: Message + Default> DataMessage<D> {
+    /// Returns true if the message is a batch of pending data.
+    ///
+    /// Pending data comes from a block that's not accepted yet, expect it to be invalidated
+    /// and replaced as the pending block changes.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            DataMessage::Data {
+                finality: DataFinality::DataStatusPending,
+                ..
+            }
+        )
+    }
+
+    /// Returns the numbers of the first and last block covered by a data message.
+    ///
+    /// The batch starting cursor is the block before the batch, so this is the range
+    /// after it up to the end cursor, inclusive. A batch without starting cursor starts at
+    /// the genesis block. For partial batches, the range is the one of the full batch.
+    /// Returns `None` for invalidate messages.
+    pub fn block_range(&self) -> Option<(u64, u64)> {
+        match self {
+            DataMessage::Data {
+                cursor, end_cursor, ..
+            } => {
+                let first = cursor.as_ref().map(|c| c.order_key + 1).unwrap_or_default();
+                Some((first, end_cursor.order_key))
+            }
+            DataMessage::Invalidate { .. } => None,
+        }
+    }
+
+    /// Returns the cursor to checkpoint after handling the message, and to resume the
+    /// stream from.
+    ///
+    /// This is the end cursor for data, and the invalidate cursor for invalidate messages.
+    /// For partial batches, it's the batch starting cursor, so that the full batch is
+    /// sent again on restart. Returns `None` if the stream restarts from the beginning.
+    pub fn next_cursor(&self) -> Option<Cursor> {
+        match self {
+            DataMessage::Data {
+                cursor,
+                is_partial: true,
+                ..
+            } => cursor.clone(),
+            DataMessage::Data { end_cursor, .. } => Some(end_cursor.clone()),
+            DataMessage::Invalidate { cursor } => cursor.clone(),
+        }
+    }
+}
+
+/// How the token is sent in the `authorization` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// Send `Bearer <token>`.
+    #[default]
+    Bearer,
+    /// Send the token without prefix.
+    Raw,
+    /// Send `<scheme> <token>`.
+    Custom(String),
+}
+
+impl AuthScheme {
+    /// Returns the `authorization` header value for the given token.
+    pub fn authorization(&self, token: &str) -> String {
+        match self {
+            AuthScheme::Bearer => format!("Bearer {token}"),
+            AuthScheme::Raw => token.to_string(),
+            AuthScheme::Custom(scheme) => format!("{scheme} {token}"),
+        }
+    }
+}
+
 /// Data stream builder.
 ///
 /// This struct is used to configure and connect to an Apibara data stream.
@@ -74,12 +260,33 @@ where
     D: Message + Default,
 {
     token: Option<String>,
+    auth_scheme: AuthScheme,
     configuration: Option<Configuration<F>>,
+    decode_pool: bool,
+    batch_timeout: Option<Duration>,
+    fair_polling: bool,
+    adaptive_batching: Option<AdaptiveConfig>,
+    strict_finality: bool,
+    raw_tap: Option<Sender<StreamDataResponse>>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    metrics: Option<Arc<dyn StreamMetrics>>,
+    max_batch_items: Option<usize>,
+    max_in_flight_requests: Option<u64>,
+    max_duration: Option<Duration>,
+    interceptor: Option<RequestInterceptor>,
+    initial_stream_window_size: Option<u32>,
+    initial_connection_window_size: Option<u32>,
+    circuit_breaker: Option<CircuitBreaker>,
+    honor_retry_after: bool,
+    backpressure: BackpressureStrategy,
     _data: PhantomData<D>,
 }
 
 /// A stream of on-chain data.
-#[derive(Debug)]
+///
+/// The stream is `Send` and can be moved into a spawned task, but it's not `Sync`
+/// since the underlying gRPC stream isn't. Polling requires exclusive access anyway,
+/// so share a [DataStreamClient] between tasks instead.
 #[pin_project]
 pub struct DataStream<F, D>
 where
@@ -87,14 +294,71 @@ where
     D: Message + Default,
 {
     stream_id: u64,
+    configured_stream_id: u64,
+    data_stream_id: u64,
+    acknowledged_stream_id: u64,
+    max_in_flight_requests: Option<u64>,
     configuration_rx: Receiver<Configuration<F>>,
     #[pin]
-    inner: Streaming<StreamDataResponse>,
+    inner: ResponseStream,
     inner_tx: Sender<StreamDataRequest>,
+    last_end_cursor: Option<Cursor>,
+    decode_pool: Option<Vec<D>>,
+    batch_timeout: Option<Duration>,
+    batch_deadline: Option<Pin<Box<Sleep>>>,
+    fair_polling: bool,
+    poll_data_first: bool,
+    adaptive_batching: Option<AdaptiveConfig>,
+    catchup_batching: Option<AdaptiveConfig>,
+    tip_batches: usize,
+    last_request: Option<StreamDataRequest>,
+    strict_finality: bool,
+    raw_tap: Option<Sender<StreamDataResponse>>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    pending_checkpoint: Option<Cursor>,
+    metrics: Option<Arc<dyn StreamMetrics>>,
+    max_batch_items: Option<usize>,
+    backpressure: Option<Arc<backpressure::BackpressureState>>,
+    pending_split: Option<RawDataMessage>,
+    deadline: Option<Pin<Box<Sleep>>>,
+    deadline_reached: bool,
+    gap_ranges: VecDeque<(u64, u64)>,
+    gap_resume: Option<StreamDataRequest>,
+    gap_range_done: bool,
+    status: StreamStatus,
+    terminated: bool,
     _data: PhantomData<D>,
 }
 
+impl<F, D> std::fmt::Debug for DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataStream")
+            .field("stream_id", &self.stream_id)
+            .field("last_end_cursor", &self.last_end_cursor)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The stream of responses from the server.
+type ResponseStream =
+    Pin<Box<dyn Stream<Item = Result<StreamDataResponse, tonic::Status>> + Send + 'static>>;
+
+/// A user interceptor that runs on the stream request, see [ClientBuilder::with_interceptor].
+type RequestInterceptor = Box<
+    dyn FnMut(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + Send + 'static,
+>;
+
 /// A client used to control a data stream.
+///
+/// The client is `Send + Sync` and cheap to clone.
+///
+/// Configurations are applied in order, but the stream only sends a request for the latest
+/// configuration received since it was last polled: configurations sent in a burst replace
+/// each other, and the intermediate ones never take effect.
 pub type DataStreamClient<F> = Sender<Configuration<F>>;
 
 impl<F, D> ClientBuilder<F, D>
@@ -108,12 +372,203 @@ where
         self
     }
 
+    /// Use the given scheme to send the token, defaults to [AuthScheme::Bearer].
+    pub fn with_auth_scheme(mut self, auth_scheme: AuthScheme) -> Self {
+        self.auth_scheme = auth_scheme;
+        self
+    }
+
     /// Send the given configuration upon connect.
     pub fn with_configuration(mut self, configuration: Configuration<F>) -> Self {
         self.configuration = Some(configuration);
         self
     }
 
+    /// Reuse decoded items between batches received with [DataStream::next_with].
+    ///
+    /// This reduces allocations when decoding wide batches.
+    pub fn with_decode_pool(mut self) -> Self {
+        self.decode_pool = true;
+        self
+    }
+
+    /// Fail with [DataStreamError::BatchTimeout] if no data is received for `timeout`.
+    ///
+    /// The timer starts when a configuration is sent and is reset on each batch of data.
+    /// Heartbeats don't reset the timer, so this detects a stalled stream even if the
+    /// connection is alive. The stream can be polled again after the error, in which case
+    /// a new timeout window starts.
+    pub fn with_batch_timeout(mut self, timeout: Duration) -> Self {
+        self.batch_timeout = Some(timeout);
+        self
+    }
+
+    /// Alternate between polling configuration changes and data first.
+    ///
+    /// Use this if the stream is reconfigured frequently, so that data is delivered even
+    /// while configuration changes keep arriving. Data received for an older configuration
+    /// may be returned before the new configuration is applied.
+    pub fn with_fair_polling(mut self) -> Self {
+        self.fair_polling = true;
+        self
+    }
+
+    /// Change the batch size depending on the distance from the chain tip.
+    ///
+    /// The batch size of the configurations sent is replaced by the backfill batch size
+    /// of `adaptive`. Once the stream is close to the tip, it's restarted from the last
+    /// batch received with the tip batch size. See [AdaptiveConfig].
+    pub fn with_adaptive_batching(mut self, adaptive: AdaptiveConfig) -> Self {
+        self.adaptive_batching = Some(adaptive);
+        self
+    }
+
+    /// Fail with [DataStreamError::UnknownFinality] if the server sends data with a finality
+    /// this client doesn't know.
+    ///
+    /// By default, unknown finality is logged and reported as
+    /// [DataFinality::DataStatusUnknown].
+    pub fn with_strict_finality(mut self) -> Self {
+        self.strict_finality = true;
+        self
+    }
+
+    /// Send a copy of every response received from the server to `tap`.
+    ///
+    /// Responses are sent before they're handled, including heartbeats and responses
+    /// for previous configurations. Use it to debug the protocol. Responses are dropped
+    /// if the channel is full, so a slow tap never blocks the stream.
+    pub fn with_raw_tap(mut self, tap: Sender<StreamDataResponse>) -> Self {
+        self.raw_tap = Some(tap);
+        self
+    }
+
+    /// Resume the stream from the cursor in `store`, and store the cursor of finalized data
+    /// once it's handled.
+    ///
+    /// The stored cursor replaces the starting cursor of the configuration sent on connect.
+    /// See [CheckpointStore] and [DataStream::flush_checkpoint].
+    pub fn with_checkpoint_store<S: CheckpointStore + 'static>(mut self, store: S) -> Self {
+        self.checkpoint_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Report measurements of the stream to `metrics`.
+    ///
+    /// See [StreamMetrics] for the measurements reported.
+    pub fn with_metrics<M: StreamMetrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Split batches with more than `max_items` items into multiple messages.
+    ///
+    /// All parts have the end cursor of the full batch, and all parts except the last one
+    /// are marked with `is_partial`. Don't resume the stream from the end cursor of a partial
+    /// batch, use [DataMessage::next_cursor]. Checkpoints are stored only after the last part.
+    pub fn with_max_batch_items(mut self, max_items: usize) -> Self {
+        assert!(max_items > 0, "max batch items must be positive");
+        self.max_batch_items = Some(max_items);
+        self
+    }
+
+    /// Limit the number of requests sent to the server and not acknowledged yet.
+    ///
+    /// Every configuration change sends a new request, and so does switching to the tip
+    /// batch size with [ClientBuilder::with_adaptive_batching]. The server acknowledges a
+    /// request when it sends the first response (data, invalidate or heartbeat) with the
+    /// request stream id, which also acknowledges all previous requests. Once
+    /// `max_requests` are in flight, new configurations stay queued in the configuration
+    /// channel until the server catches up, so that rapid reconfiguration doesn't flood the
+    /// server. Data for stale configurations is still skipped while waiting.
+    pub fn with_max_in_flight_requests(mut self, max_requests: u64) -> Self {
+        assert!(max_requests > 0, "max in-flight requests must be positive");
+        self.max_in_flight_requests = Some(max_requests);
+        self
+    }
+
+    /// End the stream once it has been running for `max_duration`.
+    ///
+    /// The duration is sent to the server as the gRPC request deadline, and also enforced
+    /// by a client-side timer in case the server doesn't. When the deadline is reached the
+    /// stream ends like it does when the server closes it, returning `None`, so that batch
+    /// jobs can shut down cleanly. Use [DataStream::is_deadline_reached] to tell the two
+    /// apart.
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Run `interceptor` on the stream request, after the authorization header is set.
+    ///
+    /// Use it to add metadata such as the W3C `traceparent` header. The stream is a single
+    /// long-lived request, so the interceptor runs once per connection and not for every
+    /// batch or configuration change. Returning an error aborts the connection.
+    pub fn with_interceptor<I>(mut self, interceptor: I) -> Self
+    where
+        I: FnMut(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + Send + 'static,
+    {
+        self.interceptor = Some(Box::new(interceptor));
+        self
+    }
+
+    /// Set the HTTP/2 flow control window of the stream, in bytes.
+    ///
+    /// This is the amount of data the server can send before the client consumes it, so it
+    /// bounds how much response data is buffered while the stream is not polled. Once the
+    /// window is full, the server stops sending until the stream is polled again.
+    /// A smaller window reduces memory usage, at the cost of throughput on high-latency
+    /// connections. Defaults to the hyper default, 2 MiB at the time of writing. Keep it
+    /// larger than the largest expected batch.
+    pub fn with_initial_stream_window_size(mut self, bytes: u32) -> Self {
+        self.initial_stream_window_size = Some(bytes);
+        self
+    }
+
+    /// Set the HTTP/2 flow control window of the connection, in bytes.
+    ///
+    /// Same as [ClientBuilder::with_initial_stream_window_size], but shared by all streams
+    /// on the connection. Since the client opens a single stream, it only matters if it's
+    /// smaller than the stream window. Defaults to the hyper default, 5 MiB at the time of
+    /// writing.
+    pub fn with_initial_connection_window_size(mut self, bytes: u32) -> Self {
+        self.initial_connection_window_size = Some(bytes);
+        self
+    }
+
+    /// Fail fast with [ClientBuilderError::CircuitOpen] after repeated connection failures.
+    ///
+    /// Connection and stream errors returned by [ClientBuilder::connect] count as failures.
+    /// Pass a clone of the same breaker to the builder of every connection attempt, for
+    /// example in a reconnect loop, so that failures are counted across attempts.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Honor the delay sent by the server when it rate limits the connection.
+    ///
+    /// When the server rejects the stream with `ResourceExhausted` and a `retry-after`
+    /// delay (see [retry_after]), [ClientBuilder::connect] fails with
+    /// [ClientBuilderError::RateLimited] and, if a circuit breaker is set, opens it for
+    /// exactly that delay instead of counting a failure. Without a delay, failures go
+    /// through the circuit breaker cooldown as usual. Use [DataStreamError::retry_after] to
+    /// get the delay of errors received while streaming.
+    pub fn with_honor_retry_after(mut self, honor_retry_after: bool) -> Self {
+        self.honor_retry_after = honor_retry_after;
+        self
+    }
+
+    /// Choose what happens when the stream is polled slower than the server sends data.
+    ///
+    /// Defaults to [BackpressureStrategy::Block]. The other strategies read the server
+    /// responses in a background task: see [BackpressureStrategy::DropOldest] for the data
+    /// it loses.
+    pub fn with_backpressure(mut self, strategy: BackpressureStrategy) -> Self {
+        self.backpressure = strategy;
+        self
+    }
+
     /// Create and connect to the stream at the given url.
     ///
     /// If a configuration was provided, the client will immediately send it to the server upon
@@ -122,120 +577,865 @@ where
         self,
         url: Uri,
     ) -> Result<(DataStream<F, D>, DataStreamClient<F>), ClientBuilderError> {
-        let channel = Channel::builder(url).connect().await?;
+        let honor_retry_after = self.honor_retry_after;
+        let breaker = self.circuit_breaker.clone();
+        if let Some(breaker) = &breaker {
+            if !breaker.allow() {
+                return Err(ClientBuilderError::CircuitOpen);
+            }
+        }
+        let result = self.try_connect(url).await;
+        let retry_after = match &result {
+            Err(ClientBuilderError::StreamError(status)) if honor_retry_after => {
+                retry_after(status)
+            }
+            _ => None,
+        };
+        if let Some(breaker) = &breaker {
+            match (&result, retry_after) {
+                (_, Some(retry_after)) => breaker.open_for(retry_after),
+                (Ok(_), None) => breaker.record_success(),
+                (
+                    Err(ClientBuilderError::TonicError(_) | ClientBuilderError::StreamError(_)),
+                    None,
+                ) => breaker.record_failure(),
+                (Err(_), None) => {}
+            }
+        }
+        match retry_after {
+            None => result,
+            Some(retry_after) => Err(ClientBuilderError::RateLimited { retry_after }),
+        }
+    }
+
+    async fn try_connect(
+        self,
+        url: Uri,
+    ) -> Result<(DataStream<F, D>, DataStreamClient<F>), ClientBuilderError> {
+        let authorization: Option<MetadataValue<Ascii>> = match &self.token {
+            None => None,
+            Some(token) => Some(self.auth_scheme.authorization(token).parse()?),
+        };
 
+        let channel = Channel::builder(url)
+            .initial_stream_window_size(self.initial_stream_window_size)
+            .initial_connection_window_size(self.initial_connection_window_size)
+            .connect()
+            .await?;
+
+        let mut interceptor = self.interceptor;
         let mut default_client =
             StreamClient::with_interceptor(channel, move |mut req: tonic::Request<()>| {
-                if let Some(token) = self.token.clone() {
-                    let token: MetadataValue<_> = format!("Bearer {token}").parse().unwrap();
-                    req.metadata_mut().insert("authorization", token);
+                if let Some(authorization) = authorization.clone() {
+                    req.metadata_mut().insert("authorization", authorization);
+                }
+                match interceptor.as_mut() {
+                    None => Ok(req),
+                    Some(interceptor) => interceptor(req),
                 }
-                Ok(req)
             });
 
         let (configuration_tx, configuration_rx) = mpsc::channel(128);
         let (inner_tx, inner_rx) = mpsc::channel(128);
 
-        if let Some(configuration) = self.configuration {
-            configuration_tx.send(configuration).await.unwrap();
+        if let Some(mut configuration) = self.configuration {
+            if let Some(store) = &self.checkpoint_store {
+                let checkpoint = store
+                    .load()
+                    .map_err(ClientBuilderError::FailedToConfigureStream)?;
+                if let Some(cursor) = checkpoint {
+                    configuration.starting_cursor = Some(cursor);
+                }
+            }
+            configuration_tx
+                .send(configuration)
+                .await
+                .map_err(|_| ClientBuilderError::ConfigurationChannelClosed)?;
         }
 
-        let inner_stream = default_client
-            .stream_data(ReceiverStream::new(inner_rx))
-            .await?
-            .into_inner();
+        let mut request = tonic::Request::new(ReceiverStream::new(inner_rx));
+        if let Some(max_duration) = self.max_duration {
+            request.set_timeout(max_duration);
+        }
+        let inner_stream = default_client.stream_data(request).await?.into_inner();
+        let (inner_stream, backpressure) = backpressure::with_backpressure(
+            Box::pin(inner_stream),
+            self.backpressure,
+            BACKPRESSURE_BUFFER_SIZE,
+        );
 
-        let stream = DataStream {
-            stream_id: 0,
-            configuration_rx,
-            inner: inner_stream,
-            inner_tx,
-            _data: PhantomData::default(),
-        };
+        let mut stream = DataStream::new(configuration_rx, inner_stream, inner_tx);
+        stream.backpressure = backpressure;
+        if self.decode_pool {
+            stream.decode_pool = Some(Vec::new());
+        }
+        stream.batch_timeout = self.batch_timeout;
+        stream.fair_polling = self.fair_polling;
+        stream.adaptive_batching = self.adaptive_batching;
+        stream.strict_finality = self.strict_finality;
+        stream.raw_tap = self.raw_tap;
+        stream.checkpoint_store = self.checkpoint_store;
+        stream.metrics = self.metrics;
+        stream.max_batch_items = self.max_batch_items;
+        stream.max_in_flight_requests = self.max_in_flight_requests;
+        if let Some(max_duration) = self.max_duration {
+            stream.deadline = Some(Box::pin(tokio::time::sleep(max_duration)));
+        }
 
         Ok((stream, configuration_tx))
     }
 }
 
-impl<F, D> Stream for DataStream<F, D>
+/// A source polled by [DataStream].
+#[derive(Clone, Copy)]
+enum PollSource {
+    Configuration,
+    Data,
+}
+
+impl<F, D> DataStream<F, D>
 where
     F: Message + Default,
     D: Message + Default,
 {
-    type Item = Result<DataMessage<D>, Box<dyn std::error::Error>>;
+    fn new(
+        configuration_rx: Receiver<Configuration<F>>,
+        inner: ResponseStream,
+        inner_tx: Sender<StreamDataRequest>,
+    ) -> Self {
+        DataStream {
+            stream_id: 0,
+            configured_stream_id: 0,
+            data_stream_id: 0,
+            acknowledged_stream_id: 0,
+            max_in_flight_requests: None,
+            configuration_rx,
+            inner,
+            inner_tx,
+            last_end_cursor: None,
+            decode_pool: None,
+            batch_timeout: None,
+            batch_deadline: None,
+            fair_polling: false,
+            poll_data_first: false,
+            adaptive_batching: None,
+            catchup_batching: None,
+            tip_batches: 0,
+            last_request: None,
+            strict_finality: false,
+            raw_tap: None,
+            checkpoint_store: None,
+            pending_checkpoint: None,
+            metrics: None,
+            max_batch_items: None,
+            backpressure: None,
+            pending_split: None,
+            deadline: None,
+            deadline_reached: false,
+            gap_ranges: VecDeque::default(),
+            gap_resume: None,
+            gap_range_done: false,
+            status: StreamStatus::Connecting,
+            terminated: false,
+            _data: PhantomData::default(),
+        }
+    }
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match self.configuration_rx.poll_recv(cx) {
-            Poll::Ready(None) => return Poll::Ready(None),
-            Poll::Ready(Some(configuration)) => {
-                self.stream_id += 1;
-                let request = StreamDataRequest {
-                    stream_id: Some(self.stream_id),
-                    batch_size: Some(configuration.batch_size),
-                    starting_cursor: configuration.starting_cursor,
-                    finality: configuration.finality.map(|f| f as i32),
-                    filter: configuration.filter.encode_to_vec(),
-                };
+    /// Polls the next data or invalidate message for the current stream.
+    ///
+    /// Configuration changes, heartbeats and messages for previous streams are handled here.
+    ///
+    /// By default, the configuration channel is always polled before the data stream, so that
+    /// data for a stale configuration is never returned. With fair polling, the two sources
+    /// take turns at being polled first, so that neither can starve the other.
+    ///
+    /// Once the stream ends, it's never polled again.
+    fn poll_message(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<RawDataMessage, DataStreamError>>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
 
-                self.inner_tx.try_send(request)?;
-                cx.waker().wake_by_ref();
-                return Poll::Pending;
+        // the caller is done with the previous message.
+        if let Err(err) = self.save_pending_checkpoint() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        if let Err(err) = self.advance_gap_refill() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        if let Some(deadline) = self.deadline.as_mut() {
+            if deadline.as_mut().poll(cx).is_ready() {
+                self.end_at_deadline();
+                return Poll::Ready(None);
             }
-            Poll::Pending => {}
         }
 
-        match Pin::new(&mut self.inner).poll_next(cx) {
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(Box::new(e)))),
-            Poll::Ready(Some(Ok(response))) => {
-                if response.stream_id != self.stream_id {
-                    cx.waker().wake_by_ref();
-                    return Poll::Pending;
-                }
+        // messages that are not forwarded to the caller (heartbeats, old streams) are skipped by
+        // polling again, so that the task is only woken up by the channels when there is
+        // progress.
+        'poll: loop {
+            let data_first = self.fair_polling && self.poll_data_first;
+            if self.fair_polling {
+                self.poll_data_first = !self.poll_data_first;
+            }
 
-                match response.message {
-                    None => {
-                        cx.waker().wake_by_ref();
-                        Poll::Pending
+            let sources = if data_first {
+                [PollSource::Data, PollSource::Configuration]
+            } else {
+                [PollSource::Configuration, PollSource::Data]
+            };
+
+            for source in sources {
+                let poll = match source {
+                    PollSource::Configuration => self.poll_configuration(cx),
+                    PollSource::Data => match self.pending_split.take() {
+                        Some(message) => Poll::Ready(Some(Ok(Some(message)))),
+                        None => self.poll_response(cx),
+                    },
+                };
+                match poll {
+                    Poll::Pending => {}
+                    Poll::Ready(None) => {
+                        self.terminated = true;
+                        return Poll::Ready(None);
                     }
-                    Some(stream_data_response::Message::Data(data)) => {
-                        let batch = data
-                            .data
-                            .into_iter()
-                            .map(|b| D::decode(b.as_slice()))
-                            .filter_map(|b| b.ok())
-                            .collect::<Vec<D>>();
-                        let message = DataMessage::Data {
-                            cursor: data.cursor,
-                            end_cursor: data.end_cursor.unwrap_or_default(),
-                            finality: DataFinality::from_i32(data.finality).unwrap_or_default(),
-                            batch,
-                        };
-                        Poll::Ready(Some(Ok(message)))
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                    Poll::Ready(Some(Ok(None))) => continue 'poll,
+                    Poll::Ready(Some(Ok(Some(message)))) => {
+                        let message = self.split_message(message);
+                        return Poll::Ready(Some(Ok(self.mark_first_after_configure(message))));
                     }
-                    Some(stream_data_response::Message::Invalidate(invalidate)) => {
-                        let message = DataMessage::Invalidate {
-                            cursor: invalidate.cursor,
-                        };
-                        Poll::Ready(Some(Ok(message)))
+                }
+            }
+
+            if let Some(deadline) = self.batch_deadline.as_mut() {
+                if deadline.as_mut().poll(cx).is_ready() {
+                    self.reset_batch_deadline();
+                    self.status = StreamStatus::Stalled;
+                    return Poll::Ready(Some(Err(DataStreamError::BatchTimeout)));
+                }
+            }
+            return Poll::Pending;
+        }
+    }
+
+    /// Returns true if a new request can be sent without exceeding the requests in flight.
+    pub(crate) fn can_send_request(&self) -> bool {
+        match self.max_in_flight_requests {
+            None => true,
+            Some(max_requests) => self.stream_id - self.acknowledged_stream_id < max_requests,
+        }
+    }
+
+    /// Polls the configuration channel, sending a new request to the server on changes.
+    ///
+    /// Never returns a message, only whether there was progress.
+    fn poll_configuration(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Option<RawDataMessage>, DataStreamError>>> {
+        // the task is woken up by the response that acknowledges the requests in flight.
+        if !self.can_send_request() {
+            return Poll::Pending;
+        }
+
+        let mut configuration = match self.configuration_rx.poll_recv(cx) {
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some(configuration)) => configuration,
+        };
+        // only the latest configuration matters, skip the ones queued before it.
+        // if the channel is closed, the stream ends on the next poll.
+        while let Ok(next) = self.configuration_rx.try_recv() {
+            configuration = next;
+        }
+
+        if let Some(previous) = &self.last_end_cursor {
+            if !configuration.is_forward_only_from(previous) {
+                warn!(
+                    previous = previous.order_key,
+                    starting = ?configuration.starting_cursor,
+                    "new configuration rewinds the stream, data will be sent again"
+                );
+            }
+        }
+
+        self.stream_id += 1;
+        self.configured_stream_id = self.stream_id;
+        // the rest of a split batch belongs to the previous stream.
+        self.pending_split = None;
+        let batch_size = self.adaptive_batch_size(&configuration);
+        let request = StreamDataRequest {
+            stream_id: Some(self.stream_id),
+            batch_size: Some(batch_size),
+            starting_cursor: configuration.starting_cursor,
+            finality: configuration.finality.map(|f| f as i32),
+            filter: configuration.filter.encode_to_vec(),
+        };
+        let request = self.gap_refill_request(request);
+
+        if let Err(err) = self.inner_tx.try_send(request.clone()) {
+            return Poll::Ready(Some(Err(err.into())));
+        }
+        self.last_request = Some(request);
+        self.status = StreamStatus::Connecting;
+        self.reset_batch_deadline();
+        Poll::Ready(Some(Ok(None)))
+    }
+
+    /// Polls the server stream.
+    ///
+    /// Returns `None` inside `Ok` if the response was skipped.
+    fn poll_response(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Option<RawDataMessage>, DataStreamError>>> {
+        let response = match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(None) if self.take_overloaded() => {
+                return Poll::Ready(Some(Err(DataStreamError::Overloaded)))
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+            // the server enforces the deadline before the client-side timer fires.
+            Poll::Ready(Some(Err(e)))
+                if self.deadline.is_some() && e.code() == tonic::Code::DeadlineExceeded =>
+            {
+                self.end_at_deadline();
+                return Poll::Ready(None);
+            }
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(Some(Ok(response))) => response,
+        };
+
+        if let Some(tap) = &self.raw_tap {
+            // ignore errors, the tap must never interfere with the stream.
+            let _ = tap.try_send(response.clone());
+        }
+
+        // stream ids always increase, so a response acknowledges all previous requests.
+        if response.stream_id <= self.stream_id {
+            self.acknowledged_stream_id = self.acknowledged_stream_id.max(response.stream_id);
+        }
+
+        if response.stream_id != self.stream_id {
+            return Poll::Ready(Some(Ok(None)));
+        }
+
+        match response.message {
+            None => Poll::Ready(Some(Ok(None))),
+            Some(stream_data_response::Message::Data(data)) => {
+                // a default cursor points to the genesis block, never use it in
+                // place of the real cursor.
+                let end_cursor = match data.end_cursor {
+                    None => return Poll::Ready(Some(Err(DataStreamError::MissingEndCursor))),
+                    Some(end_cursor) => end_cursor,
+                };
+                self.last_end_cursor = Some(end_cursor.clone());
+                self.track_gap_refill(&end_cursor);
+                self.reset_batch_deadline();
+                self.status = StreamStatus::Streaming;
+                let finality = match DataFinality::from_i32(data.finality) {
+                    Some(finality) => finality,
+                    None if self.strict_finality => {
+                        let err = DataStreamError::UnknownFinality(data.finality);
+                        return Poll::Ready(Some(Err(err)));
                     }
-                    Some(stream_data_response::Message::Heartbeat(_)) => {
-                        debug!("received heartbeat");
-                        cx.waker().wake_by_ref();
-                        Poll::Pending
+                    None => {
+                        warn!(
+                            finality = data.finality,
+                            "server sent unknown data finality"
+                        );
+                        DataFinality::default()
                     }
+                };
+                let adapted = self.adapt_batch_size(data.cursor.as_ref(), &end_cursor, finality);
+                if let Err(err) = adapted {
+                    return Poll::Ready(Some(Err(err)));
                 }
+                let message = RawDataMessage::Data {
+                    cursor: data.cursor,
+                    end_cursor,
+                    finality,
+                    batch: data.data,
+                    is_partial: false,
+                    is_first_after_configure: false,
+                    received_at: SystemTime::now(),
+                };
+                Poll::Ready(Some(Ok(Some(message))))
+            }
+            Some(stream_data_response::Message::Invalidate(invalidate)) => {
+                self.status = StreamStatus::Streaming;
+                let message = RawDataMessage::Invalidate {
+                    cursor: invalidate.cursor,
+                };
+                Poll::Ready(Some(Ok(Some(message))))
+            }
+            Some(stream_data_response::Message::Heartbeat(_)) => {
+                debug!("received heartbeat");
+                // heartbeats don't reset the batch timeout, so they don't end a stall.
+                if matches!(
+                    self.status,
+                    StreamStatus::Connecting | StreamStatus::Reconnecting
+                ) {
+                    self.status = StreamStatus::Streaming;
+                }
+                Poll::Ready(Some(Ok(None)))
+            }
+        }
+    }
+
+    /// Flags the first batch returned since the caller changed the configuration.
+    ///
+    /// Internal restarts, for example to change the batch size or refill a gap, send a
+    /// new request with the same configuration and don't count.
+    fn mark_first_after_configure(&mut self, mut message: RawDataMessage) -> RawDataMessage {
+        if let RawDataMessage::Data {
+            is_first_after_configure,
+            ..
+        } = &mut message
+        {
+            // messages for previous streams are skipped, so data always belongs to the
+            // current stream.
+            *is_first_after_configure = self.data_stream_id < self.configured_stream_id;
+            self.data_stream_id = self.stream_id;
+        }
+        message
+    }
+
+    /// Returns the number of responses received by the stream but not returned yet.
+    ///
+    /// This counts the responses read from the server by the background task of a buffered
+    /// [BackpressureStrategy], up to [BACKPRESSURE_BUFFER_SIZE], and the rest of a batch
+    /// split by [ClientBuilder::with_max_batch_items]. With [BackpressureStrategy::Block]
+    /// the stream doesn't buffer responses, data waiting in the HTTP/2 transport is not
+    /// visible and it's bounded by the flow control window set with
+    /// [ClientBuilder::with_initial_stream_window_size].
+    pub fn pending_responses(&self) -> usize {
+        self.backpressure_buffered() + usize::from(self.pending_split.is_some())
+    }
+
+    /// Returns true if the stream ended because the deadline set with
+    /// [ClientBuilder::with_max_duration] was reached.
+    pub fn is_deadline_reached(&self) -> bool {
+        self.deadline_reached
+    }
+
+    /// Ends the stream because the deadline was reached.
+    fn end_at_deadline(&mut self) {
+        debug!("stream deadline reached");
+        self.deadline_reached = true;
+        self.terminated = true;
+        self.pending_split = None;
+    }
+
+    /// Restarts the batch timeout window, if the timeout is enabled.
+    fn reset_batch_deadline(&mut self) {
+        let timeout = match self.batch_timeout {
+            None => return,
+            Some(timeout) => timeout,
+        };
+        match self.batch_deadline.as_mut() {
+            Some(deadline) => deadline.as_mut().reset(Instant::now() + timeout),
+            None => self.batch_deadline = Some(Box::pin(tokio::time::sleep(timeout))),
+        }
+    }
+}
+
+impl<F, D> Stream for DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    type Item = Result<DataMessage<D>, DataStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.poll_message(cx) {
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(Some(Ok(RawDataMessage::Data {
+                cursor,
+                end_cursor,
+                finality,
+                batch,
+                is_partial,
+                is_first_after_configure,
+                received_at,
+            }))) => {
+                let timer = self.start_decode(&batch);
+                let batch = batch
+                    .into_iter()
+                    .map(|b| D::decode(b.as_slice()))
+                    .filter_map(|b| b.ok())
+                    .collect::<Vec<D>>();
+                self.finish_decode(timer, batch.len());
+                let message = DataMessage::Data {
+                    cursor,
+                    end_cursor,
+                    finality,
+                    batch,
+                    is_partial,
+                    is_first_after_configure,
+                    received_at,
+                };
+                Poll::Ready(Some(Ok(message)))
+            }
+            Poll::Ready(Some(Ok(RawDataMessage::Invalidate { cursor }))) => {
+                Poll::Ready(Some(Ok(DataMessage::Invalidate { cursor })))
             }
         }
     }
 }
 
+impl<F, D> FusedStream for DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{ClientBuilder, Configuration, Uri};
-    use apibara_core::starknet::v1alpha2::{Block, Filter, HeaderFilter};
+    use crate::{
+        test_utils::{data_response, new_test_stream, new_test_stream_with},
+        AuthScheme, ClientBuilder, Configuration, DataMessage, DataStream, DataStreamClient,
+        DataStreamError, Uri,
+    };
+    use apibara_core::{
+        node::v1alpha2::{
+            stream_data_response, Cursor, Data, DataFinality, Heartbeat, StreamDataResponse,
+        },
+        starknet::v1alpha2::{Block, BlockHeader, Filter, HeaderFilter, StateUpdate},
+    };
+    use futures::stream::FusedStream;
     use futures_util::{StreamExt, TryStreamExt};
+    use prost::Message;
+    use std::time::{Duration, SystemTime};
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::ReceiverStream;
+
+    fn heartbeat_response(stream_id: u64) -> StreamDataResponse {
+        StreamDataResponse {
+            stream_id,
+            message: Some(stream_data_response::Message::Heartbeat(Heartbeat {})),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_do_not_wake_task() {
+        let mut responses = vec![heartbeat_response(1); 100];
+        responses.push(data_response(0, 0, Vec::default()));
+        responses.push(data_response(1, 0, Vec::default()));
+        let (mut stream, client, _requests) = new_test_stream(responses);
+        client.send(Configuration::default()).await.unwrap();
+
+        let mut polls = 0;
+        let message = futures::future::poll_fn(|cx| {
+            polls += 1;
+            stream.poll_next_unpin(cx)
+        })
+        .await;
+        assert!(matches!(message, Some(Ok(DataMessage::Data { .. }))));
+        assert_eq!(polls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_is_first_after_configure() {
+        let responses = vec![
+            data_response(1, 0, Vec::default()),
+            data_response(1, 0, Vec::default()),
+            data_response(2, 0, Vec::default()),
+        ];
+        let (mut stream, client, _requests) = new_test_stream(responses);
+        let is_first = |message: Option<Result<DataMessage<Block>, DataStreamError>>| match message
+        {
+            Some(Ok(DataMessage::Data {
+                is_first_after_configure,
+                ..
+            })) => is_first_after_configure,
+            _ => panic!("expected data"),
+        };
+
+        client.send(Configuration::default()).await.unwrap();
+        assert!(is_first(stream.next().await));
+        assert!(!is_first(stream.next().await));
+        client.send(Configuration::default()).await.unwrap();
+        assert!(is_first(stream.next().await));
+    }
+
+    #[tokio::test]
+    async fn test_missing_end_cursor_is_an_error() {
+        let (mut stream, client, _requests) = new_test_stream(vec![StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Data(Data::default())),
+        }]);
+        client.send(Configuration::default()).await.unwrap();
+
+        let result = stream.next().await.unwrap();
+        assert!(matches!(result, Err(DataStreamError::MissingEndCursor)));
+    }
+
+    #[tokio::test]
+    async fn test_fair_polling_interleaves_configuration_and_data() {
+        // configurations are queued before data for the first configuration is polled.
+        // they're coalesced into the first request, so the data for it is not skipped
+        // even without fair polling.
+        let (mut stream, client, _requests) =
+            new_test_stream(vec![data_response(1, 0, Vec::default())]);
+        for _ in 0..3 {
+            client.send(Configuration::default()).await.unwrap();
+        }
+        let message = stream.next().await;
+        assert!(matches!(message, Some(Ok(DataMessage::Data { .. }))));
+
+        let (mut stream, client, _requests) =
+            new_test_stream(vec![data_response(1, 0, Vec::default())]);
+        stream.fair_polling = true;
+        for _ in 0..3 {
+            client.send(Configuration::default()).await.unwrap();
+        }
+        let message = stream.next().await;
+        assert!(matches!(message, Some(Ok(DataMessage::Data { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_requests() {
+        let (response_tx, response_rx) = mpsc::channel(128);
+        let inner = ReceiverStream::new(response_rx);
+        let (mut stream, configuration_tx, mut requests) = new_test_stream_with(Box::pin(inner));
+        stream.max_in_flight_requests = Some(1);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        assert!(futures::poll!(stream.next()).is_pending());
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        assert!(futures::poll!(stream.next()).is_pending());
+
+        // the second configuration is only sent after the first request is acknowledged.
+        assert_eq!(requests.try_recv().unwrap().stream_id, Some(1));
+        assert!(requests.try_recv().is_err());
+        response_tx
+            .send(Ok(data_response(1, 0, Vec::default())))
+            .await
+            .unwrap();
+        let message = stream.next().await;
+        assert!(matches!(message, Some(Ok(DataMessage::Data { .. }))));
+        assert!(futures::poll!(stream.next()).is_pending());
+        assert_eq!(requests.try_recv().unwrap().stream_id, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_batch_timeout() {
+        let inner = futures::stream::pending();
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(Box::pin(inner));
+        stream.batch_timeout = Some(Duration::from_millis(10));
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let result = stream.next().await.unwrap();
+        assert!(matches!(result, Err(DataStreamError::BatchTimeout)));
+        // a new window starts after the timeout.
+        let result = stream.next().await.unwrap();
+        assert!(matches!(result, Err(DataStreamError::BatchTimeout)));
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let inner = futures::stream::pending();
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(Box::pin(inner));
+        stream.deadline = Some(Box::pin(tokio::time::sleep(Duration::from_millis(10))));
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        assert!(!stream.is_deadline_reached());
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_deadline_reached());
+        assert!(stream.is_terminated());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded_by_server() {
+        let inner = futures::stream::iter(vec![Err(tonic::Status::deadline_exceeded("late"))]);
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(Box::pin(inner));
+        stream.deadline = Some(Box::pin(tokio::time::sleep(Duration::from_secs(60))));
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_deadline_reached());
+    }
+
+    #[tokio::test]
+    async fn test_strict_finality() {
+        let response = StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Data(Data {
+                end_cursor: Some(Cursor::default()),
+                finality: 42,
+                ..Data::default()
+            })),
+        };
+        let (mut stream, client, _requests) = new_test_stream(vec![response.clone()]);
+        client.send(Configuration::default()).await.unwrap();
+        match stream.try_next().await.unwrap().unwrap() {
+            DataMessage::Data { finality, .. } => {
+                assert_eq!(finality, DataFinality::DataStatusUnknown)
+            }
+            DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+        }
+
+        let (mut stream, client, _requests) = new_test_stream(vec![response]);
+        stream.strict_finality = true;
+        client.send(Configuration::default()).await.unwrap();
+        let err = stream.try_next().await.unwrap_err();
+        assert!(matches!(err, DataStreamError::UnknownFinality(42)));
+    }
+
+    #[tokio::test]
+    async fn test_raw_tap() {
+        let heartbeat = StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Heartbeat(Heartbeat {})),
+        };
+        let old_stream = StreamDataResponse {
+            stream_id: 0,
+            message: None,
+        };
+        let (mut stream, client, _requests) = new_test_stream(vec![heartbeat.clone(), old_stream]);
+        let (tap_tx, mut tap_rx) = mpsc::channel(1);
+        stream.raw_tap = Some(tap_tx);
+        client.send(Configuration::default()).await.unwrap();
+        assert!(stream.next().await.is_none());
+
+        // the channel is full after the first response, the second is dropped.
+        assert_eq!(tap_rx.recv().await.unwrap(), heartbeat);
+        assert!(tap_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_state_updates_only_batch() {
+        let block = Block {
+            header: Some(BlockHeader::default()),
+            state_update: Some(StateUpdate::default()),
+            ..Block::default()
+        };
+        let response = data_response(1, 0, vec![block.encode_to_vec()]);
+        let (mut stream, client, _requests) = new_test_stream(vec![response]);
+        client
+            .send(Configuration::default().state_updates_only())
+            .await
+            .unwrap();
+        match stream.next().await {
+            Some(Ok(DataMessage::Data { batch, .. })) => assert_eq!(batch, vec![block]),
+            _ => panic!("expected data"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queued_configurations_are_coalesced() {
+        let (mut stream, client, mut requests) =
+            new_test_stream(vec![data_response(1, 0, Vec::default())]);
+        for batch_size in 1..=3 {
+            client
+                .send(Configuration::default().with_batch_size(batch_size))
+                .await
+                .unwrap();
+        }
+        assert!(stream.next().await.unwrap().is_ok());
+
+        let request = requests.try_recv().unwrap();
+        assert_eq!(request.stream_id, Some(1));
+        assert_eq!(request.batch_size, Some(3));
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_stays_terminated() {
+        let (mut stream, client, _requests) = new_test_stream(Vec::default());
+        client.send(Configuration::default()).await.unwrap();
+        assert!(!stream.is_terminated());
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_terminated());
+        // new configurations are not sent once the stream ended.
+        client.send(Configuration::default()).await.unwrap();
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_terminated());
+    }
+
+    #[tokio::test]
+    async fn test_configuration_channel_closed() {
+        let (stream, client, _requests) = new_test_stream(Vec::default());
+        drop(stream);
+        let err: DataStreamError = client
+            .send(Configuration::<Filter>::default())
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, DataStreamError::ConfigurationChannelClosed));
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let cursor = |order_key| Cursor {
+            order_key,
+            unique_key: Vec::default(),
+        };
+        let data = DataMessage::<Block>::Data {
+            cursor: Some(cursor(1)),
+            end_cursor: cursor(2),
+            finality: DataFinality::DataStatusAccepted,
+            batch: Vec::default(),
+            is_partial: false,
+            is_first_after_configure: true,
+            received_at: SystemTime::now(),
+        };
+        assert_eq!(data.next_cursor(), Some(cursor(2)));
+        assert_eq!(data.block_range(), Some((2, 2)));
+        let invalidate = DataMessage::<Block>::Invalidate {
+            cursor: Some(cursor(3)),
+        };
+        assert_eq!(invalidate.next_cursor(), Some(cursor(3)));
+        assert_eq!(invalidate.block_range(), None);
+    }
+
+    #[test]
+    fn test_auth_scheme() {
+        assert_eq!(AuthScheme::default().authorization("abc"), "Bearer abc");
+        assert_eq!(AuthScheme::Raw.authorization("abc"), "abc");
+        assert_eq!(
+            AuthScheme::Custom("Token".to_string()).authorization("abc"),
+            "Token abc"
+        );
+    }
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn test_data_stream_is_send() {
+        assert_send::<DataStream<Filter, Block>>();
+        assert_send::<DataStreamError>();
+        assert_sync::<DataStreamError>();
+        assert_send::<DataStreamClient<Filter>>();
+        assert_sync::<DataStreamClient<Filter>>();
+    }
 
     #[tokio::test]
     async fn test_apibara_high_level_api() -> Result<(), Box<dyn std::error::Error>> {
@@ -0,0 +1,104 @@
+//! Measure the stream performance.
+
+use std::time::{Duration, Instant};
+
+use prost::Message;
+
+use crate::DataStream;
+
+/// Receives measurements of the stream.
+///
+/// Enable it with [crate::ClientBuilder::with_metrics]. Nothing is measured without it.
+/// Callbacks run in the task polling the stream, so keep them cheap, for example by
+/// updating counters or histograms.
+pub trait StreamMetrics: Send + Sync {
+    /// Called after decoding the items of a batch, with the time it took, the size of the
+    /// encoded items and the number of items decoded.
+    ///
+    /// Compare it with the time between batches to tell whether the stream is limited by
+    /// the network or by decoding. Items that fail to decode count in `bytes`, but not in
+    /// `items`.
+    fn on_decode(&self, duration: Duration, bytes: usize, items: usize);
+}
+
+/// Measures the decoding of a batch, see [DataStream::start_decode].
+pub(crate) struct DecodeTimer {
+    started_at: Instant,
+    bytes: usize,
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Starts measuring the decoding of `batch`, if metrics are enabled.
+    pub(crate) fn start_decode(&self, batch: &[Vec<u8>]) -> Option<DecodeTimer> {
+        self.metrics.as_ref()?;
+        Some(DecodeTimer {
+            started_at: Instant::now(),
+            bytes: batch.iter().map(Vec::len).sum(),
+        })
+    }
+
+    /// Reports the decoding measured by `timer`, which decoded `items` items.
+    pub(crate) fn finish_decode(&self, timer: Option<DecodeTimer>, items: usize) {
+        if let (Some(metrics), Some(timer)) = (&self.metrics, timer) {
+            metrics.on_decode(timer.started_at.elapsed(), timer.bytes, items);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use apibara_core::starknet::v1alpha2::{Block, BlockHeader};
+    use futures::StreamExt;
+    use prost::Message;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration,
+    };
+
+    use super::StreamMetrics;
+
+    #[derive(Default)]
+    struct TestMetrics {
+        decoded: Mutex<Vec<(usize, usize)>>,
+    }
+
+    impl StreamMetrics for TestMetrics {
+        fn on_decode(&self, _duration: Duration, bytes: usize, items: usize) {
+            self.decoded.lock().unwrap().push((bytes, items));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_decode() {
+        let block = Block {
+            header: Some(BlockHeader {
+                block_number: 1,
+                ..BlockHeader::default()
+            }),
+            ..Block::default()
+        }
+        .encode_to_vec();
+        let response = data_response(1, 0, vec![block.clone(), vec![0xff, 0xff]]);
+        let (mut stream, configuration_tx, _requests) = new_test_stream(vec![response]);
+        let metrics = Arc::new(TestMetrics::default());
+        stream.metrics = Some(metrics.clone());
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        assert!(stream.next().await.unwrap().is_ok());
+        let decoded = metrics.decoded.lock().unwrap().clone();
+        assert_eq!(decoded, vec![(block.len() + 2, 1)]);
+    }
+}
@@ -0,0 +1,159 @@
+//! Decode data into a pool of reused items.
+
+use std::time::SystemTime;
+
+use apibara_core::node::v1alpha2::{Cursor, DataFinality};
+use futures::future::poll_fn;
+use prost::Message;
+
+use crate::{DataStream, DataStreamError, RawDataMessage};
+
+/// A message generated by [DataStream::next_with].
+///
+/// Same as [crate::DataMessage], but the batch borrows the items from the stream decode pool.
+#[derive(Debug)]
+pub enum PooledDataMessage<'a, D: Message + Default> {
+    /// A new batch of data.
+    Data {
+        /// The batch starting cursor.
+        cursor: Option<Cursor>,
+        /// The batch end cursor.
+        ///
+        /// Use this value as the start cursor to receive data for the next batch.
+        end_cursor: Cursor,
+        /// The data finality.
+        finality: DataFinality,
+        /// The batch of data.
+        batch: &'a [D],
+        /// True if this is not the last part of a split batch, see [crate::DataMessage].
+        is_partial: bool,
+        /// True if this is the first batch since the stream was configured, see
+        /// [crate::DataMessage].
+        is_first_after_configure: bool,
+        /// When the batch was received by the client.
+        received_at: SystemTime,
+    },
+    /// Invalidate all data received after the given cursor.
+    Invalidate {
+        /// The cursor.
+        cursor: Option<Cursor>,
+    },
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Receives the next message and passes it to `handler`, returning the handler result.
+    ///
+    /// If the decode pool is enabled with [crate::ClientBuilder::with_decode_pool], the items
+    /// in the batch are cleared and reused to decode the next batch. This means the batch
+    /// is only valid inside `handler`: process the items (or clone them) before returning.
+    /// Without decode pool, items are allocated for each batch.
+    ///
+    /// Returns `None` when the stream ends.
+    pub async fn next_with<H, T>(&mut self, handler: H) -> Option<Result<T, DataStreamError>>
+    where
+        H: FnOnce(PooledDataMessage<'_, D>) -> T,
+    {
+        let message = match poll_fn(|cx| self.poll_message(cx)).await? {
+            Ok(message) => message,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let result = match message {
+            RawDataMessage::Data {
+                cursor,
+                end_cursor,
+                finality,
+                batch,
+                is_partial,
+                is_first_after_configure,
+                received_at,
+            } => {
+                let mut fresh = Vec::new();
+                let timer = self.start_decode(&batch);
+                let size = {
+                    let pool = self.decode_pool.as_mut().unwrap_or(&mut fresh);
+                    decode_into_pool(pool, &batch)
+                };
+                self.finish_decode(timer, size);
+                let pool = self.decode_pool.as_mut().unwrap_or(&mut fresh);
+                handler(PooledDataMessage::Data {
+                    cursor,
+                    end_cursor,
+                    finality,
+                    batch: &pool[..size],
+                    is_partial,
+                    is_first_after_configure,
+                    received_at,
+                })
+            }
+            RawDataMessage::Invalidate { cursor } => {
+                handler(PooledDataMessage::Invalidate { cursor })
+            }
+        };
+
+        Some(Ok(result))
+    }
+}
+
+/// Decodes `data` into the items in `pool`, growing it if needed.
+///
+/// Items that fail to decode are skipped. Returns the number of decoded items.
+fn decode_into_pool<D: Message + Default>(pool: &mut Vec<D>, data: &[Vec<u8>]) -> usize {
+    let mut size = 0;
+    for bytes in data {
+        if size == pool.len() {
+            pool.push(D::default());
+        }
+        let item = &mut pool[size];
+        item.clear();
+        if item.merge(bytes.as_slice()).is_ok() {
+            size += 1;
+        }
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::{Block, BlockHeader};
+    use prost::Message;
+
+    use super::decode_into_pool;
+
+    fn encoded_block(number: u64) -> Vec<u8> {
+        Block {
+            header: Some(BlockHeader {
+                block_number: number,
+                ..BlockHeader::default()
+            }),
+            ..Block::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_decode_into_pool_reuses_items() {
+        let mut pool = Vec::<Block>::new();
+        let size = decode_into_pool(&mut pool, &[encoded_block(1), encoded_block(2)]);
+        assert_eq!(size, 2);
+        assert_eq!(pool.len(), 2);
+
+        let size = decode_into_pool(&mut pool, &[encoded_block(3)]);
+        assert_eq!(size, 1);
+        // pool is not shrunk, and items are cleared before being reused.
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool[0].header.as_ref().unwrap().block_number, 3);
+    }
+
+    #[test]
+    fn test_decode_into_pool_skips_invalid_items() {
+        let mut pool = Vec::<Block>::new();
+        let size = decode_into_pool(&mut pool, &[vec![0xff, 0xff], encoded_block(1)]);
+        assert_eq!(size, 1);
+        assert_eq!(pool[0].header.as_ref().unwrap().block_number, 1);
+    }
+}
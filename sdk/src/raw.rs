@@ -0,0 +1,119 @@
+//! Stream undecoded data.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+
+use apibara_core::node::v1alpha2::{Cursor, DataFinality};
+use futures::Stream;
+use prost::Message;
+
+use crate::{DataStream, DataStreamError};
+
+/// A message generated by [RawDataStream].
+///
+/// Same as [crate::DataMessage], but the batch contains the protobuf-encoded items exactly
+/// as sent by the server.
+#[derive(Debug)]
+pub enum RawDataMessage {
+    /// A new batch of data.
+    Data {
+        /// The batch starting cursor.
+        cursor: Option<Cursor>,
+        /// The batch end cursor.
+        ///
+        /// Use this value as the start cursor to receive data for the next batch.
+        end_cursor: Cursor,
+        /// The data finality.
+        finality: DataFinality,
+        /// The batch of encoded data.
+        batch: Vec<Vec<u8>>,
+        /// True if this is not the last part of a split batch, see [crate::DataMessage].
+        is_partial: bool,
+        /// True if this is the first batch since the stream was configured, see
+        /// [crate::DataMessage].
+        is_first_after_configure: bool,
+        /// When the batch was received by the client.
+        received_at: SystemTime,
+    },
+    /// Invalidate all data received after the given cursor.
+    Invalidate {
+        /// The cursor.
+        cursor: Option<Cursor>,
+    },
+}
+
+/// A [DataStream] that doesn't decode data.
+///
+/// Use it to forward data to other clients without decoding and encoding it again.
+/// Created with [DataStream::into_raw].
+#[derive(Debug)]
+pub struct RawDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    inner: DataStream<F, D>,
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Stream the data without decoding it.
+    pub fn into_raw(self) -> RawDataStream<F, D> {
+        RawDataStream { inner: self }
+    }
+}
+
+impl<F, D> Stream for RawDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    type Item = Result<RawDataMessage, DataStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_message(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration,
+    };
+
+    use super::RawDataMessage;
+
+    #[tokio::test]
+    async fn test_raw_data_is_not_decoded() {
+        // not a valid block, would be dropped by the decoding stream.
+        let data = vec![vec![0xff, 0xff], vec![]];
+        let (stream, configuration_tx, _requests) =
+            new_test_stream(vec![data_response(1, 0, data.clone())]);
+        let mut stream = stream.into_raw();
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        match stream.next().await.unwrap().unwrap() {
+            RawDataMessage::Data {
+                batch,
+                is_first_after_configure,
+                ..
+            } => {
+                assert_eq!(batch, data);
+                assert!(is_first_after_configure);
+            }
+            RawDataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Emit data in fixed-size chunks.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use apibara_core::node::v1alpha2::Cursor;
+use futures::Stream;
+use pin_project::pin_project;
+use prost::Message;
+
+use crate::{DataMessage, DataStream, DataStreamError};
+
+/// A message generated by [RechunkedDataStream].
+#[derive(Debug)]
+pub enum ChunkMessage<D: Message + Default> {
+    /// A chunk of data.
+    Chunk {
+        /// The chunk items.
+        ///
+        /// All chunks have the requested size, except the last one before the stream ends.
+        batch: Vec<D>,
+        /// The cursor to use to resume the stream after processing this chunk.
+        ///
+        /// Chunks don't follow the server batches, and the stream can only resume at the end of
+        /// a batch. This is the end cursor of the last batch whose items were all sent in
+        /// this or previous chunks. If the chunk ends in the middle of a batch, items of that
+        /// batch in this chunk will be received again after resuming from this cursor.
+        ///
+        /// `None` if the chunk doesn't complete any batch, in which case the previous
+        /// checkpoint is still the one to use.
+        checkpoint: Option<Cursor>,
+    },
+    /// Invalidate all data received after the given cursor.
+    ///
+    /// Items of invalidated batches that were not sent yet are discarded.
+    Invalidate {
+        /// The cursor.
+        cursor: Option<Cursor>,
+    },
+}
+
+/// A [DataStream] that emits data in fixed-size chunks.
+///
+/// Created with [DataStream::rechunk].
+#[pin_project]
+#[derive(Debug)]
+pub struct RechunkedDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    #[pin]
+    inner: DataStream<F, D>,
+    size: usize,
+    buffer: VecDeque<D>,
+    /// Batch end cursors, together with the buffer length after the batch's items.
+    boundaries: VecDeque<(Cursor, usize)>,
+    inner_done: bool,
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Regroup the items received into chunks of `size` items, regardless of the batches sent
+    /// by the server.
+    ///
+    /// Use [ChunkMessage::Chunk] `checkpoint` to resume the stream, since chunk boundaries
+    /// don't match batch boundaries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn rechunk(self, size: usize) -> RechunkedDataStream<F, D> {
+        assert!(size > 0, "chunk size must be positive");
+        RechunkedDataStream {
+            inner: self,
+            size,
+            buffer: VecDeque::default(),
+            boundaries: VecDeque::default(),
+            inner_done: false,
+        }
+    }
+}
+
+impl<F, D> Stream for RechunkedDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    type Item = Result<ChunkMessage<D>, DataStreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if this.buffer.len() >= *this.size || (*this.inner_done && !this.buffer.is_empty()) {
+                let size = usize::min(*this.size, this.buffer.len());
+                let batch = this.buffer.drain(..size).collect();
+                let mut checkpoint = None;
+                while let Some((_, end)) = this.boundaries.front() {
+                    if *end > size {
+                        break;
+                    }
+                    checkpoint = this.boundaries.pop_front().map(|(cursor, _)| cursor);
+                }
+                for (_, end) in this.boundaries.iter_mut() {
+                    *end -= size;
+                }
+                let message = ChunkMessage::Chunk { batch, checkpoint };
+                return Poll::Ready(Some(Ok(message)));
+            }
+
+            if *this.inner_done {
+                return Poll::Ready(None);
+            }
+
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => *this.inner_done = true,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(DataMessage::Data {
+                    end_cursor,
+                    batch,
+                    is_partial,
+                    ..
+                }))) => {
+                    this.buffer.extend(batch);
+                    // only the last part of a split batch ends at its end cursor.
+                    if !is_partial {
+                        this.boundaries.push_back((end_cursor, this.buffer.len()));
+                    }
+                }
+                Poll::Ready(Some(Ok(DataMessage::Invalidate { cursor }))) => {
+                    // keep the items of batches up to the invalidated cursor.
+                    let order_key = cursor.as_ref().map(|c| c.order_key);
+                    while let Some((end_cursor, _)) = this.boundaries.back() {
+                        if Some(end_cursor.order_key) <= order_key {
+                            break;
+                        }
+                        this.boundaries.pop_back();
+                    }
+                    let keep = this.boundaries.back().map(|(_, end)| *end).unwrap_or(0);
+                    this.buffer.truncate(keep);
+                    return Poll::Ready(Some(Ok(ChunkMessage::Invalidate { cursor })));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::{
+        node::v1alpha2::{stream_data_response, Invalidate, StreamDataResponse},
+        starknet::v1alpha2::{Block, BlockHeader},
+    };
+    use futures_util::TryStreamExt;
+    use prost::Message;
+
+    use crate::{
+        test_utils::{cursor, data_response, new_test_stream},
+        Configuration,
+    };
+
+    use super::ChunkMessage;
+
+    /// Returns `count` encoded blocks, numbered from `first`.
+    fn blocks(first: u64, count: u64) -> Vec<Vec<u8>> {
+        (first..first + count)
+            .map(|block_number| {
+                Block {
+                    header: Some(BlockHeader {
+                        block_number,
+                        ..BlockHeader::default()
+                    }),
+                    ..Block::default()
+                }
+                .encode_to_vec()
+            })
+            .collect()
+    }
+
+    async fn play(responses: Vec<StreamDataResponse>, size: usize) -> Vec<(Vec<u64>, Option<u64>)> {
+        let (stream, configuration_tx, _requests) = new_test_stream(responses);
+        let mut stream = stream.rechunk(size);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let mut chunks = Vec::default();
+        while let Some(message) = stream.try_next().await.unwrap() {
+            match message {
+                ChunkMessage::Chunk { batch, checkpoint } => {
+                    let numbers = batch
+                        .iter()
+                        .map(|b| b.header.as_ref().unwrap().block_number)
+                        .collect();
+                    chunks.push((numbers, checkpoint.map(|c| c.order_key)));
+                }
+                ChunkMessage::Invalidate { .. } => {}
+            }
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn test_rechunk_across_batches() {
+        let responses = vec![
+            data_response(1, 1, blocks(0, 3)),
+            data_response(1, 2, blocks(3, 2)),
+            data_response(1, 3, blocks(5, 4)),
+        ];
+        let chunks = play(responses, 4).await;
+        assert_eq!(
+            chunks,
+            vec![
+                (vec![0, 1, 2, 3], Some(1)),
+                (vec![4, 5, 6, 7], Some(2)),
+                (vec![8], Some(3)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rechunk_discards_invalidated_items() {
+        let invalidate = StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Invalidate(Invalidate {
+                cursor: Some(cursor(1)),
+            })),
+        };
+        let responses = vec![
+            data_response(1, 1, blocks(0, 2)),
+            data_response(1, 2, blocks(2, 2)),
+            invalidate,
+            data_response(1, 2, blocks(10, 2)),
+        ];
+        let chunks = play(responses, 5).await;
+        assert_eq!(chunks, vec![(vec![0, 1, 10, 11], Some(2))]);
+    }
+}
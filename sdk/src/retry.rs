@@ -0,0 +1,56 @@
+//! Honor the server rate limits.
+
+use std::time::Duration;
+
+use crate::DataStreamError;
+
+/// Metadata key of the rate limit delay.
+const RETRY_AFTER: &str = "retry-after";
+
+/// Returns how long the server asked to wait before retrying, if it rate limited the request.
+///
+/// Only `ResourceExhausted` statuses are considered, with the delay in the `retry-after`
+/// metadata, as a whole number of seconds. Other formats are ignored.
+pub fn retry_after(status: &tonic::Status) -> Option<Duration> {
+    if status.code() != tonic::Code::ResourceExhausted {
+        return None;
+    }
+    let value = status.metadata().get(RETRY_AFTER)?.to_str().ok()?;
+    let seconds = value.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+impl DataStreamError {
+    /// Returns how long to wait before reconnecting, if the server rate limited the stream.
+    ///
+    /// See [retry_after].
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            DataStreamError::StreamError(status) => retry_after(status),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::retry_after;
+
+    #[test]
+    fn test_retry_after() {
+        let mut status = tonic::Status::resource_exhausted("rate limited");
+        assert_eq!(retry_after(&status), None);
+        status
+            .metadata_mut()
+            .insert("retry-after", "30".parse().unwrap());
+        assert_eq!(retry_after(&status), Some(Duration::from_secs(30)));
+
+        let mut status = tonic::Status::unavailable("unavailable");
+        status
+            .metadata_mut()
+            .insert("retry-after", "30".parse().unwrap());
+        assert_eq!(retry_after(&status), None);
+    }
+}
@@ -0,0 +1,235 @@
+//! Split large batches.
+//!
+//! The server decides how many items are in a batch, a batch covering many blocks can be very
+//! large. With [crate::ClientBuilder::with_max_batch_items], batches with more items are split
+//! and returned as multiple messages.
+//!
+//! The server only sends cursors at batch boundaries, so all parts of a batch have the same
+//! `cursor` and `end_cursor`, the end cursor of the full batch. All parts except the last one
+//! are marked as partial: the end cursor of a partial batch is not safe to resume the stream
+//! from, since the items in the following parts would be skipped. Checkpoints are only stored
+//! after the last part is handled, and [crate::DataMessage::next_cursor] returns the batch
+//! starting cursor for partial batches, so that the full batch is sent again on restart.
+//!
+//! The same applies to consumers: a batch is the unit of checkpointing, if handling fails
+//! half-way through a batch, the whole batch is sent again after a restart. Consumers that
+//! handle items idempotently can use [crate::DataMessage::split_at] to set aside the items
+//! already handled, and skip them when the batch is sent again.
+
+use prost::Message;
+
+use crate::{DataMessage, DataStream, RawDataMessage};
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Returns the first part of `message`, storing the rest to be returned next.
+    ///
+    /// Also marks the batch to be checkpointed once the last part is handled.
+    pub(crate) fn split_message(&mut self, message: RawDataMessage) -> RawDataMessage {
+        let message = match (message, self.max_batch_items) {
+            (
+                RawDataMessage::Data {
+                    cursor,
+                    end_cursor,
+                    finality,
+                    mut batch,
+                    is_first_after_configure,
+                    received_at,
+                    ..
+                },
+                Some(max_items),
+            ) if batch.len() > max_items => {
+                let rest = batch.split_off(max_items);
+                self.pending_split = Some(RawDataMessage::Data {
+                    cursor: cursor.clone(),
+                    end_cursor: end_cursor.clone(),
+                    finality,
+                    batch: rest,
+                    is_partial: false,
+                    is_first_after_configure: false,
+                    received_at,
+                });
+                RawDataMessage::Data {
+                    cursor,
+                    end_cursor,
+                    finality,
+                    batch,
+                    is_partial: true,
+                    is_first_after_configure,
+                    received_at,
+                }
+            }
+            (message, _) => message,
+        };
+
+        if let RawDataMessage::Data {
+            end_cursor,
+            finality,
+            is_partial: false,
+            ..
+        } = &message
+        {
+            self.track_checkpoint(end_cursor, *finality);
+        }
+        message
+    }
+}
+
+impl<D: Message + Default> DataMessage<D> {
+    /// Splits a data message in two at `index`.
+    ///
+    /// The first part contains the items before `index` and is marked as partial, the
+    /// second part contains the rest and is `None` if there are no items left. Both parts
+    /// keep the cursors of the original batch, since the server only sends cursors at
+    /// batch boundaries. Invalidate messages are returned unchanged.
+    ///
+    /// Use it after handling fails at item `index`: record the number of items of the
+    /// first part together with [DataMessage::next_cursor], and retry the second part.
+    pub fn split_at(self, index: usize) -> (Self, Option<Self>) {
+        match self {
+            DataMessage::Data {
+                cursor,
+                end_cursor,
+                finality,
+                mut batch,
+                is_partial,
+                is_first_after_configure,
+                received_at,
+            } if index < batch.len() => {
+                let rest = batch.split_off(index);
+                let first = DataMessage::Data {
+                    cursor: cursor.clone(),
+                    end_cursor: end_cursor.clone(),
+                    finality,
+                    batch,
+                    is_partial: true,
+                    is_first_after_configure,
+                    received_at,
+                };
+                let second = DataMessage::Data {
+                    cursor,
+                    end_cursor,
+                    finality,
+                    batch: rest,
+                    is_partial,
+                    is_first_after_configure: false,
+                    received_at,
+                };
+                (first, Some(second))
+            }
+            message => (message, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use apibara_core::{
+        node::v1alpha2::{stream_data_response, Data, DataFinality, StreamDataResponse},
+        starknet::v1alpha2::{Block, BlockHeader},
+    };
+    use futures_util::TryStreamExt;
+    use prost::Message;
+
+    use crate::{
+        test_utils::{cursor, new_test_stream},
+        Configuration, DataMessage,
+    };
+
+    fn block(number: u64) -> Vec<u8> {
+        Block {
+            header: Some(BlockHeader {
+                block_number: number,
+                ..BlockHeader::default()
+            }),
+            ..Block::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_split_large_batch() {
+        let responses = vec![StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Data(Data {
+                cursor: Some(cursor(0)),
+                end_cursor: Some(cursor(5)),
+                data: (1..=5).map(block).collect(),
+                ..Data::default()
+            })),
+        }];
+        let (mut stream, configuration_tx, _requests) = new_test_stream(responses);
+        stream.max_batch_items = Some(2);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let mut parts = Vec::default();
+        while let Some(message) = stream.try_next().await.unwrap() {
+            let next_cursor = message.next_cursor();
+            match message {
+                DataMessage::Data {
+                    end_cursor,
+                    batch,
+                    is_partial,
+                    ..
+                } => {
+                    assert_eq!(end_cursor, cursor(5));
+                    let numbers = batch
+                        .iter()
+                        .map(|b| b.header.as_ref().unwrap().block_number)
+                        .collect::<Vec<_>>();
+                    parts.push((numbers, is_partial, next_cursor));
+                }
+                DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+            }
+        }
+
+        assert_eq!(
+            parts,
+            vec![
+                (vec![1, 2], true, Some(cursor(0))),
+                (vec![3, 4], true, Some(cursor(0))),
+                (vec![5], false, Some(cursor(5))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_at() {
+        let message = DataMessage::<Block>::Data {
+            cursor: Some(cursor(0)),
+            end_cursor: cursor(3),
+            finality: DataFinality::DataStatusAccepted,
+            batch: vec![Block::default(); 3],
+            is_partial: false,
+            is_first_after_configure: true,
+            received_at: SystemTime::now(),
+        };
+
+        let (first, second) = message.split_at(1);
+        assert_eq!(first.next_cursor(), Some(cursor(0)));
+        let second = second.unwrap();
+        assert_eq!(second.next_cursor(), Some(cursor(3)));
+        match second {
+            DataMessage::Data {
+                batch,
+                is_first_after_configure,
+                ..
+            } => {
+                assert_eq!(batch.len(), 2);
+                assert!(!is_first_after_configure);
+            }
+            DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+        }
+
+        let (_, rest) = first.split_at(1);
+        assert!(rest.is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Report the stream health.
+
+use prost::Message;
+
+use crate::DataStream;
+
+/// The health of a [DataStream], returned by [DataStream::status].
+///
+/// The stream starts `Connecting`, and moves to `Streaming` on the first response for the
+/// current configuration. Every configuration change moves it back to `Connecting`. When
+/// the stream restarts itself with the same configuration, to switch batch size (see
+/// [crate::ClientBuilder::with_adaptive_batching]) or to refill the next gap (see
+/// [DataStream::with_gap_refill]), it's `Reconnecting` until the first response for the
+/// new request, then `Streaming` again. A batch timeout (see
+/// [crate::ClientBuilder::with_batch_timeout]) moves it to `Stalled`, and the next data or
+/// invalidate message back to `Streaming`.
+///
+/// With a buffered [crate::BackpressureStrategy], the stream is `Paused` while the buffer
+/// is full, whatever its other state, until the consumer catches up. Once the stream ends,
+/// for any reason, it's `Terminated` and never changes again.
+///
+/// Dropped connections are not retried by the stream, they end it with an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StreamStatus {
+    /// Waiting for the server to answer the current configuration.
+    Connecting,
+    /// Receiving data for the current configuration.
+    Streaming,
+    /// Waiting for the server to answer a restart with the current configuration.
+    Reconnecting,
+    /// The consumer is behind the server and the backpressure buffer is full.
+    Paused,
+    /// No data was received within the batch timeout.
+    Stalled,
+    /// The stream ended.
+    Terminated,
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Returns the current health of the stream.
+    ///
+    /// Use it to report the stream health, for example from a health check endpoint,
+    /// without inferring it from the messages received.
+    pub fn status(&self) -> StreamStatus {
+        if self.terminated {
+            StreamStatus::Terminated
+        } else if self.is_backpressure_full() {
+            StreamStatus::Paused
+        } else {
+            self.status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use crate::{
+        backpressure::with_backpressure,
+        test_utils::{batch_response, data_response, new_test_stream_with},
+        AdaptiveConfig, BackpressureStrategy, Configuration,
+    };
+
+    use super::StreamStatus;
+
+    #[tokio::test]
+    async fn test_status() {
+        let inner = futures::stream::iter(vec![Ok(data_response(1, 0, Vec::default()))])
+            .chain(futures::stream::pending());
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(Box::pin(inner));
+        stream.batch_timeout = Some(Duration::from_millis(10));
+        assert_eq!(stream.status(), StreamStatus::Connecting);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(stream.status(), StreamStatus::Streaming);
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(stream.status(), StreamStatus::Stalled);
+
+        drop(configuration_tx);
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.status(), StreamStatus::Terminated);
+    }
+
+    #[tokio::test]
+    async fn test_status_reconnecting() {
+        let responses = vec![Ok(batch_response(1, 0, 3)), Ok(batch_response(2, 3, 4))];
+        let inner = futures::stream::iter(responses).chain(futures::stream::pending());
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(Box::pin(inner));
+        stream.adaptive_batching = Some(
+            AdaptiveConfig::default()
+                .with_backfill_batch_size(10)
+                .with_tip_after(1),
+        );
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        // the short batch restarts the stream with the tip batch size.
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(stream.status(), StreamStatus::Reconnecting);
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(stream.status(), StreamStatus::Streaming);
+    }
+
+    #[tokio::test]
+    async fn test_status_paused() {
+        let responses = vec![
+            Ok(batch_response(1, 0, 1)),
+            Ok(batch_response(1, 1, 2)),
+            Ok(batch_response(1, 2, 3)),
+        ];
+        let inner = futures::stream::iter(responses).chain(futures::stream::pending());
+        let (inner, backpressure) =
+            with_backpressure(Box::pin(inner), BackpressureStrategy::DropOldest, 2);
+        let (mut stream, configuration_tx, _requests) = new_test_stream_with(inner);
+        stream.backpressure = backpressure;
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+        // let the background task fill the buffer.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(stream.status(), StreamStatus::Paused);
+
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(stream.status(), StreamStatus::Streaming);
+    }
+}
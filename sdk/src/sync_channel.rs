@@ -0,0 +1,83 @@
+//! Forward data to synchronous consumers.
+
+use crossbeam_channel::TrySendError;
+use futures::StreamExt;
+use prost::Message;
+
+use crate::{DataMessage, DataStream, DataStreamError};
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default + 'static,
+    D: Message + Default + 'static,
+{
+    /// Forward the stream messages to a bounded channel, for consumers running outside of an
+    /// async runtime.
+    ///
+    /// The stream is polled by a task of the current tokio runtime, which waits for space
+    /// in the channel before polling again, so the stream only receives data as fast as the
+    /// consumer processes it. While the channel is full, the message is sent from a blocking
+    /// task so that the runtime worker is never blocked.
+    ///
+    /// The channel is closed after the stream ends. Dropping the receiver stops the task and
+    /// closes the stream the next time a message is forwarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn into_sync_channel(
+        mut self,
+        capacity: usize,
+    ) -> crossbeam_channel::Receiver<Result<DataMessage<D>, DataStreamError>> {
+        let (tx, rx) = crossbeam_channel::bounded(capacity);
+        tokio::spawn(async move {
+            while let Some(message) = self.next().await {
+                let message = match tx.try_send(message) {
+                    Ok(()) => continue,
+                    Err(TrySendError::Disconnected(_)) => return,
+                    Err(TrySendError::Full(message)) => message,
+                };
+                let tx = tx.clone();
+                let sent = tokio::task::spawn_blocking(move || tx.send(message).is_ok()).await;
+                if !matches!(sent, Ok(true)) {
+                    // receiver dropped.
+                    return;
+                }
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration, DataMessage,
+    };
+
+    #[tokio::test]
+    async fn test_into_sync_channel() {
+        let responses = (0..3)
+            .map(|order_key| data_response(1, order_key, Vec::default()))
+            .collect();
+        let (stream, configuration_tx, _requests) = new_test_stream(responses);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let rx = stream.into_sync_channel(1);
+        let end_cursors = tokio::task::spawn_blocking(move || {
+            rx.iter()
+                .map(|message| match message.unwrap() {
+                    DataMessage::Data { end_cursor, .. } => end_cursor.order_key,
+                    DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap();
+        assert_eq!(end_cursors, vec![0, 1, 2]);
+    }
+}
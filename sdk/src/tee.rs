@@ -0,0 +1,101 @@
+//! Send the stream messages to two consumers.
+
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use prost::Message;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{DataMessage, DataStream, DataStreamError};
+
+/// A message sent by [DataStream::tee].
+///
+/// Errors are shared by both consumers, so they are wrapped in an [Arc].
+pub type TeeMessage<D> = Result<DataMessage<D>, Arc<DataStreamError>>;
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default + 'static,
+    D: Message + Default + Clone + 'static,
+{
+    /// Split the stream into two streams that both receive every message.
+    ///
+    /// Use it to feed two consumers, for example an in-memory consumer and a storage
+    /// writer, from the same subscription. Messages are cloned, which requires `D: Clone`
+    /// and copies every batch.
+    ///
+    /// The stream is polled by a task of the current tokio runtime, which waits until both
+    /// consumers have room for a message before polling again. The two consumers are
+    /// coupled: the slower one throttles both, and a consumer that stops polling without
+    /// being dropped stops the other one too. Dropping one of the streams doesn't stop the
+    /// other, the task ends when the stream ends or both streams are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn tee(
+        mut self,
+    ) -> (
+        impl Stream<Item = TeeMessage<D>>,
+        impl Stream<Item = TeeMessage<D>>,
+    ) {
+        let (first_tx, first_rx) = mpsc::channel(1);
+        let (second_tx, second_rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            while let Some(message) = self.next().await {
+                let message = message.map_err(Arc::new);
+                let first_sent = first_tx.send(message.clone()).await.is_ok();
+                let second_sent = second_tx.send(message).await.is_ok();
+                // both consumers dropped their stream.
+                if !first_sent && !second_sent {
+                    return;
+                }
+            }
+        });
+        (
+            ReceiverStream::new(first_rx),
+            ReceiverStream::new(second_rx),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::Block;
+    use futures::StreamExt;
+
+    use crate::{
+        test_utils::{data_response, new_test_stream},
+        Configuration, DataMessage,
+    };
+
+    use super::TeeMessage;
+
+    fn end_cursors(messages: Vec<TeeMessage<Block>>) -> Vec<u64> {
+        messages
+            .into_iter()
+            .map(|message| match message.unwrap() {
+                DataMessage::Data { end_cursor, .. } => end_cursor.order_key,
+                DataMessage::Invalidate { .. } => panic!("unexpected invalidate"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_tee() {
+        let responses = (0..3)
+            .map(|order_key| data_response(1, order_key, Vec::default()))
+            .collect();
+        let (stream, configuration_tx, _requests) = new_test_stream(responses);
+        configuration_tx
+            .send(Configuration::default())
+            .await
+            .unwrap();
+
+        let (first, second) = stream.tee();
+        let (first, second) = futures::join!(first.collect::<Vec<_>>(), second.collect::<Vec<_>>());
+        assert_eq!(end_cursors(first), vec![0, 1, 2]);
+        assert_eq!(end_cursors(second), vec![0, 1, 2]);
+    }
+}
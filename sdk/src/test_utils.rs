@@ -0,0 +1,61 @@
+//! Helpers shared by the unit tests.
+
+use apibara_core::{
+    node::v1alpha2::{stream_data_response, Cursor, Data, StreamDataRequest, StreamDataResponse},
+    starknet::v1alpha2::{Block, Filter},
+};
+use tokio::sync::mpsc::Receiver;
+
+use crate::{testing::unconfigured_mock_stream, DataStream, DataStreamClient, ResponseStream};
+
+/// A stream under test, with the client that configures it and the requests it sends.
+pub(crate) type TestStream = (
+    DataStream<Filter, Block>,
+    DataStreamClient<Filter>,
+    Receiver<StreamDataRequest>,
+);
+
+/// Creates a stream that receives the given responses from the server, then ends.
+///
+/// See [unconfigured_mock_stream].
+pub(crate) fn new_test_stream(responses: Vec<StreamDataResponse>) -> TestStream {
+    let inner = futures::stream::iter(responses.into_iter().map(Ok));
+    new_test_stream_with(Box::pin(inner))
+}
+
+/// Same as [new_test_stream], but the server responses are read from `inner`.
+pub(crate) fn new_test_stream_with(inner: ResponseStream) -> TestStream {
+    unconfigured_mock_stream(inner)
+}
+
+/// Returns a cursor without block hash.
+pub(crate) fn cursor(order_key: u64) -> Cursor {
+    Cursor {
+        order_key,
+        unique_key: Vec::default(),
+    }
+}
+
+/// Returns a batch with the given items, ending at `end`.
+pub(crate) fn data_response(stream_id: u64, end: u64, data: Vec<Vec<u8>>) -> StreamDataResponse {
+    StreamDataResponse {
+        stream_id,
+        message: Some(stream_data_response::Message::Data(Data {
+            end_cursor: Some(cursor(end)),
+            data,
+            ..Data::default()
+        })),
+    }
+}
+
+/// Returns an empty batch starting after `start` and ending at `end`.
+pub(crate) fn batch_response(stream_id: u64, start: u64, end: u64) -> StreamDataResponse {
+    StreamDataResponse {
+        stream_id,
+        message: Some(stream_data_response::Message::Data(Data {
+            cursor: Some(cursor(start)),
+            end_cursor: Some(cursor(end)),
+            ..Data::default()
+        })),
+    }
+}
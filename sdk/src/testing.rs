@@ -0,0 +1,293 @@
+//! Utilities to test code consuming a [DataStream].
+//!
+//! Enable the `testing` feature to use them.
+
+use apibara_core::{
+    node::v1alpha2::{
+        stream_data_response, Cursor, Data, DataFinality, Invalidate, StreamDataRequest,
+        StreamDataResponse,
+    },
+    starknet::v1alpha2::{Block, BlockHeader, BlockStatus, FieldElement},
+};
+use prost::Message;
+use tokio::sync::mpsc::{self, Receiver};
+
+use crate::{Configuration, DataStream, DataStreamClient, ResponseStream};
+
+/// Creates a [DataStream] that receives the given responses instead of connecting to a server.
+///
+/// The stream is configured with the default configuration, so the responses must have
+/// `stream_id = 1` to be delivered. The stream ends after the last response.
+///
+/// The requests sent to the server are returned together with the stream, keep the receiver
+/// alive for as long as the stream is used.
+pub fn mock_data_stream<F, D>(
+    responses: Vec<StreamDataResponse>,
+) -> (
+    DataStream<F, D>,
+    DataStreamClient<F>,
+    Receiver<StreamDataRequest>,
+)
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    let inner = futures::stream::iter(responses.into_iter().map(Ok));
+    let (stream, configuration_tx, inner_rx) = unconfigured_mock_stream(Box::pin(inner));
+    configuration_tx
+        .try_send(Configuration::default())
+        .expect("new channel has capacity");
+    (stream, configuration_tx, inner_rx)
+}
+
+/// Creates a [DataStream] that receives the responses of `inner`.
+///
+/// Unlike [mock_data_stream], the stream is not configured: it starts once a configuration
+/// is sent with the returned client.
+pub(crate) fn unconfigured_mock_stream<F, D>(
+    inner: ResponseStream,
+) -> (
+    DataStream<F, D>,
+    DataStreamClient<F>,
+    Receiver<StreamDataRequest>,
+)
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    let (configuration_tx, configuration_rx) = mpsc::channel(128);
+    let (inner_tx, inner_rx) = mpsc::channel(128);
+    let stream = DataStream::new(configuration_rx, inner, inner_tx);
+    (stream, configuration_tx, inner_rx)
+}
+
+/// A scripted sequence of blocks that includes a chain reorganization.
+///
+/// The stream first sends the blocks from the starting block to the head, one per batch.
+/// Then it invalidates the last `depth` blocks and sends a new branch that's one block
+/// longer than the invalidated one.
+#[derive(Debug, Clone)]
+pub struct ReorgScenario {
+    responses: Vec<StreamDataResponse>,
+}
+
+/// Builder for a [ReorgScenario].
+#[derive(Debug, Clone)]
+pub struct ReorgScenarioBuilder {
+    starting_block: u64,
+    head: u64,
+    depth: u64,
+}
+
+impl ReorgScenario {
+    /// Creates a new scenario builder.
+    pub fn builder() -> ReorgScenarioBuilder {
+        ReorgScenarioBuilder::default()
+    }
+
+    /// Blocks 0 to 10, then the last block is replaced.
+    pub fn shallow() -> Self {
+        ReorgScenario::builder().with_head(10).with_depth(1).build()
+    }
+
+    /// Blocks 0 to 100, then the last 50 blocks are replaced.
+    pub fn deep() -> Self {
+        ReorgScenario::builder()
+            .with_head(100)
+            .with_depth(50)
+            .build()
+    }
+
+    /// Blocks 0 to 10, then all blocks are replaced, including genesis.
+    pub fn to_genesis() -> Self {
+        ReorgScenario::builder()
+            .with_head(10)
+            .with_depth(11)
+            .build()
+    }
+
+    /// Returns the server responses of the scenario.
+    pub fn responses(&self) -> &[StreamDataResponse] {
+        &self.responses
+    }
+
+    /// Creates a mock stream that plays the scenario.
+    ///
+    /// See [mock_data_stream].
+    pub fn into_stream<F>(
+        self,
+    ) -> (
+        DataStream<F, Block>,
+        DataStreamClient<F>,
+        Receiver<StreamDataRequest>,
+    )
+    where
+        F: Message + Default,
+    {
+        mock_data_stream(self.responses)
+    }
+}
+
+impl ReorgScenarioBuilder {
+    /// Start the stream at the given block.
+    pub fn with_starting_block(mut self, starting_block: u64) -> Self {
+        self.starting_block = starting_block;
+        self
+    }
+
+    /// The last block sent before the reorg.
+    pub fn with_head(mut self, head: u64) -> Self {
+        self.head = head;
+        self
+    }
+
+    /// The number of blocks invalidated by the reorg.
+    ///
+    /// If `depth` is larger than the number of blocks sent, all blocks are invalidated.
+    pub fn with_depth(mut self, depth: u64) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Builds the scenario.
+    pub fn build(self) -> ReorgScenario {
+        let mut responses = Vec::default();
+        let mut previous = None;
+        for number in self.starting_block..=self.head {
+            let (response, end_cursor) = block_response(number, 0, previous.take());
+            responses.push(response);
+            previous = Some(end_cursor);
+        }
+
+        // invalidate all blocks after the last one that's kept.
+        let last_kept = self
+            .head
+            .checked_sub(self.depth)
+            .filter(|number| *number >= self.starting_block);
+        let cursor = last_kept.map(|number| block_cursor(number, 0));
+        responses.push(StreamDataResponse {
+            stream_id: 1,
+            message: Some(stream_data_response::Message::Invalidate(Invalidate {
+                cursor: cursor.clone(),
+            })),
+        });
+
+        let first_replaced = last_kept
+            .map(|number| number + 1)
+            .unwrap_or(self.starting_block);
+        let mut previous = cursor;
+        for number in first_replaced..=self.head + 1 {
+            let (response, end_cursor) = block_response(number, 1, previous.take());
+            responses.push(response);
+            previous = Some(end_cursor);
+        }
+
+        ReorgScenario { responses }
+    }
+}
+
+impl Default for ReorgScenarioBuilder {
+    fn default() -> Self {
+        ReorgScenarioBuilder {
+            starting_block: 0,
+            head: 10,
+            depth: 1,
+        }
+    }
+}
+
+/// Returns the hash of the block at the given height, on the given branch.
+fn block_hash(number: u64, branch: u64) -> FieldElement {
+    FieldElement {
+        lo_lo: branch,
+        lo_hi: 0,
+        hi_lo: 0,
+        hi_hi: number,
+    }
+}
+
+fn block_cursor(number: u64, branch: u64) -> Cursor {
+    Cursor {
+        order_key: number,
+        unique_key: block_hash(number, branch).to_bytes().to_vec(),
+    }
+}
+
+fn block_response(
+    number: u64,
+    branch: u64,
+    cursor: Option<Cursor>,
+) -> (StreamDataResponse, Cursor) {
+    let parent_block_hash = cursor
+        .as_ref()
+        .and_then(|c| c.unique_key.as_slice().try_into().ok())
+        .map(FieldElement::from_bytes);
+    let block = Block {
+        status: BlockStatus::AcceptedOnL2 as i32,
+        header: Some(BlockHeader {
+            block_hash: Some(block_hash(number, branch)),
+            parent_block_hash,
+            block_number: number,
+            ..BlockHeader::default()
+        }),
+        ..Block::default()
+    };
+    let end_cursor = block_cursor(number, branch);
+    let response = StreamDataResponse {
+        stream_id: 1,
+        message: Some(stream_data_response::Message::Data(Data {
+            end_cursor: Some(end_cursor.clone()),
+            finality: DataFinality::DataStatusAccepted as i32,
+            data: vec![block.encode_to_vec()],
+            cursor,
+        })),
+    };
+    (response, end_cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::Filter;
+    use futures_util::TryStreamExt;
+
+    use crate::DataMessage;
+
+    use super::ReorgScenario;
+
+    /// Plays the scenario, returning the block numbers received and the invalidate cursors.
+    async fn play(scenario: ReorgScenario) -> (Vec<u64>, Vec<Option<u64>>) {
+        let (mut stream, _client, _requests) = scenario.into_stream::<Filter>();
+        let mut numbers = Vec::default();
+        let mut invalidated = Vec::default();
+        while let Some(message) = stream.try_next().await.unwrap() {
+            match message {
+                DataMessage::Data { batch, .. } => numbers.extend(
+                    batch
+                        .iter()
+                        .map(|b| b.header.as_ref().unwrap().block_number),
+                ),
+                DataMessage::Invalidate { cursor } => invalidated.push(cursor.map(|c| c.order_key)),
+            }
+        }
+        (numbers, invalidated)
+    }
+
+    #[tokio::test]
+    async fn test_shallow_reorg() {
+        let (numbers, invalidated) = play(ReorgScenario::shallow()).await;
+        let mut expected: Vec<u64> = (0..=10).collect();
+        expected.extend([10, 11]);
+        assert_eq!(numbers, expected);
+        assert_eq!(invalidated, vec![Some(9)]);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_to_genesis() {
+        let (numbers, invalidated) = play(ReorgScenario::to_genesis()).await;
+        let mut expected: Vec<u64> = (0..=10).collect();
+        expected.extend(0..=11);
+        assert_eq!(numbers, expected);
+        // no cursor means everything is invalidated.
+        assert_eq!(invalidated, vec![None]);
+    }
+}
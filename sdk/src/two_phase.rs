@@ -0,0 +1,109 @@
+//! Backfill, then stream live data.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use apibara_core::node::v1alpha2::DataFinality;
+use futures::Stream;
+use pin_project::pin_project;
+use prost::Message;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::{Configuration, DataMessage, DataStream, DataStreamClient, DataStreamError};
+
+/// A [DataStream] that switches from a backfill configuration to a live configuration once it
+/// reaches the chain tip.
+///
+/// Created with [DataStream::two_phase].
+#[pin_project]
+pub struct TwoPhaseDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    #[pin]
+    inner: DataStream<F, D>,
+    client: DataStreamClient<F>,
+    live_configuration: Option<Configuration<F>>,
+}
+
+impl<F, D> DataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Stream data using `backfill_configuration` until the chain tip, then switch to
+    /// `live_configuration`.
+    ///
+    /// The tip is reached when the stream sends the first batch of data that is not finalized.
+    /// For this reason, the backfill configuration should request accepted data, otherwise the
+    /// stream never leaves the backfill phase.
+    ///
+    /// The live configuration starting cursor is replaced with the end cursor of the last
+    /// backfill batch, so that no data is sent twice.
+    pub fn two_phase(
+        self,
+        client: DataStreamClient<F>,
+        backfill_configuration: Configuration<F>,
+        live_configuration: Configuration<F>,
+    ) -> Result<TwoPhaseDataStream<F, D>, DataStreamError> {
+        client
+            .try_send(backfill_configuration)
+            .map_err(|_| DataStreamError::ConfigurationChannelClosed)?;
+
+        Ok(TwoPhaseDataStream {
+            inner: self,
+            client,
+            live_configuration: Some(live_configuration),
+        })
+    }
+}
+
+impl<F, D> TwoPhaseDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    /// Returns true if the stream switched to the live configuration.
+    pub fn is_live(&self) -> bool {
+        self.live_configuration.is_none()
+    }
+}
+
+impl<F, D> Stream for TwoPhaseDataStream<F, D>
+where
+    F: Message + Default,
+    D: Message + Default,
+{
+    type Item = Result<DataMessage<D>, DataStreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let poll = this.inner.poll_next(cx);
+
+        if let Poll::Ready(Some(Ok(DataMessage::Data {
+            end_cursor,
+            finality,
+            is_partial: false,
+            ..
+        }))) = &poll
+        {
+            if *finality != DataFinality::DataStatusFinalized {
+                if let Some(configuration) = this.live_configuration.take() {
+                    let configuration = configuration.with_starting_cursor(end_cursor.clone());
+                    // the channel receiver is owned by the inner stream, so it can only be full.
+                    // in that case, try again after the next batch.
+                    if let Err(TrySendError::Full(configuration)) =
+                        this.client.try_send(configuration)
+                    {
+                        *this.live_configuration = Some(configuration);
+                    }
+                }
+            }
+        }
+
+        poll
+    }
+}
@@ -0,0 +1,54 @@
+//! Attach the source block to items flattened out of blocks.
+
+use apibara_core::{
+    node::v1alpha2::{Cursor, DataFinality},
+    starknet::v1alpha2::BlockHeader,
+};
+
+/// An item flattened out of a block, together with the block it comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithBlock<T> {
+    /// The cursor of the block that contains the item.
+    ///
+    /// The order key is the block number and the unique key is the block hash. Compare it
+    /// with invalidate cursors to drop the items of blocks that are not canonical anymore.
+    pub block: Cursor,
+    /// The finality of the block.
+    pub finality: DataFinality,
+    /// The item.
+    pub item: T,
+}
+
+impl<T> WithBlock<T> {
+    /// Returns the block number.
+    pub fn block_number(&self) -> u64 {
+        self.block.order_key
+    }
+
+    /// Maps the item, keeping the block and finality.
+    pub fn map<U, F>(self, f: F) -> WithBlock<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        WithBlock {
+            block: self.block,
+            finality: self.finality,
+            item: f(self.item),
+        }
+    }
+}
+
+/// Returns the cursor of the block with the given header.
+///
+/// Returns `None` without header: a default cursor would point to the genesis block.
+pub(crate) fn block_cursor(header: Option<&BlockHeader>) -> Option<Cursor> {
+    let header = header?;
+    Some(Cursor {
+        order_key: header.block_number,
+        unique_key: header
+            .block_hash
+            .as_ref()
+            .map(|hash| hash.to_bytes().to_vec())
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,155 @@
+//! Benchmarks of the storage read and write paths, over a synthetic chain.
+
+use std::sync::Arc;
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{libmdbx::Environment, MdbxEnvironmentExt};
+use apibara_starknet::{
+    core::GlobalBlockId,
+    db::{
+        receipts_bloom, tables, BlockBody, BodyReader, DatabaseStorage, HeaderReader,
+        ReceiptReader, StorageReader, StorageWriter,
+    },
+    NoWriteMap,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tempfile::{tempdir, TempDir};
+
+/// Number of blocks in the synthetic chain.
+const CHAIN_LENGTH: u64 = 1_000;
+/// Number of transactions in each block.
+const TRANSACTIONS_PER_BLOCK: u64 = 50;
+/// Number of events emitted by each transaction.
+const EVENTS_PER_TRANSACTION: u64 = 4;
+
+fn block_id(number: u64) -> GlobalBlockId {
+    GlobalBlockId::new(number, v1alpha2::FieldElement::from_u64(number).into())
+}
+
+fn header(number: u64) -> v1alpha2::BlockHeader {
+    v1alpha2::BlockHeader {
+        block_hash: Some(v1alpha2::FieldElement::from_u64(number)),
+        parent_block_hash: number.checked_sub(1).map(v1alpha2::FieldElement::from_u64),
+        block_number: number,
+        ..v1alpha2::BlockHeader::default()
+    }
+}
+
+fn body(number: u64) -> BlockBody {
+    let transactions = (0..TRANSACTIONS_PER_BLOCK)
+        .map(|index| v1alpha2::Transaction {
+            meta: Some(v1alpha2::TransactionMeta {
+                hash: Some(v1alpha2::FieldElement::from_u64(number << 16 | index)),
+                ..v1alpha2::TransactionMeta::default()
+            }),
+            ..v1alpha2::Transaction::default()
+        })
+        .collect();
+    BlockBody { transactions }
+}
+
+fn receipts(number: u64, transactions: u64) -> Vec<v1alpha2::TransactionReceipt> {
+    (0..transactions)
+        .map(|index| {
+            let events = (0..EVENTS_PER_TRANSACTION)
+                .map(|event| v1alpha2::Event {
+                    from_address: Some(v1alpha2::FieldElement::from_u64(index % 10)),
+                    keys: vec![v1alpha2::FieldElement::from_u64(event)],
+                    data: vec![v1alpha2::FieldElement::from_u64(number)],
+                })
+                .collect();
+            v1alpha2::TransactionReceipt {
+                transaction_hash: Some(v1alpha2::FieldElement::from_u64(number << 16 | index)),
+                transaction_index: index,
+                events,
+                ..v1alpha2::TransactionReceipt::default()
+            }
+        })
+        .collect()
+}
+
+/// Creates a new storage with all tables.
+fn new_storage() -> (TempDir, DatabaseStorage<NoWriteMap>) {
+    let path = tempdir().unwrap();
+    let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+    let txn = db.begin_rw_txn().unwrap();
+    tables::ensure(&txn).unwrap();
+    txn.commit().unwrap();
+    (path, DatabaseStorage::new(Arc::new(db)))
+}
+
+/// Creates a new storage with the full synthetic chain.
+fn new_chain_storage() -> (TempDir, DatabaseStorage<NoWriteMap>) {
+    let (path, storage) = new_storage();
+    let mut txn = storage.begin_txn().unwrap();
+    for number in 0..CHAIN_LENGTH {
+        let id = block_id(number);
+        txn.extend_canonical_chain(&id).unwrap();
+        txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL2)
+            .unwrap();
+        txn.write_header(&id, header(number)).unwrap();
+        txn.write_body(&id, body(number)).unwrap();
+        txn.write_receipts(&id, receipts(number, TRANSACTIONS_PER_BLOCK))
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    (path, storage)
+}
+
+fn bench_bloom(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receipts_bloom");
+    for transactions in [1, 50, 500] {
+        let receipts = receipts(0, transactions);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(transactions),
+            &receipts,
+            |b, receipts| b.iter(|| receipts_bloom(black_box(receipts))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_write_receipts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write_receipts");
+    for bloom_enabled in [true, false] {
+        let (_path, storage) = new_storage();
+        let storage = storage.with_bloom_enabled(bloom_enabled);
+        let mut number = 0;
+        group.bench_function(BenchmarkId::new("bloom", bloom_enabled), |b| {
+            b.iter(|| {
+                let receipts = receipts(number, TRANSACTIONS_PER_BLOCK);
+                let mut txn = storage.begin_txn().unwrap();
+                txn.write_receipts(&block_id(number), receipts).unwrap();
+                txn.commit().unwrap();
+                number += 1;
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_read(c: &mut Criterion) {
+    let (_path, storage) = new_chain_storage();
+    let id = block_id(CHAIN_LENGTH / 2);
+
+    c.bench_function("read_full_block", |b| {
+        b.iter(|| {
+            let header = storage.read_header(black_box(&id)).unwrap();
+            let body = storage.read_body(&id).unwrap();
+            let receipts = storage.read_receipts(&id).unwrap();
+            (header, body, receipts)
+        })
+    });
+
+    let address = v1alpha2::FieldElement::from_u64(1);
+    c.bench_function("canonical_chain_walk", |b| {
+        b.iter(|| {
+            storage
+                .blocks_matching_bloom(black_box(&address), 0, CHAIN_LENGTH - 1)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_bloom, bench_write_receipts, bench_read);
+criterion_main!(benches);
@@ -142,6 +142,20 @@ impl From<&BlockHash> for v1alpha2::FieldElement {
     }
 }
 
+impl TryFrom<&Cursor> for GlobalBlockId {
+    type Error = InvalidBlockHashSize;
+
+    fn try_from(cursor: &Cursor) -> Result<Self, Self::Error> {
+        Self::from_cursor(cursor)
+    }
+}
+
+impl From<&GlobalBlockId> for Cursor {
+    fn from(id: &GlobalBlockId) -> Self {
+        id.to_cursor()
+    }
+}
+
 impl Display for GlobalBlockId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let hash = hex::encode(self.hash().as_bytes());
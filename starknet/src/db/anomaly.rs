@@ -0,0 +1,164 @@
+//! Detect inconsistent block hashes.
+
+use std::collections::BTreeMap;
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind},
+    MdbxTransactionExt,
+};
+
+use crate::core::{BlockHash, GlobalBlockId};
+
+use super::{tables, DatabaseStorage};
+
+/// An inconsistency between the canonical chain and the stored blocks.
+///
+/// Returned by [DatabaseStorage::find_hash_anomalies].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HashAnomaly {
+    /// The same hash is canonical at multiple heights.
+    DuplicateHash { hash: BlockHash, numbers: Vec<u64> },
+    /// The header stored for a canonical block has a different number.
+    HeaderNumberMismatch {
+        id: GlobalBlockId,
+        header_number: u64,
+    },
+    /// The header stored for a canonical block has a different hash.
+    HeaderHashMismatch {
+        id: GlobalBlockId,
+        header_hash: Option<v1alpha2::FieldElement>,
+    },
+    /// A block that's not canonical has an accepted status, at the height of a canonical
+    /// block.
+    ConflictingStatus {
+        canonical: GlobalBlockId,
+        other: GlobalBlockId,
+        status: v1alpha2::BlockStatus,
+    },
+}
+
+impl<E: EnvironmentKind> DatabaseStorage<E> {
+    /// Scans the canonical chain and the stored headers and statuses for inconsistent
+    /// block hashes.
+    ///
+    /// This is a read-only diagnostic to track down sync bugs, it reads the whole canonical
+    /// chain and status table in a single transaction. Canonical blocks without a stored
+    /// header are not reported.
+    pub fn find_hash_anomalies(&self) -> Result<Vec<HashAnomaly>, libmdbx::Error> {
+        let reader = self.reader_with_txn()?;
+        let mut canon_cursor = reader
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(reader.namespace)?;
+        let mut header_cursor = reader
+            .txn
+            .open_cursor_in::<tables::BlockHeaderTable>(reader.namespace)?;
+        let mut status_cursor = reader
+            .txn
+            .open_cursor_in::<tables::BlockStatusTable>(reader.namespace)?;
+
+        let mut anomalies = Vec::default();
+        let mut numbers_by_hash = BTreeMap::<Vec<u8>, (BlockHash, Vec<u64>)>::new();
+        let mut maybe_entry = canon_cursor.first()?;
+        while let Some((number, hash)) = maybe_entry {
+            let id = GlobalBlockId::new(number, BlockHash::from(&hash));
+            numbers_by_hash
+                .entry(id.hash().as_bytes().to_vec())
+                .or_insert_with(|| (*id.hash(), Vec::default()))
+                .1
+                .push(number);
+
+            if let Some((_, header)) = header_cursor.seek_exact(&id)? {
+                if header.block_number != number {
+                    anomalies.push(HashAnomaly::HeaderNumberMismatch {
+                        id,
+                        header_number: header.block_number,
+                    });
+                }
+                if header.block_hash.as_ref() != Some(&hash) {
+                    anomalies.push(HashAnomaly::HeaderHashMismatch {
+                        id,
+                        header_hash: header.block_hash,
+                    });
+                }
+            }
+            maybe_entry = canon_cursor.next()?;
+        }
+
+        for (_, (hash, numbers)) in numbers_by_hash {
+            if numbers.len() > 1 {
+                anomalies.push(HashAnomaly::DuplicateHash { hash, numbers });
+            }
+        }
+
+        let mut maybe_entry = status_cursor.first()?;
+        while let Some((other, status)) = maybe_entry {
+            let status = status.status();
+            let accepted = matches!(
+                status,
+                v1alpha2::BlockStatus::AcceptedOnL2 | v1alpha2::BlockStatus::AcceptedOnL1
+            );
+            if accepted {
+                if let Some((number, hash)) = canon_cursor.seek_exact(&other.number())? {
+                    let canonical = GlobalBlockId::new(number, BlockHash::from(&hash));
+                    if canonical.hash() != other.hash() {
+                        anomalies.push(HashAnomaly::ConflictingStatus {
+                            canonical,
+                            other,
+                            status,
+                        });
+                    }
+                }
+            }
+            maybe_entry = status_cursor.next()?;
+        }
+
+        Ok(anomalies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_hash, block_id, new_storage, write_chain},
+        StorageWriter,
+    };
+
+    use super::HashAnomaly;
+
+    #[test]
+    fn test_header_hash_mismatch() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+        write_chain(&storage, 0, 3, 0, 0);
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=3 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        assert_eq!(storage.find_hash_anomalies().unwrap(), vec![]);
+
+        // the header of block 2 is stored with the hash of a block on another branch.
+        let mut txn = storage.begin_txn().unwrap();
+        let header = v1alpha2::BlockHeader {
+            block_hash: Some(block_hash(2, 1)),
+            parent_block_hash: Some(block_hash(1, 0)),
+            block_number: 2,
+            ..v1alpha2::BlockHeader::default()
+        };
+        txn.write_header(&block_id(2, 0), header).unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(
+            storage.find_hash_anomalies().unwrap(),
+            vec![HashAnomaly::HeaderHashMismatch {
+                id: block_id(2, 0),
+                header_hash: Some(block_hash(2, 1)),
+            }]
+        );
+    }
+}
@@ -3,9 +3,10 @@
 use std::io::Cursor;
 
 use apibara_core::starknet::v1alpha2;
-use apibara_node::db::{KeyDecodeError, Table, TableKey};
+use apibara_node::db::{KeyDecodeError, ProstCodec, Table, TableKey};
 use byteorder::{BigEndian, ReadBytesExt};
 use prost::Message;
+use starknet::core::types::FieldElement;
 
 use crate::core::{BlockHash, GlobalBlockId};
 
@@ -57,10 +58,151 @@ pub struct BlockStatus {
     pub status: i32,
 }
 
+/// Store the history of block status changes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockStatusHistoryTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockStatusHistory {
+    #[prost(message, repeated, tag = "1")]
+    pub transitions: prost::alloc::vec::Vec<BlockStatusTransition>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockStatusTransition {
+    #[prost(enumeration = "v1alpha2::BlockStatus", tag = "1")]
+    pub status: i32,
+    /// Unix timestamp (in seconds) of when the status was written.
+    #[prost(uint64, tag = "2")]
+    pub timestamp: u64,
+}
+
+/// Number of stored blocks by status.
+///
+/// Returned by [super::StorageReader::total_by_status].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusHistogram {
+    pub unspecified: u64,
+    pub pending: u64,
+    pub accepted_on_l2: u64,
+    pub accepted_on_l1: u64,
+    pub rejected: u64,
+}
+
+impl StatusHistogram {
+    /// Counts one more block with the given status.
+    pub fn add(&mut self, status: v1alpha2::BlockStatus) {
+        match status {
+            v1alpha2::BlockStatus::Unspecified => self.unspecified += 1,
+            v1alpha2::BlockStatus::Pending => self.pending += 1,
+            v1alpha2::BlockStatus::AcceptedOnL2 => self.accepted_on_l2 += 1,
+            v1alpha2::BlockStatus::AcceptedOnL1 => self.accepted_on_l1 += 1,
+            v1alpha2::BlockStatus::Rejected => self.rejected += 1,
+        }
+    }
+
+    /// Returns the number of blocks counted.
+    pub fn total(&self) -> u64 {
+        self.unspecified + self.pending + self.accepted_on_l2 + self.accepted_on_l1 + self.rejected
+    }
+}
+
+/// Store consumer-defined data for each block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserMetaTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct UserMeta {
+    /// Opaque bytes, serialized by the consumer.
+    #[prost(bytes, tag = "1")]
+    pub data: prost::alloc::vec::Vec<u8>,
+}
+
+/// Store aggregate stats of each block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockStatsTable {}
+
+/// Aggregate stats of a block, computed when its body and receipts are written.
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockStats {
+    /// Sum of the fees paid by the block transactions.
+    #[prost(message, optional, tag = "1")]
+    pub total_fee: Option<v1alpha2::FieldElement>,
+    /// Number of transactions in the block body.
+    #[prost(uint64, tag = "2")]
+    pub transaction_count: u64,
+    /// Number of events in the block receipts.
+    #[prost(uint64, tag = "3")]
+    pub event_count: u64,
+}
+
+impl BlockStats {
+    /// Adds the fees and events of the given receipts to the stats.
+    ///
+    /// Fees that are not valid field elements are skipped.
+    pub fn add_receipts(&mut self, receipts: &[v1alpha2::TransactionReceipt]) {
+        let mut total_fee = self
+            .total_fee
+            .as_ref()
+            .and_then(|fee| FieldElement::from_bytes_be(&fee.to_bytes()).ok())
+            .unwrap_or(FieldElement::ZERO);
+        for receipt in receipts {
+            let fee = receipt
+                .actual_fee
+                .as_ref()
+                .and_then(|fee| FieldElement::from_bytes_be(&fee.to_bytes()).ok());
+            if let Some(fee) = fee {
+                total_fee = total_fee + fee;
+            }
+            self.event_count += receipt.events.len() as u64;
+        }
+        self.total_fee = Some(v1alpha2::FieldElement::from_bytes(&total_fee.to_bytes_be()));
+    }
+}
+
 /// Store block header.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BlockHeaderTable {}
 
+/// Store block numbers by their timestamp, then number.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeaderByTimestampTable {}
+
+/// Store block numbers by their sequencer address, then number.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SequencerIndexTable {}
+
+/// A sequencer address together with the number of a block it produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequencerKey {
+    pub sequencer: v1alpha2::FieldElement,
+    pub block_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SequencerBlock {
+    /// Hash of the block, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "1")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+}
+
+/// A block timestamp together with the block number.
+///
+/// Timestamps are not strictly increasing, so the block number is part of the key to keep
+/// all blocks with the same timestamp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeaderByTimestampKey {
+    pub timestamp: u64,
+    pub block_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockTimestamp {
+    /// Hash of the block, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "1")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+}
+
 impl TableKey for BlockHash {
     type Encoded = [u8; 32];
 
@@ -106,15 +248,162 @@ impl TableKey for GlobalBlockId {
 impl Table for BlockStatusTable {
     type Key = GlobalBlockId;
     type Value = BlockStatus;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "BlockStatus"
     }
 }
 
+impl Table for BlockStatusHistoryTable {
+    type Key = GlobalBlockId;
+    type Value = BlockStatusHistory;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "BlockStatusHistory"
+    }
+}
+
+impl Table for BlockStatsTable {
+    type Key = GlobalBlockId;
+    type Value = BlockStats;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "BlockStats"
+    }
+}
+
+impl Table for UserMetaTable {
+    type Key = GlobalBlockId;
+    type Value = UserMeta;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "UserMeta"
+    }
+}
+
+impl HeaderByTimestampKey {
+    pub fn new(timestamp: u64, block_number: u64) -> Self {
+        HeaderByTimestampKey {
+            timestamp,
+            block_number,
+        }
+    }
+
+    /// Returns the key of the given header, or `None` if it has no valid timestamp.
+    pub fn from_header(header: &v1alpha2::BlockHeader) -> Option<Self> {
+        let timestamp = header.timestamp.as_ref()?.seconds.try_into().ok()?;
+        Some(HeaderByTimestampKey::new(timestamp, header.block_number))
+    }
+}
+
+// The key is encoded as:
+// - 8 bytes big endian representation of the timestamp
+// - 8 bytes big endian representation of the block number
+impl TableKey for HeaderByTimestampKey {
+    type Encoded = [u8; 16];
+
+    fn encode(&self) -> Self::Encoded {
+        let mut out = [0; 16];
+        out[..8].copy_from_slice(&self.timestamp.to_be_bytes());
+        out[8..].copy_from_slice(&self.block_number.to_be_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        if b.len() != 16 {
+            return Err(KeyDecodeError::InvalidByteSize {
+                expected: 16,
+                actual: b.len(),
+            });
+        }
+        let mut cursor = Cursor::new(b);
+        let timestamp = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        let block_number = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        Ok(HeaderByTimestampKey::new(timestamp, block_number))
+    }
+}
+
+impl SequencerKey {
+    pub fn new(sequencer: v1alpha2::FieldElement, block_number: u64) -> Self {
+        SequencerKey {
+            sequencer,
+            block_number,
+        }
+    }
+
+    /// Returns the key of the given header, or `None` if it has no sequencer address.
+    pub fn from_header(header: &v1alpha2::BlockHeader) -> Option<Self> {
+        let sequencer = header.sequencer_address.clone()?;
+        Some(SequencerKey::new(sequencer, header.block_number))
+    }
+}
+
+// The key is encoded as:
+// - 32 bytes sequencer address
+// - 8 bytes big endian representation of the block number
+// so that all entries for a sequencer are sorted by block number.
+impl TableKey for SequencerKey {
+    type Encoded = [u8; 40];
+
+    fn encode(&self) -> Self::Encoded {
+        let mut out = [0; 40];
+        out[..32].copy_from_slice(&self.sequencer.to_bytes());
+        out[32..].copy_from_slice(&self.block_number.to_be_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        if b.len() != 40 {
+            return Err(KeyDecodeError::InvalidByteSize {
+                expected: 40,
+                actual: b.len(),
+            });
+        }
+        let mut sequencer = [0; 32];
+        sequencer.copy_from_slice(&b[..32]);
+        let mut cursor = Cursor::new(&b[32..]);
+        let block_number = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        Ok(SequencerKey::new(
+            v1alpha2::FieldElement::from_bytes(&sequencer),
+            block_number,
+        ))
+    }
+}
+
+impl Table for SequencerIndexTable {
+    type Key = SequencerKey;
+    type Value = SequencerBlock;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "SequencerIndex"
+    }
+}
+
+impl Table for HeaderByTimestampTable {
+    type Key = HeaderByTimestampKey;
+    type Value = BlockTimestamp;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "HeaderByTimestamp"
+    }
+}
+
 impl Table for BlockHeaderTable {
     type Key = GlobalBlockId;
     type Value = v1alpha2::BlockHeader;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "BlockHeader"
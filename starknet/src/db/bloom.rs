@@ -0,0 +1,131 @@
+//! Portable bloom filter format.
+
+use super::Bloom;
+
+/// Magic bytes at the start of blooms serialized by [BloomExt::to_standard_bytes].
+pub const BLOOM_MAGIC: [u8; 4] = *b"DNAB";
+
+/// Version of the format written by [BloomExt::to_standard_bytes].
+pub const BLOOM_FORMAT_VERSION: u8 = 1;
+
+/// Size in bytes of the header that comes before the bitmap.
+const HEADER_SIZE: usize = 49;
+
+/// Error returned by [BloomExt::from_standard_bytes].
+#[derive(Debug, thiserror::Error)]
+pub enum BloomFormatError {
+    #[error("bloom is too short: {0} bytes")]
+    TooShort(usize),
+    #[error("invalid bloom magic bytes")]
+    InvalidMagic,
+    #[error("unsupported bloom format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("bloom must have at least one bit and one hash function")]
+    Empty,
+    #[error("bitmap size mismatch: expected {expected} bytes, got {actual}")]
+    BitmapSizeMismatch { expected: usize, actual: usize },
+}
+
+/// Serialize [Bloom] to a stable format, independent of the on-disk layout.
+///
+/// Use it to export blooms to other tools. The format is, with all integers little endian:
+///
+///  - bytes `0..4`: the magic bytes `DNAB`.
+///  - byte `4`: the format version, currently `1`.
+///  - bytes `5..13`: number of bits in the bitmap, `u64`.
+///  - bytes `13..17`: number of hash functions, `u32`.
+///  - bytes `17..49`: the four SipHash-1-3 keys, `u64` each, in the order `k0_0`, `k0_1`,
+///    `k1_0`, `k1_1`. Each pair `(k*_0, k*_1)` keys one of the two hashers.
+///  - bytes `49..`: the bitmap, `ceil(bits / 8)` bytes. Bit `i` is bit `7 - i % 8` of byte
+///    `i / 8`.
+///
+/// Items are hashed like the `bloomfilter` crate, version 1, does: each field element is
+/// fed to the keyed hashers through Rust's `Hash` implementation of its 32 bytes, big
+/// endian, representation, which writes the length (`32usize`) before the bytes.
+pub trait BloomExt: Sized {
+    /// Serializes the bloom to the portable format.
+    fn to_standard_bytes(&self) -> Vec<u8>;
+
+    /// Deserializes a bloom serialized with [BloomExt::to_standard_bytes].
+    fn from_standard_bytes(bytes: &[u8]) -> Result<Self, BloomFormatError>;
+}
+
+impl BloomExt for Bloom {
+    fn to_standard_bytes(&self) -> Vec<u8> {
+        let bitmap = self.bitmap();
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + bitmap.len());
+        bytes.extend_from_slice(&BLOOM_MAGIC);
+        bytes.push(BLOOM_FORMAT_VERSION);
+        bytes.extend_from_slice(&self.number_of_bits().to_le_bytes());
+        bytes.extend_from_slice(&self.number_of_hash_functions().to_le_bytes());
+        for (key0, key1) in self.sip_keys() {
+            bytes.extend_from_slice(&key0.to_le_bytes());
+            bytes.extend_from_slice(&key1.to_le_bytes());
+        }
+        bytes.extend_from_slice(&bitmap);
+        bytes
+    }
+
+    fn from_standard_bytes(bytes: &[u8]) -> Result<Self, BloomFormatError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(BloomFormatError::TooShort(bytes.len()));
+        }
+        if bytes[0..4] != BLOOM_MAGIC {
+            return Err(BloomFormatError::InvalidMagic);
+        }
+        if bytes[4] != BLOOM_FORMAT_VERSION {
+            return Err(BloomFormatError::UnsupportedVersion(bytes[4]));
+        }
+        let bitmap_bits = read_u64(bytes, 5);
+        let number_of_hash_functions = u32::from_le_bytes(bytes[13..17].try_into().unwrap());
+        if bitmap_bits == 0 || number_of_hash_functions == 0 {
+            return Err(BloomFormatError::Empty);
+        }
+        let sip_keys = [
+            (read_u64(bytes, 17), read_u64(bytes, 25)),
+            (read_u64(bytes, 33), read_u64(bytes, 41)),
+        ];
+        let bitmap = &bytes[HEADER_SIZE..];
+        let expected = ((bitmap_bits + 7) / 8) as usize;
+        if bitmap.len() != expected {
+            return Err(BloomFormatError::BitmapSizeMismatch {
+                expected,
+                actual: bitmap.len(),
+            });
+        }
+        let bloom = Bloom::from_existing(bitmap, bitmap_bits, number_of_hash_functions, sip_keys);
+        Ok(bloom)
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2::FieldElement;
+
+    use crate::db::{bloom_contains, Bloom};
+
+    use super::{BloomExt, BloomFormatError};
+
+    #[test]
+    fn test_standard_bytes_roundtrip() {
+        let mut bloom = Bloom::new_for_fp_rate(100, 0.01);
+        let included = FieldElement::from_u64(1);
+        bloom.set(&included);
+
+        let bytes = bloom.to_standard_bytes();
+        assert_eq!(&bytes[0..5], b"DNAB\x01");
+        let decoded = Bloom::from_standard_bytes(&bytes).unwrap();
+        assert!(bloom_contains(&decoded, &included));
+        assert_eq!(decoded.bitmap(), bloom.bitmap());
+        assert_eq!(decoded.sip_keys(), bloom.sip_keys());
+
+        assert!(matches!(
+            Bloom::from_standard_bytes(&bytes[..bytes.len() - 1]),
+            Err(BloomFormatError::BitmapSizeMismatch { .. })
+        ));
+    }
+}
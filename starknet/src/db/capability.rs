@@ -0,0 +1,333 @@
+//! Restrict the tables a reader can access.
+//!
+//! [StorageReader](super::StorageReader) gives access to all tables. The traits in this
+//! module split the reads of block data by table group, so that code can require only the
+//! groups it needs, and [DatabaseStorage::reader_for] returns a reader that implements only
+//! the traits of the groups it's created for. Reading any other group is a compile error.
+//!
+//! The group traits are supertraits of [StorageReader](super::StorageReader), so migrating
+//! is a matter of replacing `R: StorageReader` bounds with the group traits actually used,
+//! for example `R: HeaderReader + BodyReader`, and then passing a restricted reader.
+//!
+//! Since the reads of block data are declared by the group traits, callers must import the
+//! traits of the methods they call, for example [HeaderReader] to call `read_header`, even
+//! when they only use a [StorageReader](super::StorageReader).
+
+use std::marker::PhantomData;
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+
+use crate::core::GlobalBlockId;
+
+use super::{Bloom, DatabaseStorage, ScopedReader};
+
+/// The group is allowed, see [TableGroups].
+#[derive(Debug)]
+pub struct Yes;
+
+/// The group is not allowed, see [TableGroups].
+#[derive(Debug)]
+pub struct No;
+
+/// Type-level or of [Yes] and [No].
+pub trait Or<Rhs> {
+    type Output;
+}
+
+impl Or<Yes> for Yes {
+    type Output = Yes;
+}
+
+impl Or<No> for Yes {
+    type Output = Yes;
+}
+
+impl Or<Yes> for No {
+    type Output = Yes;
+}
+
+impl Or<No> for No {
+    type Output = No;
+}
+
+/// A set of table groups, each [Yes] if it's in the set and [No] otherwise.
+///
+/// Implemented by the groups ([Headers], [Bodies], [Receipts], [StateUpdates]) and by
+/// tuples of up to four sets, which allow the union of their groups.
+pub trait TableGroups {
+    type Headers;
+    type Bodies;
+    type Receipts;
+    type StateUpdates;
+}
+
+/// The block headers table, see [HeaderReader].
+#[derive(Debug)]
+pub struct Headers;
+
+/// The block bodies table, see [BodyReader].
+#[derive(Debug)]
+pub struct Bodies;
+
+/// The block receipts table, see [ReceiptReader].
+#[derive(Debug)]
+pub struct Receipts;
+
+/// The state updates table, see [StateUpdateReader].
+#[derive(Debug)]
+pub struct StateUpdates;
+
+impl TableGroups for Headers {
+    type Headers = Yes;
+    type Bodies = No;
+    type Receipts = No;
+    type StateUpdates = No;
+}
+
+impl TableGroups for Bodies {
+    type Headers = No;
+    type Bodies = Yes;
+    type Receipts = No;
+    type StateUpdates = No;
+}
+
+impl TableGroups for Receipts {
+    type Headers = No;
+    type Bodies = No;
+    type Receipts = Yes;
+    type StateUpdates = No;
+}
+
+impl TableGroups for StateUpdates {
+    type Headers = No;
+    type Bodies = No;
+    type Receipts = No;
+    type StateUpdates = Yes;
+}
+
+impl<A, B> TableGroups for (A, B)
+where
+    A: TableGroups,
+    B: TableGroups,
+    A::Headers: Or<B::Headers>,
+    A::Bodies: Or<B::Bodies>,
+    A::Receipts: Or<B::Receipts>,
+    A::StateUpdates: Or<B::StateUpdates>,
+{
+    type Headers = <A::Headers as Or<B::Headers>>::Output;
+    type Bodies = <A::Bodies as Or<B::Bodies>>::Output;
+    type Receipts = <A::Receipts as Or<B::Receipts>>::Output;
+    type StateUpdates = <A::StateUpdates as Or<B::StateUpdates>>::Output;
+}
+
+impl<A, B, C> TableGroups for (A, B, C)
+where
+    ((A, B), C): TableGroups,
+{
+    type Headers = <((A, B), C) as TableGroups>::Headers;
+    type Bodies = <((A, B), C) as TableGroups>::Bodies;
+    type Receipts = <((A, B), C) as TableGroups>::Receipts;
+    type StateUpdates = <((A, B), C) as TableGroups>::StateUpdates;
+}
+
+impl<A, B, C, D> TableGroups for (A, B, C, D)
+where
+    ((A, B, C), D): TableGroups,
+{
+    type Headers = <((A, B, C), D) as TableGroups>::Headers;
+    type Bodies = <((A, B, C), D) as TableGroups>::Bodies;
+    type Receipts = <((A, B, C), D) as TableGroups>::Receipts;
+    type StateUpdates = <((A, B, C), D) as TableGroups>::StateUpdates;
+}
+
+/// The error type shared by the group traits.
+pub trait TableReader {
+    type Error: std::error::Error + Send + Sync + 'static;
+}
+
+/// Read the canonical chain, always allowed.
+pub trait ChainReader: TableReader {
+    /// Returns the highest accepted block that was indexed.
+    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error>;
+
+    /// Returns the block id for the block at the given height, or `None` if the
+    /// canonical chain is shorter.
+    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error>;
+
+    /// Returns `true` if the given block is part of the canonical chain.
+    fn is_canonical(&self, id: &GlobalBlockId) -> Result<bool, Self::Error>;
+}
+
+/// Read the [Headers] group.
+pub trait HeaderReader: TableReader {
+    /// Returns the block header for the given block.
+    fn read_header(&self, id: &GlobalBlockId)
+        -> Result<Option<v1alpha2::BlockHeader>, Self::Error>;
+}
+
+/// Read the [Bodies] group.
+pub trait BodyReader: TableReader {
+    /// Returns all transactions in the given block.
+    fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error>;
+}
+
+/// Read the [Receipts] group.
+pub trait ReceiptReader: TableReader {
+    /// Returns all receipts in the given block together with its bloom filter.
+    fn read_receipts(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error>;
+}
+
+/// Read the [StateUpdates] group.
+pub trait StateUpdateReader: TableReader {
+    /// Returns the state update for the given block.
+    fn read_state_update(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error>;
+}
+
+/// A reader limited to the table groups in `G`.
+///
+/// Created with [DatabaseStorage::reader_for]. Like [ScopedReader], all reads run in the
+/// same transaction, which is released when the reader is dropped.
+#[derive(Debug)]
+pub struct RestrictedReader<'env, E: EnvironmentKind, G: TableGroups> {
+    inner: ScopedReader<'env, E>,
+    _groups: PhantomData<G>,
+}
+
+impl<E: EnvironmentKind> DatabaseStorage<E> {
+    /// Returns a reader that can only read the table groups in `G`, and the canonical chain.
+    ///
+    /// For example, `reader_for::<(Headers, Bodies)>()` returns a reader that implements
+    /// [HeaderReader] and [BodyReader], but not [ReceiptReader] or [StateUpdateReader].
+    ///
+    /// Reading the receipts with that reader doesn't compile:
+    ///
+    /// ```compile_fail
+    /// use apibara_node::db::libmdbx::NoWriteMap;
+    /// use apibara_starknet::{
+    ///     core::GlobalBlockId,
+    ///     db::{Bodies, DatabaseStorage, Headers, ReceiptReader},
+    /// };
+    ///
+    /// fn read(storage: &DatabaseStorage<NoWriteMap>, id: &GlobalBlockId) {
+    ///     let reader = storage.reader_for::<(Headers, Bodies)>().unwrap();
+    ///     let _ = reader.read_receipts(id);
+    /// }
+    /// ```
+    pub fn reader_for<G: TableGroups>(&self) -> Result<RestrictedReader<'_, E, G>, libmdbx::Error> {
+        let inner = self.reader_with_txn()?;
+        Ok(RestrictedReader {
+            inner,
+            _groups: PhantomData,
+        })
+    }
+}
+
+impl<'env, E: EnvironmentKind, G: TableGroups> TableReader for RestrictedReader<'env, E, G> {
+    type Error = libmdbx::Error;
+}
+
+impl<'env, E: EnvironmentKind, G: TableGroups> ChainReader for RestrictedReader<'env, E, G> {
+    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        ChainReader::highest_accepted_block(&self.inner)
+    }
+
+    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
+        ChainReader::canonical_block_id(&self.inner, number)
+    }
+
+    fn is_canonical(&self, id: &GlobalBlockId) -> Result<bool, Self::Error> {
+        ChainReader::is_canonical(&self.inner, id)
+    }
+}
+
+impl<'env, E, G> HeaderReader for RestrictedReader<'env, E, G>
+where
+    E: EnvironmentKind,
+    G: TableGroups<Headers = Yes>,
+{
+    fn read_header(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::BlockHeader>, Self::Error> {
+        HeaderReader::read_header(&self.inner, id)
+    }
+}
+
+impl<'env, E, G> BodyReader for RestrictedReader<'env, E, G>
+where
+    E: EnvironmentKind,
+    G: TableGroups<Bodies = Yes>,
+{
+    fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error> {
+        BodyReader::read_body(&self.inner, id)
+    }
+}
+
+impl<'env, E, G> ReceiptReader for RestrictedReader<'env, E, G>
+where
+    E: EnvironmentKind,
+    G: TableGroups<Receipts = Yes>,
+{
+    fn read_receipts(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error> {
+        ReceiptReader::read_receipts(&self.inner, id)
+    }
+}
+
+impl<'env, E, G> StateUpdateReader for RestrictedReader<'env, E, G>
+where
+    E: EnvironmentKind,
+    G: TableGroups<StateUpdates = Yes>,
+{
+    fn read_state_update(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error> {
+        StateUpdateReader::read_state_update(&self.inner, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_id, new_storage, write_chain},
+        BlockBody, Bodies, BodyReader, ChainReader, HeaderReader, Headers, StorageWriter,
+    };
+
+    #[test]
+    fn test_reader_for_headers_and_bodies() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+        write_chain(&storage, 0, 0, 0, 0);
+        let transactions = vec![v1alpha2::Transaction::default()];
+        let mut txn = storage.begin_txn().unwrap();
+        let body = BlockBody {
+            transactions: transactions.clone(),
+        };
+        txn.write_body(&block_id(0, 0), body).unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let reader = storage.reader_for::<(Headers, Bodies)>().unwrap();
+        assert_eq!(
+            reader.highest_accepted_block().unwrap(),
+            Some(block_id(0, 0))
+        );
+        let header = reader.read_header(&block_id(0, 0)).unwrap().unwrap();
+        assert_eq!(header.block_number, 0);
+        assert_eq!(reader.read_body(&block_id(0, 0)).unwrap(), transactions);
+        assert!(reader.read_header(&block_id(1, 0)).unwrap().is_none());
+    }
+}
@@ -1,7 +1,7 @@
 //! Canonical chain.
 
 use apibara_core::starknet::v1alpha2;
-use apibara_node::db::Table;
+use apibara_node::db::{ProstCodec, Table};
 
 /// Store canonical chain.
 #[derive(Debug, Clone, Copy, Default)]
@@ -10,6 +10,7 @@ pub struct CanonicalChainTable {}
 impl Table for CanonicalChainTable {
     type Key = u64;
     type Value = v1alpha2::FieldElement;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "CanonicalChain"
@@ -0,0 +1,97 @@
+//! Contract information for event decoders.
+
+use apibara_core::starknet::v1alpha2;
+
+use crate::core::GlobalBlockId;
+
+use super::StorageReader;
+
+/// The deployment block and current class of a contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractInfo {
+    /// The canonical block that deployed the contract.
+    pub deployed_at: GlobalBlockId,
+    /// The class hash as of the highest accepted block.
+    pub class_hash: Option<v1alpha2::FieldElement>,
+}
+
+/// Looks up the deployment block and class hash of `address` in storage.
+///
+/// Event-decoding consumers need both before decoding the first event: the deployment block
+/// is where the stream can start, and the class hash selects the ABI. Call it once per
+/// contract before streaming, instead of waiting for the data to arrive. Returns `None` if
+/// the contract wasn't deployed in a stored block.
+pub fn warm_contract<R: StorageReader>(
+    storage: &R,
+    address: &v1alpha2::FieldElement,
+) -> Result<Option<ContractInfo>, R::Error> {
+    let deployed_at = match storage.contract_deployment_block(address)? {
+        None => return Ok(None),
+        Some(deployed_at) => deployed_at,
+    };
+    let tip = storage
+        .highest_accepted_block()?
+        .map(|id| id.number())
+        .unwrap_or_else(|| deployed_at.number());
+    let class_hash = storage.class_hash_at(address, tip)?;
+    Ok(Some(ContractInfo {
+        deployed_at,
+        class_hash,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_id, new_storage},
+        StorageWriter,
+    };
+
+    use super::{warm_contract, ContractInfo};
+
+    fn deployment(contract: u64, class_hash: u64) -> v1alpha2::StateUpdate {
+        v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                deployed_contracts: vec![v1alpha2::DeployedContract {
+                    contract_address: Some(v1alpha2::FieldElement::from_u64(contract)),
+                    class_hash: Some(v1alpha2::FieldElement::from_u64(class_hash)),
+                }],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        }
+    }
+
+    #[test]
+    fn test_warm_contract() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+        let contract = v1alpha2::FieldElement::from_u64(7);
+
+        assert_eq!(warm_contract(&storage, &contract).unwrap(), None);
+
+        // the contract is deployed in block 2, its class is replaced in block 4.
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=5 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.write_state_update(&block_id(2, 0), deployment(7, 30))
+            .unwrap();
+        txn.write_state_update(&block_id(4, 0), deployment(7, 31))
+            .unwrap();
+        txn.write_state_update(&block_id(3, 0), deployment(8, 40))
+            .unwrap();
+        txn.commit().unwrap();
+
+        let expected = ContractInfo {
+            deployed_at: block_id(2, 0),
+            class_hash: Some(v1alpha2::FieldElement::from_u64(31)),
+        };
+        assert_eq!(warm_contract(&storage, &contract).unwrap(), Some(expected));
+        let unknown = v1alpha2::FieldElement::from_u64(9);
+        assert_eq!(warm_contract(&storage, &unknown).unwrap(), None);
+    }
+}
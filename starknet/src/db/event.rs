@@ -0,0 +1,81 @@
+//! Event indexes.
+
+use std::io::Cursor;
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{KeyDecodeError, ProstCodec, Table, TableKey};
+use byteorder::{BigEndian, ReadBytesExt};
+use prost::Message;
+
+/// Store events by their selector (first key), then block number.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventBySelectorTable {}
+
+/// An event selector together with a block number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventBySelectorKey {
+    pub selector: v1alpha2::FieldElement,
+    pub block_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockEvents {
+    /// Hash of the block that emitted the events, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "1")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+    #[prost(message, repeated, tag = "2")]
+    pub events: prost::alloc::vec::Vec<v1alpha2::Event>,
+}
+
+impl EventBySelectorKey {
+    pub fn new(selector: v1alpha2::FieldElement, block_number: u64) -> Self {
+        EventBySelectorKey {
+            selector,
+            block_number,
+        }
+    }
+}
+
+// The key is encoded as:
+// - 32 bytes event selector
+// - 8 bytes big endian representation of the block number
+// so that all entries for a selector are sorted by block number.
+impl TableKey for EventBySelectorKey {
+    type Encoded = [u8; 40];
+
+    fn encode(&self) -> Self::Encoded {
+        let mut out = [0; 40];
+        out[..32].copy_from_slice(&self.selector.to_bytes());
+        out[32..].copy_from_slice(&self.block_number.to_be_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        if b.len() != 40 {
+            return Err(KeyDecodeError::InvalidByteSize {
+                expected: 40,
+                actual: b.len(),
+            });
+        }
+        let mut selector = [0; 32];
+        selector.copy_from_slice(&b[..32]);
+        let mut cursor = Cursor::new(&b[32..]);
+        let block_number = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        Ok(EventBySelectorKey::new(
+            v1alpha2::FieldElement::from_bytes(&selector),
+            block_number,
+        ))
+    }
+}
+
+impl Table for EventBySelectorTable {
+    type Key = EventBySelectorKey;
+    type Value = BlockEvents;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "EventBySelector"
+    }
+}
@@ -0,0 +1,216 @@
+//! Export events to Parquet.
+
+use std::{io::Write, sync::Arc};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use arrow::{
+    array::{ArrayRef, FixedSizeBinaryBuilder, Int64Builder, ListBuilder, UInt64Builder},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use parquet::{arrow::ArrowWriter, errors::ParquetError};
+
+use crate::core::GlobalBlockId;
+
+use super::{
+    storage::iter_block_receipt_events, ChainReader, DatabaseStorage, HeaderReader, ReceiptReader,
+};
+
+/// Size in bytes of field elements.
+const FIELD_ELEMENT_SIZE: i32 = 32;
+
+/// Error returned by [DatabaseStorage::export_parquet].
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Database(#[from] libmdbx::Error),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+}
+
+impl<E: EnvironmentKind> DatabaseStorage<E> {
+    /// Writes the events emitted by the canonical blocks between `from` and `to` (inclusive)
+    /// to `writer`, as a Parquet file.
+    ///
+    /// The file has one row per event, with the following columns. Field elements are
+    /// stored as 32 bytes, big endian.
+    ///
+    ///  - `block_number`, `uint64`: block number.
+    ///  - `block_hash`, `fixed_size_binary(32)`: block hash.
+    ///  - `block_timestamp`, nullable `int64`: block timestamp, in seconds.
+    ///  - `transaction_hash`, nullable `fixed_size_binary(32)`: hash of the transaction that
+    ///    emitted the event.
+    ///  - `transaction_index`, `uint64`: index of the transaction in the block.
+    ///  - `event_index`, `uint64`: index of the event in the block, as returned by
+    ///    [iter_block_events](super::iter_block_events).
+    ///  - `from_address`, nullable `fixed_size_binary(32)`: address of the contract that
+    ///    emitted the event.
+    ///  - `keys`, `list<fixed_size_binary(32)>`: event keys.
+    ///  - `data`, `list<fixed_size_binary(32)>`: event data.
+    ///
+    /// All blocks are read in the same transaction.
+    pub fn export_parquet<W: Write + Send>(
+        &self,
+        from: u64,
+        to: u64,
+        writer: W,
+    ) -> Result<(), ExportError> {
+        let schema = events_schema();
+        let mut writer = ArrowWriter::try_new(writer, schema.clone(), None)?;
+        let reader = self.reader_with_txn()?;
+        for number in from..=to {
+            let block_id = match reader.canonical_block_id(number)? {
+                None => break,
+                Some(block_id) => block_id,
+            };
+            let header = reader.read_header(&block_id)?;
+            let (receipts, _) = reader.read_receipts(&block_id)?;
+            let batch = block_events_batch(schema.clone(), &block_id, header, receipts)?;
+            if batch.num_rows() > 0 {
+                writer.write(&batch)?;
+            }
+        }
+        writer.close()?;
+        Ok(())
+    }
+}
+
+fn events_schema() -> SchemaRef {
+    let field_element = DataType::FixedSizeBinary(FIELD_ELEMENT_SIZE);
+    let field_elements = DataType::List(Box::new(Field::new("item", field_element.clone(), true)));
+    let schema = Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_hash", field_element.clone(), false),
+        Field::new("block_timestamp", DataType::Int64, true),
+        Field::new("transaction_hash", field_element.clone(), true),
+        Field::new("transaction_index", DataType::UInt64, false),
+        Field::new("event_index", DataType::UInt64, false),
+        Field::new("from_address", field_element, true),
+        Field::new("keys", field_elements.clone(), false),
+        Field::new("data", field_elements, false),
+    ]);
+    Arc::new(schema)
+}
+
+fn block_events_batch(
+    schema: SchemaRef,
+    block_id: &GlobalBlockId,
+    header: Option<v1alpha2::BlockHeader>,
+    receipts: Vec<v1alpha2::TransactionReceipt>,
+) -> Result<RecordBatch, ArrowError> {
+    let block_hash = v1alpha2::FieldElement::from(block_id.hash()).to_bytes();
+    let block_timestamp = header.and_then(|h| h.timestamp).map(|t| t.seconds);
+
+    let mut block_number_col = UInt64Builder::new();
+    let mut block_hash_col = FixedSizeBinaryBuilder::new(FIELD_ELEMENT_SIZE);
+    let mut block_timestamp_col = Int64Builder::new();
+    let mut transaction_hash_col = FixedSizeBinaryBuilder::new(FIELD_ELEMENT_SIZE);
+    let mut transaction_index_col = UInt64Builder::new();
+    let mut event_index_col = UInt64Builder::new();
+    let mut from_address_col = FixedSizeBinaryBuilder::new(FIELD_ELEMENT_SIZE);
+    let mut keys_col = ListBuilder::new(FixedSizeBinaryBuilder::new(FIELD_ELEMENT_SIZE));
+    let mut data_col = ListBuilder::new(FixedSizeBinaryBuilder::new(FIELD_ELEMENT_SIZE));
+
+    for (event_index, receipt, event) in iter_block_receipt_events(&receipts) {
+        block_number_col.append_value(block_id.number());
+        block_hash_col.append_value(block_hash)?;
+        block_timestamp_col.append_option(block_timestamp);
+        append_field_element(&mut transaction_hash_col, receipt.transaction_hash.as_ref())?;
+        transaction_index_col.append_value(receipt.transaction_index);
+        event_index_col.append_value(event_index as u64);
+        append_field_element(&mut from_address_col, event.from_address.as_ref())?;
+        for key in &event.keys {
+            keys_col.values().append_value(key.to_bytes())?;
+        }
+        keys_col.append(true);
+        for value in &event.data {
+            data_col.values().append_value(value.to_bytes())?;
+        }
+        data_col.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(block_number_col.finish()),
+        Arc::new(block_hash_col.finish()),
+        Arc::new(block_timestamp_col.finish()),
+        Arc::new(transaction_hash_col.finish()),
+        Arc::new(transaction_index_col.finish()),
+        Arc::new(event_index_col.finish()),
+        Arc::new(from_address_col.finish()),
+        Arc::new(keys_col.finish()),
+        Arc::new(data_col.finish()),
+    ];
+    RecordBatch::try_new(schema, columns)
+}
+
+fn append_field_element(
+    builder: &mut FixedSizeBinaryBuilder,
+    value: Option<&v1alpha2::FieldElement>,
+) -> Result<(), ArrowError> {
+    match value {
+        None => {
+            builder.append_null();
+            Ok(())
+        }
+        Some(value) => builder.append_value(value.to_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use apibara_core::starknet::v1alpha2;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_id, new_storage},
+        StorageWriter,
+    };
+
+    use super::events_schema;
+
+    #[test]
+    fn test_export_parquet() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let event = v1alpha2::Event {
+            from_address: Some(v1alpha2::FieldElement::from_u64(1)),
+            keys: vec![v1alpha2::FieldElement::from_u64(2)],
+            data: vec![v1alpha2::FieldElement::from_u64(3)],
+        };
+        // block `number` has `number + 1` events.
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..2 {
+            let receipt = v1alpha2::TransactionReceipt {
+                events: vec![event.clone(); number as usize + 1],
+                ..v1alpha2::TransactionReceipt::default()
+            };
+            txn.write_receipts(&block_id(number, 0), vec![receipt])
+                .unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        // the export stops at the end of the canonical chain.
+        let file_path = path.path().join("events.parquet");
+        let file = File::create(&file_path).unwrap();
+        storage.export_parquet(0, 10, file).unwrap();
+
+        let file = File::open(&file_path).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(builder.schema().fields(), events_schema().fields());
+        let rows: usize = builder
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum();
+        assert_eq!(rows, 3);
+    }
+}
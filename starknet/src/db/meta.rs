@@ -0,0 +1,25 @@
+//! Storage metadata.
+
+use apibara_node::db::{ProstCodec, Table};
+use prost::Message;
+
+/// Store storage metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetaTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Meta {
+    /// Last known chain tip, as recorded by the consumer.
+    #[prost(uint64, optional, tag = "1")]
+    pub chain_tip: Option<u64>,
+}
+
+impl Table for MetaTable {
+    type Key = ();
+    type Value = Meta;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "Meta"
+    }
+}
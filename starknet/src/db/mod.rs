@@ -1,29 +1,96 @@
+mod anomaly;
 mod block;
+mod bloom;
+mod capability;
 mod chain;
+mod contract;
+mod event;
+#[cfg(feature = "parquet")]
+mod export;
+mod meta;
+mod pipe;
+mod readonly;
+mod resume;
 mod state;
 mod storage;
+mod stream;
+#[cfg(test)]
+mod test_utils;
 mod transaction;
 
-pub use self::block::{BlockBody, BlockReceipts, BlockStatus};
-pub use self::storage::{DatabaseStorage, DatabaseStorageWriter, StorageReader, StorageWriter};
+pub use self::anomaly::HashAnomaly;
+pub use self::block::{
+    BlockBody, BlockReceipts, BlockStats, BlockStatus, BlockStatusHistory, BlockStatusTransition,
+    BlockTimestamp, HeaderByTimestampKey, SequencerBlock, SequencerKey, StatusHistogram, UserMeta,
+};
+pub use self::bloom::{BloomExt, BloomFormatError, BLOOM_FORMAT_VERSION, BLOOM_MAGIC};
+pub use self::capability::{
+    Bodies, BodyReader, ChainReader, HeaderReader, Headers, No, Or, ReceiptReader, Receipts,
+    RestrictedReader, StateUpdateReader, StateUpdates, TableGroups, TableReader, Yes,
+};
+pub use self::contract::{warm_contract, ContractInfo};
+pub use self::event::{BlockEvents, EventBySelectorKey};
+#[cfg(feature = "parquet")]
+pub use self::export::ExportError;
+pub use self::meta::Meta;
+pub use self::pipe::{affected_blocks, pipe_to_storage, PipeError};
+pub use self::readonly::ReadOnlyDatabaseStorage;
+pub use self::resume::ConfigurationExt;
+pub use self::state::{ContractClass, ContractClassKey, ContractNonce, NonceKey, StorageDiffs};
+pub use self::storage::{
+    bloom_contains, iter_block_events, receipts_bloom, receipts_bloom_with_config, Bloom,
+    BloomConfig, DatabaseStorage, DatabaseStorageWriter, IndexKind, ScopedReader, StorageError,
+    StorageReader, StorageWriter,
+};
+pub use self::transaction::{
+    BlockL1Handlers, L1Handler, TransactionHashKey, TransactionIndexEntry, TransactionLocation,
+};
 
 pub mod tables {
     use apibara_node::db::libmdbx::{EnvironmentKind, Error as MdbxError, Transaction, RW};
     use apibara_node::db::MdbxRWTransactionExt;
 
-    pub use super::block::{BlockHeaderTable, BlockStatusTable};
+    pub use super::block::{
+        BlockHeaderTable, BlockStatsTable, BlockStatusHistoryTable, BlockStatusTable,
+        HeaderByTimestampTable, SequencerIndexTable, UserMetaTable,
+    };
     pub use super::chain::CanonicalChainTable;
-    pub use super::state::StateUpdateTable;
-    pub use super::transaction::{BlockBodyTable, BlockReceiptsTable};
+    pub use super::event::EventBySelectorTable;
+    pub use super::meta::MetaTable;
+    pub use super::state::{ContractClassTable, NonceTable, StateUpdateTable};
+    pub use super::transaction::{
+        BlockBodyTable, BlockReceiptsTable, L1HandlerIndexTable, TransactionByHashTable,
+    };
 
     /// Ensures all tables exist.
     pub fn ensure<E: EnvironmentKind>(txn: &Transaction<RW, E>) -> Result<(), MdbxError> {
-        txn.ensure_table::<self::BlockBodyTable>(None)?;
-        txn.ensure_table::<self::BlockHeaderTable>(None)?;
-        txn.ensure_table::<self::BlockStatusTable>(None)?;
-        txn.ensure_table::<self::CanonicalChainTable>(None)?;
-        txn.ensure_table::<self::BlockReceiptsTable>(None)?;
-        txn.ensure_table::<self::StateUpdateTable>(None)?;
+        ensure_in(txn, "")
+    }
+
+    /// Ensures all tables exist inside `namespace`.
+    ///
+    /// See [super::DatabaseStorage::with_namespace].
+    pub fn ensure_in<E: EnvironmentKind>(
+        txn: &Transaction<RW, E>,
+        namespace: &str,
+    ) -> Result<(), MdbxError> {
+        txn.ensure_table_in::<self::BlockBodyTable>(namespace, None)?;
+        txn.ensure_table_in::<self::BlockHeaderTable>(namespace, None)?;
+        txn.ensure_table_in::<self::BlockStatusTable>(namespace, None)?;
+        txn.ensure_table_in::<self::BlockStatusHistoryTable>(namespace, None)?;
+        txn.ensure_table_in::<self::CanonicalChainTable>(namespace, None)?;
+        txn.ensure_table_in::<self::BlockReceiptsTable>(namespace, None)?;
+        txn.ensure_table_in::<self::StateUpdateTable>(namespace, None)?;
+        txn.ensure_table_in::<self::MetaTable>(namespace, None)?;
+        txn.ensure_table_in::<self::L1HandlerIndexTable>(namespace, None)?;
+        txn.ensure_table_in::<self::ContractClassTable>(namespace, None)?;
+        txn.ensure_table_in::<self::EventBySelectorTable>(namespace, None)?;
+        txn.ensure_table_in::<self::UserMetaTable>(namespace, None)?;
+        txn.ensure_table_in::<self::HeaderByTimestampTable>(namespace, None)?;
+        txn.ensure_table_in::<self::NonceTable>(namespace, None)?;
+        txn.ensure_table_in::<self::BlockStatsTable>(namespace, None)?;
+        txn.ensure_table_in::<self::TransactionByHashTable>(namespace, None)?;
+        txn.ensure_table_in::<self::SequencerIndexTable>(namespace, None)?;
         Ok(())
     }
 }
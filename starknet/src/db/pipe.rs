@@ -0,0 +1,212 @@
+//! Fill storage from a data stream.
+
+use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use apibara_sdk::{DataMessage, DataStream, DataStreamError};
+use futures::TryStreamExt;
+use tracing::debug;
+
+use crate::core::{GlobalBlockId, InvalidBlock};
+
+use super::{ChainReader, DatabaseStorage, StorageError, StorageWriter};
+
+/// Error returned by [pipe_to_storage].
+#[derive(Debug, thiserror::Error)]
+pub enum PipeError {
+    #[error(transparent)]
+    Stream(#[from] DataStreamError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Database(#[from] libmdbx::Error),
+    #[error(transparent)]
+    InvalidBlock(#[from] InvalidBlock),
+}
+
+/// Writes the blocks received from `stream` to `storage`, until the stream ends.
+///
+/// Each batch is written and added to the canonical chain in a single transaction, so
+/// storage always ends at a batch boundary. Invalidate messages reject all canonical blocks
+/// after the invalidated cursor, in a single transaction.
+///
+/// Only the data sent by the stream is stored, so the stream filter must include the header
+/// (not weak) and all the transactions, receipts and state updates that should be stored.
+pub async fn pipe_to_storage<E: EnvironmentKind>(
+    mut stream: DataStream<v1alpha2::Filter, v1alpha2::Block>,
+    storage: &DatabaseStorage<E>,
+) -> Result<(), PipeError> {
+    while let Some(message) = stream.try_next().await? {
+        match message {
+            DataMessage::Data { batch, .. } => write_batch(storage, batch)?,
+            DataMessage::Invalidate { cursor } => reject_blocks_after(storage, cursor)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_batch<E: EnvironmentKind>(
+    storage: &DatabaseStorage<E>,
+    batch: Vec<v1alpha2::Block>,
+) -> Result<(), PipeError> {
+    let mut txn = storage.begin_txn()?;
+    for block in batch {
+        let id = GlobalBlockId::from_block(&block)?;
+        txn.write_block(&id, block)?;
+        txn.extend_canonical_chain(&id)?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+/// Returns the canonical blocks invalidated by an invalidate message with the given `cursor`.
+///
+/// These are all the canonical blocks strictly after the cursor, in ascending order, that
+/// should be rejected from the canonical chain. A `None` cursor invalidates all blocks.
+///
+/// Only the canonical chain is read, so any [StorageReader](super::StorageReader) or
+/// restricted reader works. Pass the reader returned by [DatabaseStorage::reader_with_txn]
+/// to read all blocks in the same transaction.
+pub fn affected_blocks<R: ChainReader>(
+    reader: &R,
+    cursor: Option<Cursor>,
+) -> Result<Vec<GlobalBlockId>, R::Error> {
+    let first_invalid = cursor.map(|c| c.order_key + 1).unwrap_or_default();
+    let highest = match reader.highest_accepted_block()? {
+        None => return Ok(Vec::default()),
+        Some(highest) => highest,
+    };
+    let mut invalid = Vec::default();
+    for number in first_invalid..=highest.number() {
+        if let Some(id) = reader.canonical_block_id(number)? {
+            invalid.push(id);
+        }
+    }
+    Ok(invalid)
+}
+
+/// Rejects all canonical blocks after `cursor`.
+fn reject_blocks_after<E: EnvironmentKind>(
+    storage: &DatabaseStorage<E>,
+    cursor: Option<Cursor>,
+) -> Result<(), PipeError> {
+    // read the block ids before starting the write transaction.
+    let invalid = affected_blocks(&storage.reader_with_txn()?, cursor)?;
+    if invalid.is_empty() {
+        return Ok(());
+    }
+    debug!(
+        first_invalid = invalid[0].number(),
+        count = invalid.len(),
+        "rejecting blocks"
+    );
+
+    let mut txn = storage.begin_txn()?;
+    for id in invalid.iter().rev() {
+        txn.reject_block_from_canonical_chain(id)?;
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use apibara_sdk::testing::ReorgScenario;
+    use tempfile::tempdir;
+
+    use crate::{
+        core::GlobalBlockId,
+        db::{tables, ChainReader, DatabaseStorage, Headers, StorageReader},
+    };
+
+    use super::{affected_blocks, pipe_to_storage};
+
+    fn block_id(number: u64, branch: u64) -> GlobalBlockId {
+        // same hashes as the scenario blocks.
+        let hash = v1alpha2::FieldElement {
+            lo_lo: branch,
+            lo_hi: 0,
+            hi_lo: 0,
+            hi_hi: number,
+        };
+        GlobalBlockId::new(number, hash.into())
+    }
+
+    /// Pipes the scenario to a new storage.
+    async fn play(scenario: ReorgScenario) -> DatabaseStorage<NoWriteMap> {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+        let storage = DatabaseStorage::new(Arc::new(db));
+
+        let (stream, _client, _requests) = scenario.into_stream::<v1alpha2::Filter>();
+        pipe_to_storage(stream, &storage).await.unwrap();
+        storage
+    }
+
+    /// Returns the canonical chain, from genesis to the highest accepted block.
+    fn canonical_chain(storage: &DatabaseStorage<NoWriteMap>) -> Vec<GlobalBlockId> {
+        let highest = storage.highest_accepted_block().unwrap().unwrap();
+        (0..=highest.number())
+            .map(|number| storage.canonical_block_id(number).unwrap().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pipe_shallow_reorg() {
+        let storage = play(ReorgScenario::shallow()).await;
+
+        let mut expected: Vec<_> = (0..10).map(|number| block_id(number, 0)).collect();
+        expected.extend([block_id(10, 1), block_id(11, 1)]);
+        assert_eq!(canonical_chain(&storage), expected);
+
+        for id in &expected {
+            let status = storage.read_status(id).unwrap();
+            assert_eq!(status, Some(v1alpha2::BlockStatus::AcceptedOnL2));
+        }
+        let status = storage.read_status(&block_id(10, 0)).unwrap();
+        assert_eq!(status, Some(v1alpha2::BlockStatus::Rejected));
+        assert!(!storage.is_canonical(&block_id(10, 0)).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pipe_reorg_to_genesis() {
+        let storage = play(ReorgScenario::to_genesis()).await;
+
+        let expected: Vec<_> = (0..=11).map(|number| block_id(number, 1)).collect();
+        assert_eq!(canonical_chain(&storage), expected);
+
+        for id in &expected {
+            let status = storage.read_status(id).unwrap();
+            assert_eq!(status, Some(v1alpha2::BlockStatus::AcceptedOnL2));
+        }
+        for number in 0..=10 {
+            let status = storage.read_status(&block_id(number, 0)).unwrap();
+            assert_eq!(status, Some(v1alpha2::BlockStatus::Rejected));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_affected_blocks() {
+        let storage = play(ReorgScenario::shallow()).await;
+        let cursor = Cursor {
+            order_key: 9,
+            unique_key: Vec::default(),
+        };
+
+        let expected = vec![block_id(10, 1), block_id(11, 1)];
+        let affected = affected_blocks(&storage, Some(cursor.clone())).unwrap();
+        assert_eq!(affected, expected);
+        let reader = storage.reader_for::<Headers>().unwrap();
+        assert_eq!(affected_blocks(&reader, Some(cursor)).unwrap(), expected);
+        assert_eq!(affected_blocks(&reader, None).unwrap().len(), 12);
+    }
+}
@@ -0,0 +1,165 @@
+//! Read-only storage.
+
+use std::{collections::BTreeSet, path::Path, sync::Arc};
+
+use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
+use apibara_node::db::{
+    libmdbx::{self, Environment, EnvironmentKind},
+    MdbxEnvironmentExt,
+};
+
+use crate::core::GlobalBlockId;
+
+use super::{
+    storage::forward_reads, BlockStats, BlockStatusTransition, Bloom, BodyReader, ChainReader,
+    DatabaseStorage, HeaderReader, L1Handler, ReceiptReader, ScopedReader, StateUpdateReader,
+    StatusHistogram, StorageReader, TableReader, TransactionLocation,
+};
+
+/// A [DatabaseStorage] opened in read-only mode.
+///
+/// It only implements [StorageReader], so it can't be used to write by mistake. Created
+/// with [DatabaseStorage::open_readonly].
+#[derive(Debug, Clone)]
+pub struct ReadOnlyDatabaseStorage<E: EnvironmentKind> {
+    inner: DatabaseStorage<E>,
+}
+
+impl<E: EnvironmentKind> DatabaseStorage<E> {
+    /// Opens the database at `path` in read-only mode.
+    ///
+    /// Use it to read a database from a different process than the one writing to it, for
+    /// example to serve or analyze data while the node is indexing. mdbx supports any
+    /// number of reader processes next to one writer: each read transaction sees the
+    /// database as it was when the transaction started, and later writes become visible to
+    /// new transactions only. Keep read transactions short, since the writer can't reuse
+    /// pages while an older read transaction is open, and the database file grows.
+    ///
+    /// The writer grows the database map as data is added. mdbx remaps it in readers when
+    /// a transaction starts, so starting a transaction can fail if the new size can't be
+    /// mapped. Treat errors from reads as transient and retry with a new transaction.
+    ///
+    /// The database must already exist, with its tables created by the writer.
+    pub fn open_readonly(path: &Path) -> Result<ReadOnlyDatabaseStorage<E>, libmdbx::Error> {
+        let db = Environment::<E>::builder().with_read_only().open(path)?;
+        let inner = DatabaseStorage::new(Arc::new(db));
+        Ok(ReadOnlyDatabaseStorage { inner })
+    }
+}
+
+impl<E: EnvironmentKind> ReadOnlyDatabaseStorage<E> {
+    /// Reads the tables inside `namespace`, see [DatabaseStorage::with_namespace].
+    pub fn with_namespace(self, namespace: &str) -> Self {
+        let inner = self.inner.with_namespace(namespace);
+        ReadOnlyDatabaseStorage { inner }
+    }
+
+    /// Returns a reader that keeps a read transaction open until it's dropped.
+    ///
+    /// See [DatabaseStorage::reader_with_txn].
+    pub fn reader_with_txn(&self) -> Result<ScopedReader<'_, E>, libmdbx::Error> {
+        self.inner.reader_with_txn()
+    }
+}
+
+impl<E: EnvironmentKind> TableReader for ReadOnlyDatabaseStorage<E> {
+    type Error = libmdbx::Error;
+}
+
+impl<E: EnvironmentKind> ChainReader for ReadOnlyDatabaseStorage<E> {
+    forward_reads!(highest_accepted_block, canonical_block_id, is_canonical);
+}
+
+impl<E: EnvironmentKind> HeaderReader for ReadOnlyDatabaseStorage<E> {
+    forward_reads!(read_header);
+}
+
+impl<E: EnvironmentKind> BodyReader for ReadOnlyDatabaseStorage<E> {
+    forward_reads!(read_body);
+}
+
+impl<E: EnvironmentKind> ReceiptReader for ReadOnlyDatabaseStorage<E> {
+    forward_reads!(read_receipts);
+}
+
+impl<E: EnvironmentKind> StateUpdateReader for ReadOnlyDatabaseStorage<E> {
+    forward_reads!(read_state_update);
+}
+
+impl<E: EnvironmentKind> StorageReader for ReadOnlyDatabaseStorage<E> {
+    // the bloom can't be stored, so it's never backfilled.
+    forward_reads!(
+        highest_finalized_block,
+        finality_lag,
+        canonical_gaps,
+        find_common_ancestor,
+        read_status,
+        read_status_history,
+        total_by_status,
+        blocks_by_sequencer,
+        blocks_in_time_range,
+        read_recent_blocks,
+        read_recent_transactions,
+        transaction_location,
+        read_block_events,
+        blocks_matching_bloom,
+        distinct_event_addresses,
+        block_may_contain,
+        read_l1_handlers,
+        read_events_by_selector,
+        events_with_key_prefix,
+        read_events,
+        class_hash_at,
+        contract_deployment_block,
+        blocks_touching_class,
+        nonce_at,
+        read_user_meta,
+        read_block_stats,
+        read_block_at_cursor,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::GlobalBlockId,
+        db::{tables, ChainReader, DatabaseStorage, HeaderReader, StorageWriter},
+    };
+
+    #[test]
+    fn test_read_written_database() {
+        let path = tempdir().unwrap();
+        let hash = v1alpha2::FieldElement::from_u64(1);
+        let id = GlobalBlockId::new(0, hash.clone().into());
+        let header = v1alpha2::BlockHeader {
+            block_hash: Some(hash),
+            ..v1alpha2::BlockHeader::default()
+        };
+
+        {
+            let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+            let txn = db.begin_rw_txn().unwrap();
+            tables::ensure(&txn).unwrap();
+            txn.commit().unwrap();
+            let storage = DatabaseStorage::new(Arc::new(db));
+            let mut txn = storage.begin_txn().unwrap();
+            txn.write_header(&id, header.clone()).unwrap();
+            txn.extend_canonical_chain(&id).unwrap();
+            txn.commit().unwrap();
+        }
+
+        let storage = DatabaseStorage::<NoWriteMap>::open_readonly(path.path()).unwrap();
+        assert_eq!(storage.highest_accepted_block().unwrap(), Some(id));
+        assert_eq!(storage.read_header(&id).unwrap(), Some(header));
+        assert!(storage.is_canonical(&id).unwrap());
+    }
+}
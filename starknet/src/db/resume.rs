@@ -0,0 +1,112 @@
+//! Resume streams from the data already in storage.
+
+use apibara_node::db::{libmdbx::EnvironmentKind, MdbxTransactionExt};
+use apibara_sdk::{Configuration, GapError, GapSource};
+use prost::Message;
+
+use super::{tables, DatabaseStorage, StorageReader};
+
+/// Extend [Configuration] to start streaming after the data already in storage.
+pub trait ConfigurationExt: Sized {
+    /// Set the starting cursor to the highest accepted block in `reader`.
+    ///
+    /// Call it when restarting a node to avoid reprocessing the blocks already stored.
+    /// The cursor includes the block hash, so the server invalidates the block if it was
+    /// reorged out while the node was down. If the storage is empty, the configured
+    /// starting cursor is kept, if any, and the stream starts from genesis otherwise.
+    fn resume_from_storage<R: StorageReader>(self, reader: &R) -> Result<Self, R::Error>;
+}
+
+impl<F> ConfigurationExt for Configuration<F>
+where
+    F: Message + Default,
+{
+    fn resume_from_storage<R: StorageReader>(self, reader: &R) -> Result<Self, R::Error> {
+        match reader.highest_accepted_block()? {
+            None => Ok(self),
+            Some(block_id) => Ok(self.with_starting_cursor(block_id.to_cursor())),
+        }
+    }
+}
+
+/// Reports the gaps in the canonical chain, between its lowest and highest block.
+///
+/// Use it with [apibara_sdk::DataStream::with_gap_refill] to stream the missing blocks
+/// when restarting a node. Blocks before the lowest canonical block, for example when
+/// storage was started from a snapshot, are not reported as missing.
+impl<E: EnvironmentKind> GapSource for DatabaseStorage<E> {
+    fn missing_ranges(&self) -> Result<Vec<(u64, u64)>, GapError> {
+        let reader = self.reader_with_txn()?;
+        let mut cursor = reader
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(reader.namespace)?;
+        let (first, last) = match (cursor.first()?, cursor.last()?) {
+            (Some((first, _)), Some((last, _))) => (first, last),
+            _ => return Ok(Vec::default()),
+        };
+        Ok(reader.canonical_gaps(first, last)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use apibara_sdk::{Configuration, GapSource};
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_id, new_storage},
+        StorageWriter,
+    };
+
+    use super::ConfigurationExt;
+
+    #[test]
+    fn test_resume_from_storage() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let configuration = Configuration::<v1alpha2::Filter>::default()
+            .resume_from_storage(&storage)
+            .unwrap();
+        assert_eq!(configuration.starting_cursor, None);
+
+        let configuration = Configuration::<v1alpha2::Filter>::default()
+            .with_starting_block(7)
+            .resume_from_storage(&storage)
+            .unwrap();
+        assert_eq!(configuration.starting_cursor.unwrap().order_key, 7);
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..5 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let configuration = Configuration::<v1alpha2::Filter>::default()
+            .with_starting_block(7)
+            .resume_from_storage(&storage)
+            .unwrap();
+        assert_eq!(
+            configuration.starting_cursor,
+            Some(block_id(4, 0).to_cursor())
+        );
+    }
+
+    #[test]
+    fn test_missing_ranges() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        assert!(storage.missing_ranges().unwrap().is_empty());
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in [2, 3, 5, 6, 9] {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        // blocks before the lowest canonical block are not missing.
+        assert_eq!(storage.missing_ranges().unwrap(), vec![(4, 4), (7, 8)]);
+    }
+}
@@ -1,10 +1,16 @@
 //! State update data.
 
+use std::io::Cursor;
+
 use apibara_core::starknet::v1alpha2;
-use apibara_node::db::Table;
+use apibara_node::db::{KeyDecodeError, ProstCodec, Table, TableKey};
+use byteorder::{BigEndian, ReadBytesExt};
+use prost::Message;
 
 use crate::core::GlobalBlockId;
 
+use super::StorageReader;
+
 /// Store state updates.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct StateUpdateTable {}
@@ -12,8 +18,227 @@ pub struct StateUpdateTable {}
 impl Table for StateUpdateTable {
     type Key = GlobalBlockId;
     type Value = v1alpha2::StateUpdate;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "StateUpdate"
     }
 }
+
+/// Store the class hash of contracts, by the block that set it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContractClassTable {}
+
+/// A contract address together with a block number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractClassKey {
+    pub contract_address: v1alpha2::FieldElement,
+    pub block_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ContractClass {
+    #[prost(message, tag = "1")]
+    pub class_hash: Option<v1alpha2::FieldElement>,
+    /// Hash of the block that set the class, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "2")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+}
+
+impl ContractClassKey {
+    pub fn new(contract_address: v1alpha2::FieldElement, block_number: u64) -> Self {
+        ContractClassKey {
+            contract_address,
+            block_number,
+        }
+    }
+}
+
+// The key is encoded as:
+// - 32 bytes contract address
+// - 8 bytes big endian representation of the block number
+// so that all entries for a contract are sorted by block number.
+impl TableKey for ContractClassKey {
+    type Encoded = [u8; 40];
+
+    fn encode(&self) -> Self::Encoded {
+        let mut out = [0; 40];
+        out[..32].copy_from_slice(&self.contract_address.to_bytes());
+        out[32..].copy_from_slice(&self.block_number.to_be_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        if b.len() != 40 {
+            return Err(KeyDecodeError::InvalidByteSize {
+                expected: 40,
+                actual: b.len(),
+            });
+        }
+        let mut address = [0; 32];
+        address.copy_from_slice(&b[..32]);
+        let mut cursor = Cursor::new(&b[32..]);
+        let block_number = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        Ok(ContractClassKey::new(
+            v1alpha2::FieldElement::from_bytes(&address),
+            block_number,
+        ))
+    }
+}
+
+impl Table for ContractClassTable {
+    type Key = ContractClassKey;
+    type Value = ContractClass;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "ContractClass"
+    }
+}
+
+/// Store the nonce of contracts, by the block that changed it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonceTable {}
+
+/// A contract address together with a block number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonceKey {
+    pub contract_address: v1alpha2::FieldElement,
+    pub block_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ContractNonce {
+    #[prost(message, tag = "1")]
+    pub nonce: Option<v1alpha2::FieldElement>,
+    /// Hash of the block that changed the nonce, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "2")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+}
+
+impl NonceKey {
+    pub fn new(contract_address: v1alpha2::FieldElement, block_number: u64) -> Self {
+        NonceKey {
+            contract_address,
+            block_number,
+        }
+    }
+}
+
+// The key is encoded as:
+// - 32 bytes contract address
+// - 8 bytes big endian representation of the block number
+// so that all entries for a contract are sorted by block number.
+impl TableKey for NonceKey {
+    type Encoded = [u8; 40];
+
+    fn encode(&self) -> Self::Encoded {
+        let mut out = [0; 40];
+        out[..32].copy_from_slice(&self.contract_address.to_bytes());
+        out[32..].copy_from_slice(&self.block_number.to_be_bytes());
+        out
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        if b.len() != 40 {
+            return Err(KeyDecodeError::InvalidByteSize {
+                expected: 40,
+                actual: b.len(),
+            });
+        }
+        let mut address = [0; 32];
+        address.copy_from_slice(&b[..32]);
+        let mut cursor = Cursor::new(&b[32..]);
+        let block_number = cursor
+            .read_u64::<BigEndian>()
+            .map_err(KeyDecodeError::ReadError)?;
+        Ok(NonceKey::new(
+            v1alpha2::FieldElement::from_bytes(&address),
+            block_number,
+        ))
+    }
+}
+
+impl Table for NonceTable {
+    type Key = NonceKey;
+    type Value = ContractNonce;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "Nonce"
+    }
+}
+
+/// Iterator over the storage diffs of a range of blocks.
+///
+/// Returned by [StorageReader::stream_storage_diffs].
+pub struct StorageDiffs<'a, R: StorageReader> {
+    reader: &'a R,
+    /// The next block to read, or `None` once the iterator is done.
+    next_block: Option<u64>,
+    to: u64,
+    current: Option<(u64, std::vec::IntoIter<v1alpha2::StorageDiff>)>,
+}
+
+impl<'a, R: StorageReader> StorageDiffs<'a, R> {
+    pub(super) fn new(reader: &'a R, from: u64, to: u64) -> Self {
+        StorageDiffs {
+            reader,
+            next_block: Some(from),
+            to,
+            current: None,
+        }
+    }
+
+    /// Returns the storage diffs of the canonical block at the given height, or `None` if
+    /// the canonical chain is shorter.
+    fn read_storage_diffs(
+        &self,
+        number: u64,
+    ) -> Result<Option<Vec<v1alpha2::StorageDiff>>, R::Error> {
+        let block_id = match self.reader.canonical_block_id(number)? {
+            None => return Ok(None),
+            Some(block_id) => block_id,
+        };
+        let storage_diffs = self
+            .reader
+            .read_state_update(&block_id)?
+            .and_then(|state_update| state_update.state_diff)
+            .map(|state_diff| state_diff.storage_diffs)
+            .unwrap_or_default();
+        Ok(Some(storage_diffs))
+    }
+}
+
+impl<'a, R: StorageReader> Iterator for StorageDiffs<'a, R> {
+    type Item = Result<(u64, v1alpha2::StorageDiff), R::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((number, storage_diffs)) = self.current.as_mut() {
+                if let Some(storage_diff) = storage_diffs.next() {
+                    return Some(Ok((*number, storage_diff)));
+                }
+                self.current = None;
+            }
+
+            let number = self.next_block.filter(|number| *number <= self.to)?;
+            match self.read_storage_diffs(number) {
+                Err(err) => {
+                    self.next_block = None;
+                    return Some(Err(err));
+                }
+                Ok(None) => {
+                    self.next_block = None;
+                    return None;
+                }
+                Ok(Some(storage_diffs)) => {
+                    self.next_block = number.checked_add(1);
+                    self.current = Some((number, storage_diffs.into_iter()));
+                }
+            }
+        }
+    }
+}
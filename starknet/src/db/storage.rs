@@ -1,59 +1,402 @@
 //! Abstraction over raw db tables.
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use apibara_core::starknet::v1alpha2;
+use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
 use apibara_node::db::{
-    libmdbx::{self, Environment, EnvironmentKind, Transaction, RW},
-    MdbxErrorExt, MdbxTransactionExt, TableCursor,
+    libmdbx::{self, Environment, EnvironmentKind, Transaction, RO, RW},
+    MdbxErrorExt, MdbxTransactionExt, Table, TableCursor,
 };
+use tokio::sync::watch;
 
-use crate::core::GlobalBlockId;
+use crate::core::{BlockHash, GlobalBlockId, InvalidBlock};
 
 use super::{
-    block::{BlockBody, BlockReceipts, HasherKeys, RawBloom},
+    block::{
+        BlockBody, BlockReceipts, BlockStats, BlockStatusTransition, BlockTimestamp, HasherKeys,
+        HeaderByTimestampKey, RawBloom, SequencerBlock, SequencerKey, StatusHistogram, UserMeta,
+    },
+    capability::{
+        BodyReader, ChainReader, HeaderReader, ReceiptReader, StateUpdateReader, TableReader,
+    },
+    event::{BlockEvents, EventBySelectorKey},
+    state::{ContractClass, ContractClassKey, ContractNonce, NonceKey, StorageDiffs},
     tables,
+    transaction::{
+        BlockL1Handlers, L1Handler, TransactionHashKey, TransactionIndexEntry, TransactionLocation,
+    },
 };
 
 /// Bloom filter over field elements.
 pub type Bloom = bloomfilter::Bloom<v1alpha2::FieldElement>;
 
-/// An object to read chain data from storage.
-pub trait StorageReader {
-    type Error: std::error::Error + Send + Sync + 'static;
+/// Returns true if the field element may be in the bloom filter.
+///
+/// Use this function to query blooms built by [StorageWriter::write_receipts], so that
+/// field elements are hashed the same way on writes and reads.
+pub fn bloom_contains(bloom: &Bloom, fe: &v1alpha2::FieldElement) -> bool {
+    bloom.check(fe)
+}
+
+/// Configure the field elements added to the receipts bloom.
+///
+/// Event addresses and keys are always added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BloomConfig {
+    /// Positions in the event data of extra values to add to the bloom.
+    ///
+    /// The data layout is contract specific, so this is opt-in per deployment: use it to
+    /// filter events by a value that's not in the keys, for example the recipient of a
+    /// transfer. Every position adds one value per event, which makes the bloom fill up
+    /// faster and return false positives more often for all queries. Events with shorter
+    /// data are skipped.
+    pub data_positions: Vec<usize>,
+}
+
+/// Returns the bloom filter of the event addresses and keys in the receipts.
+///
+/// This is the bloom stored by [StorageWriter::write_receipts] with the default
+/// [BloomConfig].
+pub fn receipts_bloom(receipts: &[v1alpha2::TransactionReceipt]) -> Bloom {
+    receipts_bloom_with_config(receipts, &BloomConfig::default())
+}
 
-    /// Returns the highest accepted block that was indexed.
-    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error>;
+/// Returns the bloom filter of the event addresses, keys and the data selected by `config`
+/// in the receipts.
+pub fn receipts_bloom_with_config(
+    receipts: &[v1alpha2::TransactionReceipt],
+    config: &BloomConfig,
+) -> Bloom {
+    // the bloomfilter crate expects a positive bitmapsize and items count.
+    // add 1 to the receipts count to avoid a panic.
+    let estimate_items = receipts.len() * (2 + config.data_positions.len()) + 1;
+    let mut bloom = Bloom::new(256, estimate_items);
+    set_receipts_bloom(&mut bloom, receipts, config);
+    bloom
+}
+
+/// Returns the events in the receipts of a block, together with their index in the block.
+///
+/// Events are ordered by the transaction index of their receipt, then by their position in
+/// the receipt, and indexed from 0 in that order. The order doesn't depend on how the
+/// receipts are sorted in `receipts`, so the block number and index form a stable key
+/// to deduplicate events, for example as an idempotency key when writing them elsewhere.
+pub fn iter_block_events(
+    receipts: &[v1alpha2::TransactionReceipt],
+) -> impl Iterator<Item = (usize, &v1alpha2::Event)> {
+    iter_block_receipt_events(receipts).map(|(index, _, event)| (index, event))
+}
+
+/// Same as [iter_block_events], but also returns the receipt of each event.
+pub(crate) fn iter_block_receipt_events(
+    receipts: &[v1alpha2::TransactionReceipt],
+) -> impl Iterator<Item = (usize, &v1alpha2::TransactionReceipt, &v1alpha2::Event)> {
+    let mut receipts = receipts.iter().collect::<Vec<_>>();
+    receipts.sort_by_key(|receipt| receipt.transaction_index);
+    receipts
+        .into_iter()
+        .flat_map(|receipt| receipt.events.iter().map(move |event| (receipt, event)))
+        .enumerate()
+        .map(|(index, (receipt, event))| (index, receipt, event))
+}
+
+/// Error returned by [DatabaseStorageWriter].
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error(transparent)]
+    Database(#[from] libmdbx::Error),
+    #[error("canonical chain snapshot is not contiguous: expected block {expected}, got {actual}")]
+    NonContiguousSnapshot { expected: u64, actual: u64 },
+    #[error("header doesn't match block {id}")]
+    HeaderMismatch { id: GlobalBlockId },
+    #[error("block {id} doesn't extend the canonical chain tip {tip}")]
+    BrokenChain {
+        id: GlobalBlockId,
+        tip: GlobalBlockId,
+    },
+    #[error("storage already has data")]
+    AlreadyInitialized,
+    #[error("invalid genesis block: {0}")]
+    InvalidGenesis(#[source] InvalidBlock),
+    #[error("genesis block number must be 0, got {0}")]
+    GenesisNumber(u64),
+}
 
+/// An object to read chain data from storage.
+///
+/// The reads of block data are declared by the table group traits, [ChainReader],
+/// [HeaderReader], [BodyReader], [ReceiptReader] and [StateUpdateReader].
+pub trait StorageReader:
+    ChainReader + HeaderReader + BodyReader + ReceiptReader + StateUpdateReader
+{
     /// Returns the highest finalized block that was indexed.
+    ///
+    /// Canonical blocks without a stored status are not finalized.
     fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error>;
 
-    /// Returns the block id for the block at the given height, or `None` if the
-    /// canonical chain is shorter.
-    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error>;
+    /// Returns how many blocks the highest accepted block is ahead of the highest finalized
+    /// block.
+    ///
+    /// Returns `None` if no block was finalized yet.
+    fn finality_lag(&self) -> Result<Option<u64>, Self::Error>;
+
+    /// Returns the ranges of block numbers between `from` and `to` (inclusive) that are
+    /// missing from the canonical chain.
+    ///
+    /// Ranges are inclusive and sorted by number. The range is contiguous if no range is
+    /// returned.
+    fn canonical_gaps(&self, from: u64, to: u64) -> Result<Vec<(u64, u64)>, Self::Error>;
+
+    /// Returns the most recent block that's an ancestor of both `a` and `b`, following the
+    /// headers' parent hash.
+    ///
+    /// A block is considered an ancestor of itself. Returns `None` if the two chains don't
+    /// converge within the stored headers.
+    fn find_common_ancestor(
+        &self,
+        a: &GlobalBlockId,
+        b: &GlobalBlockId,
+    ) -> Result<Option<GlobalBlockId>, Self::Error>;
 
     /// Returns the block status for the given block.
     fn read_status(&self, id: &GlobalBlockId)
         -> Result<Option<v1alpha2::BlockStatus>, Self::Error>;
 
-    /// Returns the block header for the given block.
-    fn read_header(&self, id: &GlobalBlockId)
-        -> Result<Option<v1alpha2::BlockHeader>, Self::Error>;
+    /// Returns the status changes of the given block, oldest first.
+    ///
+    /// The history is empty if the storage doesn't track status history.
+    fn read_status_history(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Vec<BlockStatusTransition>, Self::Error>;
+
+    /// Returns the number of stored blocks by status.
+    ///
+    /// All stored statuses are counted, including rejected blocks and blocks that are not
+    /// part of the canonical chain. This walks the whole status table.
+    fn total_by_status(&self) -> Result<StatusHistogram, Self::Error>;
 
-    /// Returns all transactions in the given block.
-    fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error>;
+    /// Returns the canonical blocks between `from` and `to` (inclusive) produced by the
+    /// given sequencer, sorted by number.
+    ///
+    /// Blocks are indexed by the `sequencer_address` of their header when it's written, so
+    /// blocks whose header has no sequencer address, for example on chains or versions
+    /// that don't report it, are never returned.
+    fn blocks_by_sequencer(
+        &self,
+        sequencer: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error>;
 
-    /// Returns all receipts in the given block together with its bloom filter.
-    fn read_receipts(
+    /// Returns the canonical blocks with a timestamp between `from_ts` and `to_ts`
+    /// (inclusive, in seconds), sorted by number.
+    fn blocks_in_time_range(
+        &self,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error>;
+
+    /// Returns the `n` most recent canonical blocks with their header, newest first.
+    ///
+    /// Returns fewer blocks if the canonical chain is shorter. Blocks without a stored
+    /// header are skipped, and older blocks are returned in their place.
+    fn read_recent_blocks(
+        &self,
+        n: usize,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::BlockHeader)>, Self::Error>;
+
+    /// Returns the `n` most recent transactions in the canonical chain, newest first.
+    ///
+    /// Transactions are returned with the block that contains them, in reverse order inside
+    /// each block. Returns fewer transactions if the canonical chain is shorter.
+    fn read_recent_transactions(
+        &self,
+        n: usize,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Transaction)>, Self::Error>;
+
+    /// Returns the canonical block and index of the transaction with the given hash.
+    ///
+    /// Returns `None` if the transaction is unknown or its block is not canonical.
+    fn transaction_location(
+        &self,
+        hash: &v1alpha2::FieldElement,
+    ) -> Result<Option<TransactionLocation>, Self::Error>;
+
+    /// Returns all events in the given block together with their index in the block.
+    ///
+    /// Events are ordered and indexed like [iter_block_events] does.
+    fn read_block_events(
         &self,
         id: &GlobalBlockId,
-    ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error>;
+    ) -> Result<Vec<(usize, v1alpha2::Event)>, Self::Error>;
 
-    /// Returns the state update for the given block.
-    fn read_state_update(
+    /// Returns the numbers of the canonical blocks between `from` and `to` (inclusive) whose
+    /// receipts bloom may contain `address`.
+    ///
+    /// Blocks without bloom are always returned, since they may contain the address.
+    fn blocks_matching_bloom(
+        &self,
+        address: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<u64>, Self::Error>;
+
+    /// Returns the addresses of the contracts that emitted events in the canonical blocks
+    /// between `from` and `to` (inclusive).
+    ///
+    /// There's no index by address, so this reads the receipts of every block in the
+    /// range. Both the time and the size of the result grow with the range (a mainnet
+    /// block range can contain tens of thousands of contracts), so query large ranges in
+    /// chunks and merge the results.
+    fn distinct_event_addresses(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<BTreeSet<v1alpha2::FieldElement>, Self::Error>;
+
+    /// Returns `true` if the events of the given block may contain `fe`, as address, key or
+    /// data.
+    ///
+    /// Uses the block bloom if it's stored. Blocks without bloom, for example written with
+    /// [DatabaseStorage::with_bloom_enabled] disabled, fall back to an exact scan of the
+    /// receipts, which is much slower on blocks with many events. See
+    /// [DatabaseStorage::with_backfill_bloom_on_read] to store the bloom on the first query.
+    fn block_may_contain(
         &self,
         id: &GlobalBlockId,
-    ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error>;
+        fe: &v1alpha2::FieldElement,
+    ) -> Result<bool, Self::Error>;
+
+    /// Returns an iterator over the storage diffs of the canonical blocks between `from`
+    /// and `to` (inclusive), together with their block number.
+    ///
+    /// State updates are read one block at a time, so only the diffs of the current block
+    /// are held in memory. Diffs are returned in block order, and in the order of the state
+    /// update within a block. The iterator ends early at the end of the canonical chain,
+    /// and after the first error.
+    ///
+    /// [DatabaseStorage] reads each block in new transactions. Use the reader returned by
+    /// [DatabaseStorage::reader_with_txn] to read all blocks in the same transaction.
+    fn stream_storage_diffs(&self, from: u64, to: u64) -> StorageDiffs<'_, Self>
+    where
+        Self: Sized,
+    {
+        StorageDiffs::new(self, from, to)
+    }
+
+    /// Returns the l1 handler transactions in the canonical blocks between `from` and `to`
+    /// (inclusive).
+    fn read_l1_handlers(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, L1Handler)>, Self::Error>;
+
+    /// Returns the events with the given selector (first key) emitted by the canonical blocks
+    /// between `from` and `to` (inclusive).
+    fn read_events_by_selector(
+        &self,
+        selector: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error>;
+
+    /// Returns the events whose selector (first key) starts with `prefix`, emitted by the
+    /// canonical blocks between `from` and `to` (inclusive).
+    ///
+    /// The prefix is matched against the 32 bytes big endian representation of the
+    /// selector, leading zeros included. Use it with structured key schemes, for example
+    /// to read all the events of a namespace. Events are sorted by block number.
+    ///
+    /// There is no prefix index: the query scans the range of the events by selector index
+    /// whose selectors start with `prefix`, seeking to `from` for each selector. The cost
+    /// grows with the number of distinct selectors matching the prefix, so short prefixes
+    /// can be much slower than [StorageReader::read_events_by_selector].
+    fn events_with_key_prefix(
+        &self,
+        prefix: &[u8],
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error>;
+
+    /// Returns the events emitted by the canonical blocks between `from` and `to` (inclusive),
+    /// optionally filtered by contract `address` and `selector` (first key).
+    ///
+    /// The selector is the most selective argument, since it's backed by an index: if
+    /// present, events are read from the selector index and filtered by address in memory.
+    /// Otherwise, the receipts of each block are scanned, skipping blocks whose bloom
+    /// doesn't contain `address`. Without filters, all events in the range are returned.
+    fn read_events(
+        &self,
+        address: Option<&v1alpha2::FieldElement>,
+        selector: Option<&v1alpha2::FieldElement>,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error>;
+
+    /// Returns the class hash of the contract as of the given block, or `None` if the
+    /// contract wasn't deployed yet.
+    fn class_hash_at(
+        &self,
+        contract: &v1alpha2::FieldElement,
+        block: u64,
+    ) -> Result<Option<v1alpha2::FieldElement>, Self::Error>;
+
+    /// Returns the canonical block that deployed the contract, or `None` if it wasn't
+    /// deployed in a stored block.
+    fn contract_deployment_block(
+        &self,
+        contract: &v1alpha2::FieldElement,
+    ) -> Result<Option<GlobalBlockId>, Self::Error>;
+
+    /// Returns the canonical blocks between `from` and `to` (inclusive) where a contract of
+    /// the given class emitted events or changed state, sorted by number.
+    ///
+    /// Contracts are found in the contract class index, which only records deployments, so
+    /// contracts that changed class after deployment are matched by their deployed class.
+    /// The index is keyed by contract address, so the whole `ContractClassTable` is scanned
+    /// to find the contracts of the class, whatever the range.
+    /// There is no index of blocks by contract address: receipts and state updates of every
+    /// block in the range are scanned, skipping the receipts of blocks whose bloom doesn't
+    /// contain any of the contracts. Only use it over bounded ranges.
+    fn blocks_touching_class(
+        &self,
+        class_hash: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error>;
+
+    /// Returns the nonce of the contract as of the given block, or `None` if it was never
+    /// changed.
+    ///
+    /// This is the nonce set by the most recent canonical block, at or before `block`,
+    /// whose state update changed it.
+    fn nonce_at(
+        &self,
+        contract: &v1alpha2::FieldElement,
+        block: u64,
+    ) -> Result<Option<v1alpha2::FieldElement>, Self::Error>;
+
+    /// Returns the data written with [StorageWriter::write_user_meta] for the given block.
+    fn read_user_meta(&self, id: &GlobalBlockId) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Returns the aggregate stats of the given block.
+    ///
+    /// Stats are computed by [StorageWriter::write_body] and [StorageWriter::write_receipts].
+    fn read_block_stats(&self, id: &GlobalBlockId) -> Result<Option<BlockStats>, Self::Error>;
+
+    /// Returns the block at the given stream cursor.
+    ///
+    /// The block is read like [DatabaseStorage::stream_blocks] does. Returns `None` if the
+    /// cursor has no block hash, or if the block was reorged out and is no longer
+    /// canonical at the cursor number.
+    fn read_block_at_cursor(&self, cursor: &Cursor)
+        -> Result<Option<v1alpha2::Block>, Self::Error>;
 }
 
 /// An object to write chain data to storage in a single transaction.
@@ -69,6 +412,11 @@ pub trait StorageWriter {
     /// Removes the given block from the canonical chain.
     fn reject_block_from_canonical_chain(&mut self, id: &GlobalBlockId) -> Result<(), Self::Error>;
 
+    /// Replaces the canonical chain with the given blocks.
+    ///
+    /// The blocks must be sorted and contiguous by number.
+    fn load_canonical_snapshot(&mut self, blocks: &[GlobalBlockId]) -> Result<(), Self::Error>;
+
     /// Writes the block status.
     fn write_status(
         &mut self,
@@ -99,56 +447,819 @@ pub trait StorageWriter {
         id: &GlobalBlockId,
         state_update: v1alpha2::StateUpdate,
     ) -> Result<(), Self::Error>;
+
+    /// Writes the block header, unless a header is already stored for the block.
+    ///
+    /// Returns `true` if the header was written. Only the presence of the block is
+    /// checked, not that the stored header is equal to `header`, so use it to skip
+    /// redundant writes when re-syncing blocks that can't have changed, and
+    /// [StorageWriter::write_header] to replace the data.
+    fn write_header_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        header: v1alpha2::BlockHeader,
+    ) -> Result<bool, Self::Error>;
+
+    /// Writes the transactions in a block, unless they're already stored.
+    ///
+    /// See [StorageWriter::write_header_if_absent].
+    fn write_body_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        body: BlockBody,
+    ) -> Result<bool, Self::Error>;
+
+    /// Writes the receipts in a block, unless they're already stored.
+    ///
+    /// See [StorageWriter::write_header_if_absent].
+    fn write_receipts_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        receipts: Vec<v1alpha2::TransactionReceipt>,
+    ) -> Result<bool, Self::Error>;
+
+    /// Writes the block state update, unless it's already stored.
+    ///
+    /// See [StorageWriter::write_header_if_absent].
+    fn write_state_update_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        state_update: v1alpha2::StateUpdate,
+    ) -> Result<bool, Self::Error>;
+
+    /// Writes consumer-defined data for the given block, replacing any previous data.
+    ///
+    /// The data is opaque to storage. Use it to track processing state in the same
+    /// transaction as the chain data.
+    fn write_user_meta(&mut self, id: &GlobalBlockId, data: Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// An index derived from the block headers, bodies, receipts or state updates.
+///
+/// See [DatabaseStorage::rebuild_index].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    /// The l1 handlers by block, from the bodies.
+    L1Handlers,
+    /// The transactions location by hash, from the bodies.
+    TransactionByHash,
+    /// The events by selector, from the receipts.
+    EventBySelector,
+    /// The blocks by timestamp, from the headers.
+    HeaderByTimestamp,
+    /// The blocks by sequencer, from the headers.
+    Sequencer,
+    /// The classes of deployed contracts, from the state updates.
+    ContractClass,
+    /// The contract nonces, from the state updates.
+    Nonce,
+    /// The block stats, from the bodies and receipts.
+    BlockStats,
+}
+
+impl IndexKind {
+    /// All the indexes.
+    pub const ALL: [IndexKind; 8] = [
+        IndexKind::L1Handlers,
+        IndexKind::TransactionByHash,
+        IndexKind::EventBySelector,
+        IndexKind::HeaderByTimestamp,
+        IndexKind::Sequencer,
+        IndexKind::ContractClass,
+        IndexKind::Nonce,
+        IndexKind::BlockStats,
+    ];
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct DatabaseStorage<E: EnvironmentKind> {
     db: Arc<Environment<E>>,
+    track_status_history: bool,
+    bloom_enabled: bool,
+    bloom_config: BloomConfig,
+    write_validation: bool,
+    reject_deletes_data: bool,
+    backfill_bloom_on_read: bool,
+    namespace: String,
+    finalized_tip: Arc<watch::Sender<Option<GlobalBlockId>>>,
+}
+
+// not derived, the environment kinds are not `Clone`.
+impl<E: EnvironmentKind> Clone for DatabaseStorage<E> {
+    fn clone(&self) -> Self {
+        DatabaseStorage {
+            db: self.db.clone(),
+            track_status_history: self.track_status_history,
+            bloom_enabled: self.bloom_enabled,
+            bloom_config: self.bloom_config.clone(),
+            write_validation: self.write_validation,
+            reject_deletes_data: self.reject_deletes_data,
+            backfill_bloom_on_read: self.backfill_bloom_on_read,
+            namespace: self.namespace.clone(),
+            finalized_tip: self.finalized_tip.clone(),
+        }
+    }
+}
+
+/// A [StorageReader] that runs all reads in the same transaction.
+///
+/// Created with [DatabaseStorage::reader_with_txn], drop it to release the transaction.
+#[derive(Debug)]
+pub struct ScopedReader<'env, E: EnvironmentKind> {
+    pub(super) txn: Transaction<'env, RO, E>,
+    pub(super) namespace: &'env str,
 }
 
 pub struct DatabaseStorageWriter<'env, 'txn, E: EnvironmentKind> {
     txn: Transaction<'env, RW, E>,
+    bloom_enabled: bool,
+    bloom_config: BloomConfig,
+    write_validation: bool,
+    reject_deletes_data: bool,
+    finalized_tip: Arc<watch::Sender<Option<GlobalBlockId>>>,
+    pending_finalized_tip: Option<GlobalBlockId>,
     status_cursor: TableCursor<'txn, tables::BlockStatusTable, RW>,
+    status_history_cursor: Option<TableCursor<'txn, tables::BlockStatusHistoryTable, RW>>,
     header_cursor: TableCursor<'txn, tables::BlockHeaderTable, RW>,
     body_cursor: TableCursor<'txn, tables::BlockBodyTable, RW>,
     receipts_cursor: TableCursor<'txn, tables::BlockReceiptsTable, RW>,
     state_update_cursor: TableCursor<'txn, tables::StateUpdateTable, RW>,
     canonical_chain_cursor: TableCursor<'txn, tables::CanonicalChainTable, RW>,
+    l1_handler_cursor: TableCursor<'txn, tables::L1HandlerIndexTable, RW>,
+    contract_class_cursor: TableCursor<'txn, tables::ContractClassTable, RW>,
+    nonce_cursor: TableCursor<'txn, tables::NonceTable, RW>,
+    event_by_selector_cursor: TableCursor<'txn, tables::EventBySelectorTable, RW>,
+    user_meta_cursor: TableCursor<'txn, tables::UserMetaTable, RW>,
+    header_by_timestamp_cursor: TableCursor<'txn, tables::HeaderByTimestampTable, RW>,
+    block_stats_cursor: TableCursor<'txn, tables::BlockStatsTable, RW>,
+    transaction_by_hash_cursor: TableCursor<'txn, tables::TransactionByHashTable, RW>,
+    sequencer_cursor: TableCursor<'txn, tables::SequencerIndexTable, RW>,
 }
 
 impl<E: EnvironmentKind> DatabaseStorage<E> {
     pub fn new(db: Arc<Environment<E>>) -> Self {
-        DatabaseStorage { db }
+        DatabaseStorage {
+            db,
+            track_status_history: false,
+            bloom_enabled: true,
+            bloom_config: BloomConfig::default(),
+            write_validation: false,
+            reject_deletes_data: false,
+            backfill_bloom_on_read: false,
+            namespace: String::default(),
+            finalized_tip: Arc::new(watch::channel(None).0),
+        }
+    }
+
+    /// Record every status change in the block status history.
+    ///
+    /// This is disabled by default since it adds a write on every status change.
+    pub fn with_status_history(mut self, track_status_history: bool) -> Self {
+        self.track_status_history = track_status_history;
+        self
+    }
+
+    /// Compute a bloom filter of the events in each block's receipts.
+    ///
+    /// Blooms let readers skip blocks without matching events. Disable them if reads never
+    /// filter events, since hashing every event address and key is the most expensive part
+    /// of writing receipts. Blocks written without bloom are always scanned by readers.
+    pub fn with_bloom_enabled(mut self, bloom_enabled: bool) -> Self {
+        self.bloom_enabled = bloom_enabled;
+        self
+    }
+
+    /// Configure the values added to the receipts bloom, see [BloomConfig].
+    ///
+    /// Only affects receipts written after the change, blooms already stored are not
+    /// rebuilt. Query the extra values with [bloom_contains].
+    pub fn with_bloom_config(mut self, bloom_config: BloomConfig) -> Self {
+        self.bloom_config = bloom_config;
+        self
+    }
+
+    /// Check that data matches the block it's written to.
+    ///
+    /// When enabled, [StorageWriter::write_header] fails with [StorageError::HeaderMismatch]
+    /// if the header number or hash don't match the block id.
+    pub fn with_write_validation(mut self, write_validation: bool) -> Self {
+        self.write_validation = write_validation;
+        self
+    }
+
+    /// Delete the data of blocks rejected from the canonical chain.
+    ///
+    /// By default, rejected blocks are only removed from the canonical chain and their data
+    /// is kept. When enabled, [StorageWriter::reject_block_from_canonical_chain] also deletes
+    /// the block header, body, receipts, state update, l1 handlers, user metadata, stats and
+    /// status history (when [DatabaseStorage::with_status_history] is enabled) in the same
+    /// transaction. The block status is kept to record the rejection. Derived indexes
+    /// are not cleaned up, since readers already skip entries of non-canonical blocks.
+    pub fn with_reject_deletes_data(mut self, reject_deletes_data: bool) -> Self {
+        self.reject_deletes_data = reject_deletes_data;
+        self
+    }
+
+    /// Store the bloom of blocks without bloom the first time they're queried with
+    /// [StorageReader::block_may_contain].
+    ///
+    /// Use it with [DatabaseStorage::with_bloom_enabled] disabled to move the bloom cost
+    /// from the write path to the first filtered read of each block. Queries look for the
+    /// stored bloom in a read-only transaction first. Only when the block has no bloom, the
+    /// query scans and hashes all its events and stores the bloom in a write transaction, so
+    /// it's slower than both a bloom lookup and an exact scan, and waits for other writers.
+    /// The bloom is built with the storage [BloomConfig].
+    pub fn with_backfill_bloom_on_read(mut self, backfill_bloom_on_read: bool) -> Self {
+        self.backfill_bloom_on_read = backfill_bloom_on_read;
+        self
+    }
+
+    /// Store the tables inside `namespace`, see [apibara_node::db::namespaced_table_name].
+    ///
+    /// Use it to store multiple chains (for example mainnet and testnet) in the same
+    /// environment, with one storage per chain and a different namespace for each.
+    /// Create the tables of each namespace with [tables::ensure_in] first. Every namespace
+    /// adds one database per table, so open the environment with a builder and a large
+    /// enough `with_max_dbs`, the default `open` only allows 32 databases. Defaults to the
+    /// empty namespace, which uses the plain table names.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        // each namespace is a different chain, with its own finalized tip.
+        self.finalized_tip = Arc::new(watch::channel(None).0);
+        self
+    }
+
+    /// Returns a reader that keeps a read transaction open until it's dropped.
+    ///
+    /// Use it for tight read loops, to avoid opening a transaction on every read. All reads
+    /// see the database as it was when the reader was created.
+    ///
+    /// mdbx can't reuse pages freed by writes committed after the oldest open read
+    /// transaction, so the database file grows while the reader is alive. Don't keep it
+    /// around between unrelated reads.
+    pub fn reader_with_txn(&self) -> Result<ScopedReader<'_, E>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        Ok(ScopedReader {
+            txn,
+            namespace: &self.namespace,
+        })
     }
 
     pub fn begin_txn(&self) -> Result<DatabaseStorageWriter<'_, '_, E>, libmdbx::Error> {
         let txn = self.db.begin_rw_txn()?;
-        let status_cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
-        let header_cursor = txn.open_cursor::<tables::BlockHeaderTable>()?;
-        let body_cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
-        let receipts_cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
-        let state_update_cursor = txn.open_cursor::<tables::StateUpdateTable>()?;
-        let canonical_chain_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+        let namespace = self.namespace.as_str();
+        let status_cursor = txn.open_cursor_in::<tables::BlockStatusTable>(namespace)?;
+        let status_history_cursor = if self.track_status_history {
+            Some(txn.open_cursor_in::<tables::BlockStatusHistoryTable>(namespace)?)
+        } else {
+            None
+        };
+        let header_cursor = txn.open_cursor_in::<tables::BlockHeaderTable>(namespace)?;
+        let body_cursor = txn.open_cursor_in::<tables::BlockBodyTable>(namespace)?;
+        let receipts_cursor = txn.open_cursor_in::<tables::BlockReceiptsTable>(namespace)?;
+        let state_update_cursor = txn.open_cursor_in::<tables::StateUpdateTable>(namespace)?;
+        let canonical_chain_cursor =
+            txn.open_cursor_in::<tables::CanonicalChainTable>(namespace)?;
+        let l1_handler_cursor = txn.open_cursor_in::<tables::L1HandlerIndexTable>(namespace)?;
+        let contract_class_cursor = txn.open_cursor_in::<tables::ContractClassTable>(namespace)?;
+        let nonce_cursor = txn.open_cursor_in::<tables::NonceTable>(namespace)?;
+        let event_by_selector_cursor =
+            txn.open_cursor_in::<tables::EventBySelectorTable>(namespace)?;
+        let user_meta_cursor = txn.open_cursor_in::<tables::UserMetaTable>(namespace)?;
+        let header_by_timestamp_cursor =
+            txn.open_cursor_in::<tables::HeaderByTimestampTable>(namespace)?;
+        let block_stats_cursor = txn.open_cursor_in::<tables::BlockStatsTable>(namespace)?;
+        let transaction_by_hash_cursor =
+            txn.open_cursor_in::<tables::TransactionByHashTable>(namespace)?;
+        let sequencer_cursor = txn.open_cursor_in::<tables::SequencerIndexTable>(namespace)?;
         let writer = DatabaseStorageWriter {
             txn,
+            bloom_enabled: self.bloom_enabled,
+            bloom_config: self.bloom_config.clone(),
+            write_validation: self.write_validation,
+            reject_deletes_data: self.reject_deletes_data,
+            finalized_tip: self.finalized_tip.clone(),
+            pending_finalized_tip: None,
             status_cursor,
+            status_history_cursor,
             header_cursor,
             body_cursor,
             receipts_cursor,
             state_update_cursor,
             canonical_chain_cursor,
+            l1_handler_cursor,
+            contract_class_cursor,
+            nonce_cursor,
+            event_by_selector_cursor,
+            user_meta_cursor,
+            header_by_timestamp_cursor,
+            block_stats_cursor,
+            transaction_by_hash_cursor,
+            sequencer_cursor,
         };
         Ok(writer)
     }
+
+    /// Writes `genesis` as canonical block 0 of an empty storage.
+    ///
+    /// The block and the canonical chain are written in a single transaction. Fails with
+    /// [StorageError::AlreadyInitialized], without writing anything, if the storage already
+    /// has a canonical chain or block headers. Use
+    /// [StorageWriter::load_canonical_snapshot] to start from an existing chain instead.
+    #[tracing::instrument(level = "trace", skip(self, genesis))]
+    pub fn initialize(&self, genesis: v1alpha2::Block) -> Result<(), StorageError> {
+        let id = GlobalBlockId::from_block(&genesis).map_err(StorageError::InvalidGenesis)?;
+        if id.number() != 0 {
+            return Err(StorageError::GenesisNumber(id.number()));
+        }
+        let mut txn = self.begin_txn()?;
+        if txn.canonical_chain_cursor.first()?.is_some() || txn.header_cursor.first()?.is_some() {
+            return Err(StorageError::AlreadyInitialized);
+        }
+        txn.write_block(&id, genesis)?;
+        txn.extend_canonical_chain(&id)?;
+        txn.commit()
+    }
+
+    /// Records the latest known chain tip.
+    ///
+    /// Storage doesn't know the real chain tip, so it's up to the consumer to record it,
+    /// for example from the stream cursors.
+    #[tracing::instrument(level = "trace", skip(self))]
+    pub fn record_chain_tip(&self, number: u64) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor_in::<tables::MetaTable>(&self.namespace)?;
+        let mut meta = cursor.seek_exact(&())?.map(|t| t.1).unwrap_or_default();
+        meta.chain_tip = Some(number);
+        cursor.put(&(), &meta)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns how many blocks the highest accepted block is behind the recorded chain tip.
+    ///
+    /// Returns `None` if no chain tip was recorded or no block was indexed yet.
+    #[tracing::instrument(level = "trace", skip(self))]
+    pub fn tip_lag(&self) -> Result<Option<u64>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let mut meta_cursor = txn.open_cursor_in::<tables::MetaTable>(&self.namespace)?;
+        let mut canon_cursor =
+            txn.open_cursor_in::<tables::CanonicalChainTable>(&self.namespace)?;
+        let chain_tip = meta_cursor.seek_exact(&())?.and_then(|t| t.1.chain_tip);
+        let highest_accepted = canon_cursor.last()?.map(|t| t.0);
+        txn.commit()?;
+        match (chain_tip, highest_accepted) {
+            (Some(chain_tip), Some(highest_accepted)) => {
+                Ok(Some(chain_tip.saturating_sub(highest_accepted)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns a receiver that's updated when the highest finalized block advances.
+    ///
+    /// The finalized tip is tracked as transactions committed by this storage, and its
+    /// clones, finalize canonical blocks: when a transaction writes the
+    /// [v1alpha2::BlockStatus::AcceptedOnL1] status of a canonical block higher than the
+    /// current tip, the tip moves to the highest such block once the transaction is
+    /// committed. Writes made by other processes are not seen. The receiver starts with the
+    /// highest finalized block in storage, or `None` if there's none or it can't be read.
+    pub fn subscribe_finalized_tip(&self) -> watch::Receiver<Option<GlobalBlockId>> {
+        if self.finalized_tip.borrow().is_none() {
+            match self.highest_finalized_block() {
+                Ok(Some(block_id)) => advance_finalized_tip(&self.finalized_tip, block_id),
+                Ok(None) => {}
+                Err(err) => tracing::warn!(err = ?err, "failed to read finalized tip"),
+            }
+        }
+        self.finalized_tip.subscribe()
+    }
+
+    /// Rebuilds the given index for the blocks between `from` and `to` (inclusive).
+    ///
+    /// The index entries of these blocks are deleted, then written again from the data
+    /// stored in the base tables, in a single transaction. Use it to repair an index that
+    /// became inconsistent with the base tables, for example after a bug or an interrupted
+    /// write, without resyncing the chain. Each index is rebuilt independently.
+    ///
+    /// Indexes shared by the blocks at the same height (or containing the same transaction)
+    /// skip rejected blocks and keep the canonical block entries, as if the blocks had been
+    /// written again in order. Indexes keyed by block id are rebuilt for all stored blocks.
+    /// Deleting the entries scans the whole index.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn rebuild_index(&self, which: IndexKind, from: u64, to: u64) -> Result<(), StorageError> {
+        let mut txn = self.begin_txn()?;
+        txn.rebuild_index(which, from, to)?;
+        txn.commit()
+    }
+}
+
+/// Moves the finalized tip to `block_id`, if it's higher than the current tip.
+fn advance_finalized_tip(
+    finalized_tip: &watch::Sender<Option<GlobalBlockId>>,
+    block_id: GlobalBlockId,
+) {
+    finalized_tip.send_if_modified(|tip| match tip {
+        Some(tip) if tip.number() >= block_id.number() => false,
+        _ => {
+            *tip = Some(block_id);
+            true
+        }
+    });
+}
+
+/// Implements the given reads by running each one in the [ScopedReader] returned by
+/// `self.reader_with_txn()`, which starts a new read transaction.
+///
+/// Shared by [DatabaseStorage] and [super::ReadOnlyDatabaseStorage], so that each read is
+/// forwarded in one place. The types in the signatures are resolved where the macro is
+/// used.
+macro_rules! forward_reads {
+    ($($name:ident),* $(,)?) => {
+        $(forward_reads!(@read $name);)*
+    };
+    (@read highest_accepted_block) => {
+        fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.highest_accepted_block()
+        }
+    };
+    (@read canonical_block_id) => {
+        fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.canonical_block_id(number)
+        }
+    };
+    (@read is_canonical) => {
+        fn is_canonical(&self, id: &GlobalBlockId) -> Result<bool, Self::Error> {
+            self.reader_with_txn()?.is_canonical(id)
+        }
+    };
+    (@read read_header) => {
+        fn read_header(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<Option<v1alpha2::BlockHeader>, Self::Error> {
+            self.reader_with_txn()?.read_header(id)
+        }
+    };
+    (@read read_body) => {
+        fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error> {
+            self.reader_with_txn()?.read_body(id)
+        }
+    };
+    (@read read_receipts) => {
+        fn read_receipts(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error> {
+            self.reader_with_txn()?.read_receipts(id)
+        }
+    };
+    (@read read_state_update) => {
+        fn read_state_update(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error> {
+            self.reader_with_txn()?.read_state_update(id)
+        }
+    };
+    (@read highest_finalized_block) => {
+        fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.highest_finalized_block()
+        }
+    };
+    (@read finality_lag) => {
+        fn finality_lag(&self) -> Result<Option<u64>, Self::Error> {
+            self.reader_with_txn()?.finality_lag()
+        }
+    };
+    (@read canonical_gaps) => {
+        fn canonical_gaps(&self, from: u64, to: u64) -> Result<Vec<(u64, u64)>, Self::Error> {
+            self.reader_with_txn()?.canonical_gaps(from, to)
+        }
+    };
+    (@read find_common_ancestor) => {
+        fn find_common_ancestor(
+            &self,
+            a: &GlobalBlockId,
+            b: &GlobalBlockId,
+        ) -> Result<Option<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.find_common_ancestor(a, b)
+        }
+    };
+    (@read read_status) => {
+        fn read_status(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<Option<v1alpha2::BlockStatus>, Self::Error> {
+            self.reader_with_txn()?.read_status(id)
+        }
+    };
+    (@read read_status_history) => {
+        fn read_status_history(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<Vec<BlockStatusTransition>, Self::Error> {
+            self.reader_with_txn()?.read_status_history(id)
+        }
+    };
+    (@read total_by_status) => {
+        fn total_by_status(&self) -> Result<StatusHistogram, Self::Error> {
+            self.reader_with_txn()?.total_by_status()
+        }
+    };
+    (@read blocks_by_sequencer) => {
+        fn blocks_by_sequencer(
+            &self,
+            sequencer: &v1alpha2::FieldElement,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?
+                .blocks_by_sequencer(sequencer, from, to)
+        }
+    };
+    (@read blocks_in_time_range) => {
+        fn blocks_in_time_range(
+            &self,
+            from_ts: u64,
+            to_ts: u64,
+        ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.blocks_in_time_range(from_ts, to_ts)
+        }
+    };
+    (@read read_recent_blocks) => {
+        fn read_recent_blocks(
+            &self,
+            n: usize,
+        ) -> Result<Vec<(GlobalBlockId, v1alpha2::BlockHeader)>, Self::Error> {
+            self.reader_with_txn()?.read_recent_blocks(n)
+        }
+    };
+    (@read read_recent_transactions) => {
+        fn read_recent_transactions(
+            &self,
+            n: usize,
+        ) -> Result<Vec<(GlobalBlockId, v1alpha2::Transaction)>, Self::Error> {
+            self.reader_with_txn()?.read_recent_transactions(n)
+        }
+    };
+    (@read transaction_location) => {
+        fn transaction_location(
+            &self,
+            hash: &v1alpha2::FieldElement,
+        ) -> Result<Option<TransactionLocation>, Self::Error> {
+            self.reader_with_txn()?.transaction_location(hash)
+        }
+    };
+    (@read read_block_events) => {
+        fn read_block_events(
+            &self,
+            id: &GlobalBlockId,
+        ) -> Result<Vec<(usize, v1alpha2::Event)>, Self::Error> {
+            self.reader_with_txn()?.read_block_events(id)
+        }
+    };
+    (@read blocks_matching_bloom) => {
+        fn blocks_matching_bloom(
+            &self,
+            address: &v1alpha2::FieldElement,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<u64>, Self::Error> {
+            self.reader_with_txn()?
+                .blocks_matching_bloom(address, from, to)
+        }
+    };
+    (@read distinct_event_addresses) => {
+        fn distinct_event_addresses(
+            &self,
+            from: u64,
+            to: u64,
+        ) -> Result<BTreeSet<v1alpha2::FieldElement>, Self::Error> {
+            self.reader_with_txn()?.distinct_event_addresses(from, to)
+        }
+    };
+    (@read block_may_contain) => {
+        fn block_may_contain(
+            &self,
+            id: &GlobalBlockId,
+            fe: &v1alpha2::FieldElement,
+        ) -> Result<bool, Self::Error> {
+            self.reader_with_txn()?.block_may_contain(id, fe)
+        }
+    };
+    (@read read_l1_handlers) => {
+        fn read_l1_handlers(
+            &self,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<(GlobalBlockId, L1Handler)>, Self::Error> {
+            self.reader_with_txn()?.read_l1_handlers(from, to)
+        }
+    };
+    (@read read_events_by_selector) => {
+        fn read_events_by_selector(
+            &self,
+            selector: &v1alpha2::FieldElement,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+            self.reader_with_txn()?
+                .read_events_by_selector(selector, from, to)
+        }
+    };
+    (@read events_with_key_prefix) => {
+        fn events_with_key_prefix(
+            &self,
+            prefix: &[u8],
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+            self.reader_with_txn()?
+                .events_with_key_prefix(prefix, from, to)
+        }
+    };
+    (@read read_events) => {
+        fn read_events(
+            &self,
+            address: Option<&v1alpha2::FieldElement>,
+            selector: Option<&v1alpha2::FieldElement>,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+            self.reader_with_txn()?
+                .read_events(address, selector, from, to)
+        }
+    };
+    (@read class_hash_at) => {
+        fn class_hash_at(
+            &self,
+            contract: &v1alpha2::FieldElement,
+            block: u64,
+        ) -> Result<Option<v1alpha2::FieldElement>, Self::Error> {
+            self.reader_with_txn()?.class_hash_at(contract, block)
+        }
+    };
+    (@read contract_deployment_block) => {
+        fn contract_deployment_block(
+            &self,
+            contract: &v1alpha2::FieldElement,
+        ) -> Result<Option<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?.contract_deployment_block(contract)
+        }
+    };
+    (@read blocks_touching_class) => {
+        fn blocks_touching_class(
+            &self,
+            class_hash: &v1alpha2::FieldElement,
+            from: u64,
+            to: u64,
+        ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+            self.reader_with_txn()?
+                .blocks_touching_class(class_hash, from, to)
+        }
+    };
+    (@read nonce_at) => {
+        fn nonce_at(
+            &self,
+            contract: &v1alpha2::FieldElement,
+            block: u64,
+        ) -> Result<Option<v1alpha2::FieldElement>, Self::Error> {
+            self.reader_with_txn()?.nonce_at(contract, block)
+        }
+    };
+    (@read read_user_meta) => {
+        fn read_user_meta(&self, id: &GlobalBlockId) -> Result<Option<Vec<u8>>, Self::Error> {
+            self.reader_with_txn()?.read_user_meta(id)
+        }
+    };
+    (@read read_block_stats) => {
+        fn read_block_stats(&self, id: &GlobalBlockId) -> Result<Option<BlockStats>, Self::Error> {
+            self.reader_with_txn()?.read_block_stats(id)
+        }
+    };
+    (@read read_block_at_cursor) => {
+        fn read_block_at_cursor(
+            &self,
+            cursor: &Cursor,
+        ) -> Result<Option<v1alpha2::Block>, Self::Error> {
+            self.reader_with_txn()?.read_block_at_cursor(cursor)
+        }
+    };
+}
+
+pub(super) use forward_reads;
+
+impl<E: EnvironmentKind> TableReader for DatabaseStorage<E> {
+    type Error = libmdbx::Error;
+}
+
+impl<E: EnvironmentKind> ChainReader for DatabaseStorage<E> {
+    forward_reads!(highest_accepted_block, canonical_block_id, is_canonical);
+}
+
+impl<E: EnvironmentKind> HeaderReader for DatabaseStorage<E> {
+    forward_reads!(read_header);
+}
+
+impl<E: EnvironmentKind> BodyReader for DatabaseStorage<E> {
+    forward_reads!(read_body);
+}
+
+impl<E: EnvironmentKind> ReceiptReader for DatabaseStorage<E> {
+    forward_reads!(read_receipts);
+}
+
+impl<E: EnvironmentKind> StateUpdateReader for DatabaseStorage<E> {
+    forward_reads!(read_state_update);
 }
 
 impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
+    forward_reads!(
+        highest_finalized_block,
+        finality_lag,
+        canonical_gaps,
+        find_common_ancestor,
+        read_status,
+        read_status_history,
+        total_by_status,
+        blocks_by_sequencer,
+        blocks_in_time_range,
+        read_recent_blocks,
+        read_recent_transactions,
+        transaction_location,
+        read_block_events,
+        blocks_matching_bloom,
+        distinct_event_addresses,
+        read_l1_handlers,
+        read_events_by_selector,
+        events_with_key_prefix,
+        read_events,
+        class_hash_at,
+        contract_deployment_block,
+        blocks_touching_class,
+        nonce_at,
+        read_user_meta,
+        read_block_stats,
+        read_block_at_cursor,
+    );
+
+    fn block_may_contain(
+        &self,
+        id: &GlobalBlockId,
+        fe: &v1alpha2::FieldElement,
+    ) -> Result<bool, Self::Error> {
+        if !self.backfill_bloom_on_read {
+            return self.reader_with_txn()?.block_may_contain(id, fe);
+        }
+
+        // most blocks already have a bloom, only take the write transaction to store it.
+        {
+            let reader = self.reader_with_txn()?;
+            let mut cursor = reader
+                .txn
+                .open_cursor_in::<tables::BlockReceiptsTable>(reader.namespace)?;
+            match cursor.seek_exact(id)? {
+                None => return Ok(false),
+                Some((_, block_receipts)) => {
+                    let bloom: Option<Bloom> = block_receipts.bloom.and_then(|b| b.into());
+                    if let Some(bloom) = bloom {
+                        return Ok(bloom_contains(&bloom, fe));
+                    }
+                }
+            }
+        }
+
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor_in::<tables::BlockReceiptsTable>(&self.namespace)?;
+        let mut block_receipts = match cursor.seek_exact(id)? {
+            None => return Ok(false),
+            Some((_, block_receipts)) => block_receipts,
+        };
+        // another writer may have stored the bloom in the meantime.
+        let bloom: Option<Bloom> = block_receipts.bloom.clone().and_then(|b| b.into());
+        if let Some(bloom) = bloom {
+            return Ok(bloom_contains(&bloom, fe));
+        }
+        let bloom = receipts_bloom_with_config(&block_receipts.receipts, &self.bloom_config);
+        let may_contain = bloom_contains(&bloom, fe);
+        block_receipts.bloom = Some(bloom.into());
+        cursor.put(id, &block_receipts)?;
+        txn.commit()?;
+        Ok(may_contain)
+    }
+}
+
+impl<'env, E: EnvironmentKind> TableReader for ScopedReader<'env, E> {
     type Error = libmdbx::Error;
+}
 
+impl<'env, E: EnvironmentKind> ChainReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
     fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
         let block_id = match cursor.last()? {
             None => None,
             Some((number, hash)) => {
@@ -156,265 +1267,3363 @@ impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
                 Some(GlobalBlockId::new(number, hash))
             }
         };
-        txn.commit()?;
         Ok(block_id)
     }
 
-    #[tracing::instrument(level = "trace", skip(self))]
-    fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
-        let mut status_cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
-        let mut maybe_block_id = canon_cursor.last()?;
-        while let Some((block_num, block_hash)) = maybe_block_id {
-            let block_hash = (&block_hash)
-                .try_into()
-                .map_err(libmdbx::Error::decode_error)?;
-            let block_id = GlobalBlockId::new(block_num, block_hash);
-            let (_, status) = status_cursor
-                .seek_exact(&block_id)?
-                .expect("database is in inconsistent state.");
-
-            if status.status().is_finalized() {
-                txn.commit()?;
-                return Ok(Some(block_id));
-            }
-
-            maybe_block_id = canon_cursor.prev()?;
-        }
-        txn.commit()?;
-        Ok(None)
-    }
-
     #[tracing::instrument(level = "trace", skip(self))]
     fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
         match cursor.seek_exact(&number)? {
-            None => {
-                txn.commit()?;
-                Ok(None)
-            }
+            None => Ok(None),
             Some((_, block_hash)) => {
                 let block_hash = (&block_hash)
                     .try_into()
                     .map_err(libmdbx::Error::decode_error)?;
                 let block_id = GlobalBlockId::new(number, block_hash);
-                txn.commit()?;
                 Ok(Some(block_id))
             }
         }
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
-    fn read_status(
-        &self,
-        id: &GlobalBlockId,
-    ) -> Result<Option<v1alpha2::BlockStatus>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
-        let status = cursor.seek_exact(id)?.map(|t| t.1.status());
-        txn.commit()?;
-        Ok(status)
+    fn is_canonical(&self, id: &GlobalBlockId) -> Result<bool, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let is_canonical = match cursor.seek_exact(&id.number())? {
+            None => false,
+            Some((_, block_hash)) => {
+                let block_hash: BlockHash = (&block_hash)
+                    .try_into()
+                    .map_err(libmdbx::Error::decode_error)?;
+                block_hash == *id.hash()
+            }
+        };
+        Ok(is_canonical)
     }
+}
 
+impl<'env, E: EnvironmentKind> HeaderReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
     fn read_header(
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::BlockHeader>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockHeaderTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockHeaderTable>(self.namespace)?;
         let header = cursor.seek_exact(id)?.map(|t| t.1);
-        txn.commit()?;
         Ok(header)
     }
+}
 
+impl<'env, E: EnvironmentKind> BodyReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
     fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockBodyTable>(self.namespace)?;
         let transactions = cursor
             .seek_exact(id)?
             .map(|t| t.1.transactions)
             .unwrap_or_default();
-        txn.commit()?;
         Ok(transactions)
     }
+}
 
+impl<'env, E: EnvironmentKind> ReceiptReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
     fn read_receipts(
         &self,
         id: &GlobalBlockId,
     ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
         let block_receipts_data = cursor.seek_exact(id)?.map(|t| t.1).unwrap_or_default();
         let receipts = block_receipts_data.receipts;
         let bloom = block_receipts_data.bloom.and_then(|b| b.into());
-        txn.commit()?;
         Ok((receipts, bloom))
     }
+}
 
+impl<'env, E: EnvironmentKind> StateUpdateReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
     fn read_state_update(
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::StateUpdateTable>()?;
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::StateUpdateTable>(self.namespace)?;
         let state_update = cursor.seek_exact(id)?.map(|t| t.1);
-        txn.commit()?;
         Ok(state_update)
     }
 }
 
-impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'env, 'txn, E> {
-    type Error = libmdbx::Error;
-
-    #[tracing::instrument(level = "trace", skip(self))]
-    fn commit(self) -> Result<(), Self::Error> {
-        self.txn.commit()?;
-        Ok(())
-    }
-
+impl<'env, E: EnvironmentKind> StorageReader for ScopedReader<'env, E> {
     #[tracing::instrument(level = "trace", skip(self))]
-    fn extend_canonical_chain(&mut self, id: &GlobalBlockId) -> Result<(), Self::Error> {
-        let number = id.number();
-        let hash = id.hash().into();
-        self.canonical_chain_cursor.seek_exact(&number)?;
-        self.canonical_chain_cursor.put(&number, &hash)?;
-        Ok(())
+    fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut status_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatusTable>(self.namespace)?;
+        let mut maybe_block_id = canon_cursor.last()?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            let block_hash = (&block_hash)
+                .try_into()
+                .map_err(libmdbx::Error::decode_error)?;
+            let block_id = GlobalBlockId::new(block_num, block_hash);
+            if is_finalized(&mut status_cursor, &block_id)? {
+                return Ok(Some(block_id));
+            }
+
+            maybe_block_id = canon_cursor.prev()?;
+        }
+        Ok(None)
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
-    fn reject_block_from_canonical_chain(&mut self, id: &GlobalBlockId) -> Result<(), Self::Error> {
-        let number = id.number();
-        let target_hash = id.hash().into();
-        if let Some((_, current_hash)) = self.canonical_chain_cursor.seek_exact(&number)? {
-            if current_hash == target_hash {
-                self.canonical_chain_cursor.del()?;
-                self.write_status(id, v1alpha2::BlockStatus::Rejected)?;
+    fn finality_lag(&self) -> Result<Option<u64>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut status_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatusTable>(self.namespace)?;
+        let mut maybe_block_id = canon_cursor.last()?;
+        let highest_accepted = match &maybe_block_id {
+            None => {
+                return Ok(None);
+            }
+            Some((block_num, _)) => *block_num,
+        };
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            if is_finalized(&mut status_cursor, &block_id)? {
+                return Ok(Some(highest_accepted - block_num));
+            }
+
+            maybe_block_id = canon_cursor.prev()?;
+        }
+        Ok(None)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn canonical_gaps(&self, from: u64, to: u64) -> Result<Vec<(u64, u64)>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut gaps = Vec::default();
+        let mut next = from;
+        let mut maybe_entry = cursor.seek_range(&from)?;
+        while let Some((number, _)) = maybe_entry {
+            if number > to {
+                break;
+            }
+            if number > next {
+                gaps.push((next, number - 1));
             }
+            next = number + 1;
+            maybe_entry = cursor.next()?;
         }
-        Ok(())
+        if next <= to {
+            gaps.push((next, to));
+        }
+        Ok(gaps)
     }
 
-    #[tracing::instrument(level = "trace", skip(self, status))]
-    fn write_status(
-        &mut self,
-        id: &GlobalBlockId,
-        status: v1alpha2::BlockStatus,
-    ) -> Result<(), Self::Error> {
-        let status_v = super::BlockStatus {
-            status: status as i32,
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn find_common_ancestor(
+        &self,
+        a: &GlobalBlockId,
+        b: &GlobalBlockId,
+    ) -> Result<Option<GlobalBlockId>, Self::Error> {
+        let mut header_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockHeaderTable>(self.namespace)?;
+        let mut parent_of = |id: &GlobalBlockId| -> Result<Option<GlobalBlockId>, Self::Error> {
+            let parent_hash = header_cursor
+                .seek_exact(id)?
+                .and_then(|t| t.1.parent_block_hash);
+            match (id.number().checked_sub(1), parent_hash) {
+                (Some(number), Some(hash)) => Ok(Some(GlobalBlockId::new(number, hash.into()))),
+                _ => Ok(None),
+            }
         };
-        self.status_cursor.seek_exact(id)?;
-        self.status_cursor.put(id, &status_v)?;
-        Ok(())
+
+        // step back the higher block (or both, at the same height) until they meet.
+        let (mut a, mut b) = (*a, *b);
+        let ancestor = loop {
+            if a == b {
+                break Some(a);
+            }
+            let (number_a, number_b) = (a.number(), b.number());
+            if number_a >= number_b {
+                match parent_of(&a)? {
+                    None => break None,
+                    Some(parent) => a = parent,
+                }
+            }
+            if number_b >= number_a {
+                match parent_of(&b)? {
+                    None => break None,
+                    Some(parent) => b = parent,
+                }
+            }
+        };
+        Ok(ancestor)
     }
 
-    #[tracing::instrument(level = "trace", skip(self, header))]
-    fn write_header(
-        &mut self,
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_status(
+        &self,
         id: &GlobalBlockId,
-        header: v1alpha2::BlockHeader,
-    ) -> Result<(), Self::Error> {
-        self.header_cursor.seek_exact(id)?;
-        self.header_cursor.put(id, &header)?;
-        Ok(())
+    ) -> Result<Option<v1alpha2::BlockStatus>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatusTable>(self.namespace)?;
+        let status = cursor.seek_exact(id)?.map(|t| t.1.status());
+        Ok(status)
     }
 
-    #[tracing::instrument(level = "trace", skip(self, body))]
-    fn write_body(&mut self, id: &GlobalBlockId, body: BlockBody) -> Result<(), Self::Error> {
-        self.body_cursor.seek_exact(id)?;
-        self.body_cursor.put(id, &body)?;
-        Ok(())
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_status_history(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Vec<BlockStatusTransition>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatusHistoryTable>(self.namespace)?;
+        let transitions = cursor
+            .seek_exact(id)?
+            .map(|t| t.1.transitions)
+            .unwrap_or_default();
+        Ok(transitions)
     }
 
-    #[tracing::instrument(level = "trace", skip(self, receipts))]
-    fn write_receipts(
-        &mut self,
-        id: &GlobalBlockId,
-        receipts: Vec<v1alpha2::TransactionReceipt>,
-    ) -> Result<(), Self::Error> {
-        // compute bloom filter for receipts
-        // the bloomfilter crate expects a positive bitmapsize and items count.
-        // add 1 to the receipts count to avoid a panic.
-        let estimate_items = receipts.len() * 2 + 1;
-        let mut bloom = Bloom::new(256, estimate_items);
-
-        for receipt in receipts.iter() {
-            for event in &receipt.events {
-                if let Some(addr) = &event.from_address {
-                    bloom.set(addr);
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn total_by_status(&self) -> Result<StatusHistogram, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatusTable>(self.namespace)?;
+        let mut histogram = StatusHistogram::default();
+        let mut maybe_entry = cursor.first()?;
+        while let Some((_, status)) = maybe_entry {
+            histogram.add(status.status());
+            maybe_entry = cursor.next()?;
+        }
+        Ok(histogram)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn blocks_by_sequencer(
+        &self,
+        sequencer: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut sequencer_cursor = self
+            .txn
+            .open_cursor_in::<tables::SequencerIndexTable>(self.namespace)?;
+        let mut blocks = Vec::default();
+        let start = SequencerKey::new(sequencer.clone(), from);
+        let mut maybe_entry = sequencer_cursor.seek_range(&start)?;
+        while let Some((key, sequencer_block)) = maybe_entry {
+            if key.sequencer != *sequencer || key.block_number > to {
+                break;
+            }
+            // skip entries written by blocks that are no longer canonical.
+            if let Some((_, canonical_hash)) = canon_cursor.seek_exact(&key.block_number)? {
+                if Some(&canonical_hash) == sequencer_block.block_hash.as_ref() {
+                    blocks.push(GlobalBlockId::new(
+                        key.block_number,
+                        (&canonical_hash).into(),
+                    ));
                 }
-                for key in event.keys.iter() {
-                    bloom.set(key);
+            }
+            maybe_entry = sequencer_cursor.next()?;
+        }
+        Ok(blocks)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn blocks_in_time_range(
+        &self,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut timestamp_cursor = self
+            .txn
+            .open_cursor_in::<tables::HeaderByTimestampTable>(self.namespace)?;
+        let mut blocks = Vec::default();
+        let start = HeaderByTimestampKey::new(from_ts, 0);
+        let mut maybe_entry = timestamp_cursor.seek_range(&start)?;
+        while let Some((key, block_timestamp)) = maybe_entry {
+            if key.timestamp > to_ts {
+                break;
+            }
+            // skip entries written by blocks that are no longer canonical.
+            if let Some((_, canonical_hash)) = canon_cursor.seek_exact(&key.block_number)? {
+                if Some(&canonical_hash) == block_timestamp.block_hash.as_ref() {
+                    blocks.push(GlobalBlockId::new(
+                        key.block_number,
+                        (&canonical_hash).into(),
+                    ));
                 }
             }
+            maybe_entry = timestamp_cursor.next()?;
+        }
+        // timestamps are not strictly increasing.
+        blocks.sort_by_key(|id| id.number());
+        Ok(blocks)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_recent_blocks(
+        &self,
+        n: usize,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::BlockHeader)>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut header_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockHeaderTable>(self.namespace)?;
+        let mut blocks = Vec::with_capacity(n);
+        let mut maybe_entry = if n == 0 { None } else { canon_cursor.last()? };
+        while let Some((block_num, block_hash)) = maybe_entry {
+            let block_hash = (&block_hash)
+                .try_into()
+                .map_err(libmdbx::Error::decode_error)?;
+            let block_id = GlobalBlockId::new(block_num, block_hash);
+            if let Some((_, header)) = header_cursor.seek_exact(&block_id)? {
+                blocks.push((block_id, header));
+            }
+            if blocks.len() == n {
+                break;
+            }
+            maybe_entry = canon_cursor.prev()?;
+        }
+        Ok(blocks)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_recent_transactions(
+        &self,
+        n: usize,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Transaction)>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut body_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockBodyTable>(self.namespace)?;
+        let mut transactions = Vec::with_capacity(n);
+        let mut maybe_entry = if n == 0 { None } else { canon_cursor.last()? };
+        while let Some((block_num, block_hash)) = maybe_entry {
+            let block_hash = (&block_hash)
+                .try_into()
+                .map_err(libmdbx::Error::decode_error)?;
+            let block_id = GlobalBlockId::new(block_num, block_hash);
+            if let Some((_, body)) = body_cursor.seek_exact(&block_id)? {
+                let remaining = n - transactions.len();
+                transactions.extend(
+                    body.transactions
+                        .into_iter()
+                        .rev()
+                        .take(remaining)
+                        .map(|transaction| (block_id, transaction)),
+                );
+            }
+            if transactions.len() == n {
+                break;
+            }
+            maybe_entry = canon_cursor.prev()?;
         }
+        Ok(transactions)
+    }
 
-        let body = BlockReceipts {
-            receipts,
-            bloom: Some(bloom.into()),
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn transaction_location(
+        &self,
+        hash: &v1alpha2::FieldElement,
+    ) -> Result<Option<TransactionLocation>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::TransactionByHashTable>(self.namespace)?;
+        let entry = match cursor.seek_exact(&TransactionHashKey(hash.clone()))? {
+            None => return Ok(None),
+            Some((_, entry)) => entry,
         };
-        self.receipts_cursor.seek_exact(id)?;
-        self.receipts_cursor.put(id, &body)?;
-        Ok(())
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        // the transaction may have been reorged out.
+        match canon_cursor.seek_exact(&entry.block_number)? {
+            Some((_, canonical_hash)) if Some(&canonical_hash) == entry.block_hash.as_ref() => {
+                let block_hash = (&canonical_hash)
+                    .try_into()
+                    .map_err(libmdbx::Error::decode_error)?;
+                Ok(Some(TransactionLocation {
+                    block: GlobalBlockId::new(entry.block_number, block_hash),
+                    index: entry.index as usize,
+                }))
+            }
+            _ => Ok(None),
+        }
     }
 
-    #[tracing::instrument(level = "trace", skip(self, state_update))]
-    fn write_state_update(
-        &mut self,
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_block_events(
+        &self,
         id: &GlobalBlockId,
-        state_update: v1alpha2::StateUpdate,
-    ) -> Result<(), Self::Error> {
-        self.state_update_cursor.seek_exact(id)?;
-        self.state_update_cursor.put(id, &state_update)?;
-        Ok(())
+    ) -> Result<Vec<(usize, v1alpha2::Event)>, Self::Error> {
+        let (receipts, _) = self.read_receipts(id)?;
+        let events = iter_block_events(&receipts)
+            .map(|(index, event)| (index, event.clone()))
+            .collect();
+        Ok(events)
     }
-}
 
-impl From<RawBloom> for Option<Bloom> {
-    fn from(raw: RawBloom) -> Self {
-        if raw.bytes.is_empty() {
-            return None;
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn blocks_matching_bloom(
+        &self,
+        address: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<u64>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut receipts_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
+        let mut blocks = Vec::default();
+        let mut maybe_block_id = canon_cursor.seek_range(&from)?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            if block_num > to {
+                break;
+            }
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            let bloom: Option<Bloom> = receipts_cursor
+                .seek_exact(&block_id)?
+                .and_then(|t| t.1.bloom)
+                .and_then(|b| b.into());
+            match bloom {
+                Some(bloom) if !bloom_contains(&bloom, address) => {}
+                _ => blocks.push(block_num),
+            }
+            maybe_block_id = canon_cursor.next()?;
         }
-        let hasher_keys = raw.hasher_keys?;
-        let sip_keys = [
-            (hasher_keys.hash0_0, hasher_keys.hash0_1),
-            (hasher_keys.hash1_0, hasher_keys.hash1_1),
-        ];
-        let bloom = Bloom::from_existing(
-            &raw.bytes,
-            raw.bitmap_bits,
-            raw.number_of_hash_functions,
-            sip_keys,
-        );
-        Some(bloom)
+        Ok(blocks)
     }
-}
 
-impl From<Bloom> for RawBloom {
-    fn from(bloom: Bloom) -> Self {
-        let bytes = bloom.bitmap();
-        let bitmap_bits = bloom.number_of_bits();
-        let number_of_hash_functions = bloom.number_of_hash_functions();
-        let sip_keys = bloom.sip_keys();
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn distinct_event_addresses(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<BTreeSet<v1alpha2::FieldElement>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut receipts_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
+        let mut addresses = BTreeSet::default();
+        let mut maybe_block_id = canon_cursor.seek_range(&from)?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            if block_num > to {
+                break;
+            }
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            if let Some((_, block_receipts)) = receipts_cursor.seek_exact(&block_id)? {
+                let block_addresses = block_receipts
+                    .receipts
+                    .into_iter()
+                    .flat_map(|receipt| receipt.events)
+                    .filter_map(|event| event.from_address);
+                addresses.extend(block_addresses);
+            }
+            maybe_block_id = canon_cursor.next()?;
+        }
+        Ok(addresses)
+    }
 
-        let hasher_keys = HasherKeys {
-            hash0_0: sip_keys[0].0,
-            hash0_1: sip_keys[0].1,
-            hash1_0: sip_keys[1].0,
-            hash1_1: sip_keys[1].1,
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn block_may_contain(
+        &self,
+        id: &GlobalBlockId,
+        fe: &v1alpha2::FieldElement,
+    ) -> Result<bool, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
+        let block_receipts = match cursor.seek_exact(id)? {
+            None => return Ok(false),
+            Some((_, block_receipts)) => block_receipts,
         };
+        let bloom: Option<Bloom> = block_receipts.bloom.and_then(|b| b.into());
+        if let Some(bloom) = bloom {
+            return Ok(bloom_contains(&bloom, fe));
+        }
+        let may_contain = block_receipts
+            .receipts
+            .iter()
+            .flat_map(|receipt| receipt.events.iter())
+            .any(|event| {
+                event.from_address.as_ref() == Some(fe)
+                    || event.keys.contains(fe)
+                    || event.data.contains(fe)
+            });
+        Ok(may_contain)
+    }
 
-        RawBloom {
-            bytes,
-            bitmap_bits,
-            number_of_hash_functions,
-            hasher_keys: Some(hasher_keys),
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_l1_handlers(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, L1Handler)>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut l1_handler_cursor = self
+            .txn
+            .open_cursor_in::<tables::L1HandlerIndexTable>(self.namespace)?;
+        let mut handlers = Vec::default();
+        let mut maybe_block_id = canon_cursor.seek_range(&from)?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            if block_num > to {
+                break;
+            }
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            if let Some((_, block_handlers)) = l1_handler_cursor.seek_exact(&block_id)? {
+                handlers.extend(
+                    block_handlers
+                        .handlers
+                        .into_iter()
+                        .map(|handler| (block_id, handler)),
+                );
+            }
+            maybe_block_id = canon_cursor.next()?;
+        }
+        Ok(handlers)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_events_by_selector(
+        &self,
+        selector: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut event_cursor = self
+            .txn
+            .open_cursor_in::<tables::EventBySelectorTable>(self.namespace)?;
+        let mut events = Vec::default();
+        let start = EventBySelectorKey::new(selector.clone(), from);
+        let mut maybe_entry = event_cursor.seek_range(&start)?;
+        while let Some((key, block_events)) = maybe_entry {
+            if key.selector != *selector || key.block_number > to {
+                break;
+            }
+            // skip entries written by blocks that are no longer canonical.
+            if let Some((_, canonical_hash)) = canon_cursor.seek_exact(&key.block_number)? {
+                if Some(&canonical_hash) == block_events.block_hash.as_ref() {
+                    let block_id = GlobalBlockId::new(key.block_number, (&canonical_hash).into());
+                    events.extend(
+                        block_events
+                            .events
+                            .into_iter()
+                            .map(|event| (block_id, event)),
+                    );
+                }
+            }
+            maybe_entry = event_cursor.next()?;
+        }
+        Ok(events)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn events_with_key_prefix(
+        &self,
+        prefix: &[u8],
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+        if prefix.len() > 32 {
+            return Ok(Vec::default());
+        }
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut event_cursor = self
+            .txn
+            .open_cursor_in::<tables::EventBySelectorTable>(self.namespace)?;
+        let mut events = Vec::default();
+        let mut start = [0; 32];
+        start[..prefix.len()].copy_from_slice(prefix);
+        let start = EventBySelectorKey::new(v1alpha2::FieldElement::from_bytes(&start), from);
+        let mut maybe_entry = event_cursor.seek_range(&start)?;
+        while let Some((key, block_events)) = maybe_entry {
+            if !key.selector.to_bytes().starts_with(prefix) {
+                break;
+            }
+            if key.block_number < from {
+                let start = EventBySelectorKey::new(key.selector, from);
+                maybe_entry = event_cursor.seek_range(&start)?;
+                continue;
+            }
+            if key.block_number > to {
+                // skip to the next selector.
+                maybe_entry = match next_selector(&key.selector) {
+                    None => None,
+                    Some(selector) => {
+                        event_cursor.seek_range(&EventBySelectorKey::new(selector, from))?
+                    }
+                };
+                continue;
+            }
+            // skip entries written by blocks that are no longer canonical.
+            if let Some((_, canonical_hash)) = canon_cursor.seek_exact(&key.block_number)? {
+                if Some(&canonical_hash) == block_events.block_hash.as_ref() {
+                    let block_id = GlobalBlockId::new(key.block_number, (&canonical_hash).into());
+                    events.extend(
+                        block_events
+                            .events
+                            .into_iter()
+                            .map(|event| (block_id, event)),
+                    );
+                }
+            }
+            maybe_entry = event_cursor.next()?;
+        }
+        events.sort_by_key(|(block_id, _)| block_id.number());
+        Ok(events)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_events(
+        &self,
+        address: Option<&v1alpha2::FieldElement>,
+        selector: Option<&v1alpha2::FieldElement>,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(GlobalBlockId, v1alpha2::Event)>, Self::Error> {
+        let matches_address = |event: &v1alpha2::Event| match address {
+            None => true,
+            Some(address) => event.from_address.as_ref() == Some(address),
+        };
+
+        if let Some(selector) = selector {
+            let mut events = self.read_events_by_selector(selector, from, to)?;
+            events.retain(|(_, event)| matches_address(event));
+            return Ok(events);
+        }
+
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut receipts_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
+        let mut events = Vec::default();
+        let mut maybe_block_id = canon_cursor.seek_range(&from)?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            if block_num > to {
+                break;
+            }
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            if let Some((_, block_receipts)) = receipts_cursor.seek_exact(&block_id)? {
+                let bloom: Option<Bloom> = block_receipts.bloom.and_then(|b| b.into());
+                let skip_block = match (address, bloom) {
+                    (Some(address), Some(bloom)) => !bloom_contains(&bloom, address),
+                    _ => false,
+                };
+                if !skip_block {
+                    let block_events = block_receipts
+                        .receipts
+                        .into_iter()
+                        .flat_map(|receipt| receipt.events)
+                        .filter(|event| matches_address(event))
+                        .map(|event| (block_id, event));
+                    events.extend(block_events);
+                }
+            }
+            maybe_block_id = canon_cursor.next()?;
+        }
+        Ok(events)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn class_hash_at(
+        &self,
+        contract: &v1alpha2::FieldElement,
+        block: u64,
+    ) -> Result<Option<v1alpha2::FieldElement>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut class_cursor = self
+            .txn
+            .open_cursor_in::<tables::ContractClassTable>(self.namespace)?;
+        // move to the first entry after `block`, then walk back to the most recent
+        // canonical entry for the contract.
+        let after = ContractClassKey::new(contract.clone(), block.saturating_add(1));
+        let mut maybe_entry = match class_cursor.seek_range(&after)? {
+            None => class_cursor.last()?,
+            Some(_) => class_cursor.prev()?,
+        };
+        let mut class_hash = None;
+        while let Some((key, class)) = maybe_entry {
+            if key.contract_address != *contract {
+                break;
+            }
+            let canonical_hash = canon_cursor.seek_exact(&key.block_number)?.map(|t| t.1);
+            if canonical_hash.is_some() && canonical_hash == class.block_hash {
+                class_hash = class.class_hash;
+                break;
+            }
+            maybe_entry = class_cursor.prev()?;
+        }
+        Ok(class_hash)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn contract_deployment_block(
+        &self,
+        contract: &v1alpha2::FieldElement,
+    ) -> Result<Option<GlobalBlockId>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut class_cursor = self
+            .txn
+            .open_cursor_in::<tables::ContractClassTable>(self.namespace)?;
+        // the first canonical entry for the contract is the deployment.
+        let start = ContractClassKey::new(contract.clone(), 0);
+        let mut maybe_entry = class_cursor.seek_range(&start)?;
+        while let Some((key, class)) = maybe_entry {
+            if key.contract_address != *contract {
+                break;
+            }
+            let canonical_hash = canon_cursor.seek_exact(&key.block_number)?.map(|t| t.1);
+            if let Some(hash) = canonical_hash.filter(|h| class.block_hash.as_ref() == Some(h)) {
+                let hash = (&hash).try_into().map_err(libmdbx::Error::decode_error)?;
+                return Ok(Some(GlobalBlockId::new(key.block_number, hash)));
+            }
+            maybe_entry = class_cursor.next()?;
+        }
+        Ok(None)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn blocks_touching_class(
+        &self,
+        class_hash: &v1alpha2::FieldElement,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<GlobalBlockId>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut class_cursor = self
+            .txn
+            .open_cursor_in::<tables::ContractClassTable>(self.namespace)?;
+        let mut contracts = BTreeMap::<[u8; 32], v1alpha2::FieldElement>::new();
+        let mut maybe_entry = class_cursor.first()?;
+        while let Some((key, class)) = maybe_entry {
+            if class.class_hash.as_ref() == Some(class_hash) {
+                let canonical_hash = canon_cursor.seek_exact(&key.block_number)?.map(|t| t.1);
+                if canonical_hash.is_some() && canonical_hash == class.block_hash {
+                    contracts.insert(key.contract_address.to_bytes(), key.contract_address);
+                }
+            }
+            maybe_entry = class_cursor.next()?;
+        }
+
+        if contracts.is_empty() {
+            return Ok(Vec::default());
+        }
+        let is_contract = |address: &Option<v1alpha2::FieldElement>| {
+            address
+                .as_ref()
+                .map(|address| contracts.contains_key(&address.to_bytes()))
+                .unwrap_or(false)
+        };
+
+        let mut receipts_cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockReceiptsTable>(self.namespace)?;
+        let mut state_update_cursor = self
+            .txn
+            .open_cursor_in::<tables::StateUpdateTable>(self.namespace)?;
+        let mut blocks = Vec::default();
+        let mut maybe_block_id = canon_cursor.seek_range(&from)?;
+        while let Some((block_num, block_hash)) = maybe_block_id {
+            if block_num > to {
+                break;
+            }
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            let touches_state = state_update_cursor
+                .seek_exact(&block_id)?
+                .and_then(|t| t.1.state_diff)
+                .map(|diff| {
+                    diff.storage_diffs
+                        .iter()
+                        .any(|d| is_contract(&d.contract_address))
+                        || diff
+                            .deployed_contracts
+                            .iter()
+                            .any(|d| is_contract(&d.contract_address))
+                        || diff.nonces.iter().any(|d| is_contract(&d.contract_address))
+                })
+                .unwrap_or(false);
+            let touches_events = !touches_state
+                && match receipts_cursor.seek_exact(&block_id)? {
+                    None => false,
+                    Some((_, block_receipts)) => {
+                        let bloom: Option<Bloom> = block_receipts.bloom.and_then(|b| b.into());
+                        let may_contain = bloom
+                            .map(|bloom| {
+                                contracts
+                                    .values()
+                                    .any(|address| bloom_contains(&bloom, address))
+                            })
+                            .unwrap_or(true);
+                        may_contain
+                            && block_receipts
+                                .receipts
+                                .iter()
+                                .flat_map(|receipt| receipt.events.iter())
+                                .any(|event| is_contract(&event.from_address))
+                    }
+                };
+            if touches_state || touches_events {
+                blocks.push(block_id);
+            }
+            maybe_block_id = canon_cursor.next()?;
+        }
+        Ok(blocks)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn nonce_at(
+        &self,
+        contract: &v1alpha2::FieldElement,
+        block: u64,
+    ) -> Result<Option<v1alpha2::FieldElement>, Self::Error> {
+        let mut canon_cursor = self
+            .txn
+            .open_cursor_in::<tables::CanonicalChainTable>(self.namespace)?;
+        let mut nonce_cursor = self
+            .txn
+            .open_cursor_in::<tables::NonceTable>(self.namespace)?;
+        // same as class_hash_at: walk back from the first entry after `block`.
+        let after = NonceKey::new(contract.clone(), block.saturating_add(1));
+        let mut maybe_entry = match nonce_cursor.seek_range(&after)? {
+            None => nonce_cursor.last()?,
+            Some(_) => nonce_cursor.prev()?,
+        };
+        let mut nonce = None;
+        while let Some((key, entry)) = maybe_entry {
+            if key.contract_address != *contract {
+                break;
+            }
+            let canonical_hash = canon_cursor.seek_exact(&key.block_number)?.map(|t| t.1);
+            if canonical_hash.is_some() && canonical_hash == entry.block_hash {
+                nonce = entry.nonce;
+                break;
+            }
+            maybe_entry = nonce_cursor.prev()?;
+        }
+        Ok(nonce)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_user_meta(&self, id: &GlobalBlockId) -> Result<Option<Vec<u8>>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::UserMetaTable>(self.namespace)?;
+        let data = cursor.seek_exact(id)?.map(|t| t.1.data);
+        Ok(data)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_block_stats(&self, id: &GlobalBlockId) -> Result<Option<BlockStats>, Self::Error> {
+        let mut cursor = self
+            .txn
+            .open_cursor_in::<tables::BlockStatsTable>(self.namespace)?;
+        let stats = cursor.seek_exact(id)?.map(|t| t.1);
+        Ok(stats)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_block_at_cursor(
+        &self,
+        cursor: &Cursor,
+    ) -> Result<Option<v1alpha2::Block>, Self::Error> {
+        if cursor.unique_key.is_empty() {
+            return Ok(None);
+        }
+        let block_id = match GlobalBlockId::try_from(cursor) {
+            Err(_) => return Ok(None),
+            Ok(block_id) => block_id,
+        };
+        if !self.is_canonical(&block_id)? {
+            return Ok(None);
+        }
+        let block = self.read_full_block(&block_id)?;
+        Ok(Some(block))
+    }
+}
+
+impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'env, 'txn, E> {
+    type Error = StorageError;
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn commit(mut self) -> Result<(), Self::Error> {
+        // blocks are usually finalized before they're added to the canonical chain in the
+        // same transaction, so check it only now.
+        let finalized_tip = match self.pending_finalized_tip.take() {
+            None => None,
+            Some(block_id) => {
+                let hash: v1alpha2::FieldElement = block_id.hash().into();
+                match self.canonical_chain_cursor.seek_exact(&block_id.number())? {
+                    Some((_, canonical_hash)) if canonical_hash == hash => Some(block_id),
+                    _ => None,
+                }
+            }
+        };
+        self.txn.commit()?;
+        if let Some(block_id) = finalized_tip {
+            advance_finalized_tip(&self.finalized_tip, block_id);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn extend_canonical_chain(&mut self, id: &GlobalBlockId) -> Result<(), Self::Error> {
+        let number = id.number();
+        let hash = id.hash().into();
+        self.canonical_chain_cursor.seek_exact(&number)?;
+        self.canonical_chain_cursor.put(&number, &hash)?;
+        self.index_canonical_header(id)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn reject_block_from_canonical_chain(&mut self, id: &GlobalBlockId) -> Result<(), Self::Error> {
+        let number = id.number();
+        let target_hash = id.hash().into();
+        if let Some((_, current_hash)) = self.canonical_chain_cursor.seek_exact(&number)? {
+            if current_hash == target_hash {
+                self.canonical_chain_cursor.del()?;
+                self.write_status(id, v1alpha2::BlockStatus::Rejected)?;
+                let header = self.header_cursor.seek_exact(id)?.map(|t| t.1);
+                if let Some(key) = header.as_ref().and_then(HeaderByTimestampKey::from_header) {
+                    self.delete_timestamp_entry(&key, id)?;
+                }
+                if let Some(key) = header.as_ref().and_then(SequencerKey::from_header) {
+                    self.delete_sequencer_entry(&key, id)?;
+                }
+                if self.reject_deletes_data {
+                    self.delete_block_data(id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, blocks))]
+    fn load_canonical_snapshot(&mut self, blocks: &[GlobalBlockId]) -> Result<(), Self::Error> {
+        // validate the whole snapshot before writing anything.
+        for (prev, next) in blocks.iter().zip(blocks.iter().skip(1)) {
+            if next.number() != prev.number() + 1 {
+                return Err(StorageError::NonContiguousSnapshot {
+                    expected: prev.number() + 1,
+                    actual: next.number(),
+                });
+            }
+        }
+
+        while self.canonical_chain_cursor.first()?.is_some() {
+            self.canonical_chain_cursor.del()?;
+        }
+
+        for id in blocks {
+            let number = id.number();
+            let hash = id.hash().into();
+            self.canonical_chain_cursor.put(&number, &hash)?;
+            self.index_canonical_header(id)?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, status))]
+    fn write_status(
+        &mut self,
+        id: &GlobalBlockId,
+        status: v1alpha2::BlockStatus,
+    ) -> Result<(), Self::Error> {
+        let status_v = super::BlockStatus {
+            status: status as i32,
+        };
+        self.status_cursor.seek_exact(id)?;
+        self.status_cursor.put(id, &status_v)?;
+
+        if status == v1alpha2::BlockStatus::AcceptedOnL1 {
+            let is_higher = match &self.pending_finalized_tip {
+                None => true,
+                Some(tip) => tip.number() < id.number(),
+            };
+            if is_higher {
+                self.pending_finalized_tip = Some(*id);
+            }
+        }
+
+        if let Some(history_cursor) = &mut self.status_history_cursor {
+            let mut history = history_cursor
+                .seek_exact(id)?
+                .map(|t| t.1)
+                .unwrap_or_default();
+            // a clock before the epoch is not worth failing the write for.
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            history.transitions.push(BlockStatusTransition {
+                status: status as i32,
+                timestamp,
+            });
+            history_cursor.put(id, &history)?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, header))]
+    fn write_header(
+        &mut self,
+        id: &GlobalBlockId,
+        header: v1alpha2::BlockHeader,
+    ) -> Result<(), Self::Error> {
+        if self.write_validation {
+            let hash_matches = header
+                .block_hash
+                .as_ref()
+                .map(|hash| BlockHash::from(hash) == *id.hash())
+                .unwrap_or(true);
+            if header.block_number != id.number() || !hash_matches {
+                return Err(StorageError::HeaderMismatch { id: *id });
+            }
+        }
+
+        // the header may be written again with a different timestamp or sequencer.
+        let previous = self.header_cursor.seek_exact(id)?.map(|t| t.1);
+        if let Some(key) = previous
+            .as_ref()
+            .and_then(HeaderByTimestampKey::from_header)
+        {
+            self.delete_timestamp_entry(&key, id)?;
+        }
+        if let Some(key) = previous.as_ref().and_then(SequencerKey::from_header) {
+            self.delete_sequencer_entry(&key, id)?;
+        }
+        self.header_cursor.put(id, &header)?;
+        self.index_sequencer(id, &header)?;
+        self.index_timestamp(id, &header)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, body))]
+    fn write_body(&mut self, id: &GlobalBlockId, body: BlockBody) -> Result<(), Self::Error> {
+        self.index_l1_handlers(id, &body.transactions)?;
+        self.index_transaction_hashes(id, &body.transactions)?;
+
+        let mut stats = self.read_stats(id)?;
+        stats.transaction_count = body.transactions.len() as u64;
+        self.block_stats_cursor.put(id, &stats)?;
+
+        self.body_cursor.seek_exact(id)?;
+        self.body_cursor.put(id, &body)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, receipts))]
+    fn write_receipts(
+        &mut self,
+        id: &GlobalBlockId,
+        receipts: Vec<v1alpha2::TransactionReceipt>,
+    ) -> Result<(), Self::Error> {
+        let bloom = if self.bloom_enabled {
+            Some(receipts_bloom_with_config(&receipts, &self.bloom_config).into())
+        } else {
+            None
+        };
+
+        if let Some((_, previous)) = self.receipts_cursor.seek_exact(id)? {
+            self.delete_events_by_selector(id, &previous.receipts)?;
+        }
+        self.index_events_by_selector(id, &receipts)?;
+
+        // receipts replace the ones already stored, so the stats start from scratch.
+        let mut stats = self.read_stats(id)?;
+        stats.total_fee = None;
+        stats.event_count = 0;
+        stats.add_receipts(&receipts);
+        self.block_stats_cursor.put(id, &stats)?;
+
+        let body = BlockReceipts { receipts, bloom };
+        self.receipts_cursor.seek_exact(id)?;
+        self.receipts_cursor.put(id, &body)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, state_update))]
+    fn write_state_update(
+        &mut self,
+        id: &GlobalBlockId,
+        state_update: v1alpha2::StateUpdate,
+    ) -> Result<(), Self::Error> {
+        self.index_contract_classes(id, &state_update)?;
+        self.index_nonces(id, &state_update)?;
+
+        self.state_update_cursor.seek_exact(id)?;
+        self.state_update_cursor.put(id, &state_update)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, header))]
+    fn write_header_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        header: v1alpha2::BlockHeader,
+    ) -> Result<bool, Self::Error> {
+        if self.header_cursor.seek_exact(id)?.is_some() {
+            return Ok(false);
+        }
+        self.write_header(id, header)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, body))]
+    fn write_body_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        body: BlockBody,
+    ) -> Result<bool, Self::Error> {
+        if self.body_cursor.seek_exact(id)?.is_some() {
+            return Ok(false);
+        }
+        self.write_body(id, body)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, receipts))]
+    fn write_receipts_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        receipts: Vec<v1alpha2::TransactionReceipt>,
+    ) -> Result<bool, Self::Error> {
+        if self.receipts_cursor.seek_exact(id)?.is_some() {
+            return Ok(false);
+        }
+        self.write_receipts(id, receipts)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, state_update))]
+    fn write_state_update_if_absent(
+        &mut self,
+        id: &GlobalBlockId,
+        state_update: v1alpha2::StateUpdate,
+    ) -> Result<bool, Self::Error> {
+        if self.state_update_cursor.seek_exact(id)?.is_some() {
+            return Ok(false);
+        }
+        self.write_state_update(id, state_update)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, data))]
+    fn write_user_meta(&mut self, id: &GlobalBlockId, data: Vec<u8>) -> Result<(), Self::Error> {
+        let meta = UserMeta { data };
+        self.user_meta_cursor.seek_exact(id)?;
+        self.user_meta_cursor.put(id, &meta)?;
+        Ok(())
+    }
+}
+
+impl<'env, 'txn, E: EnvironmentKind> DatabaseStorageWriter<'env, 'txn, E> {
+    /// Adds the block to the timestamp index.
+    fn index_timestamp(
+        &mut self,
+        id: &GlobalBlockId,
+        header: &v1alpha2::BlockHeader,
+    ) -> Result<(), libmdbx::Error> {
+        if let Some(key) = HeaderByTimestampKey::from_header(header) {
+            let block_timestamp = BlockTimestamp {
+                block_hash: Some(id.hash().into()),
+            };
+            self.header_by_timestamp_cursor.seek_exact(&key)?;
+            self.header_by_timestamp_cursor
+                .put(&key, &block_timestamp)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the header of a block that became canonical to the timestamp and sequencer
+    /// indices.
+    ///
+    /// Entries are deleted when a block is rejected, and blocks at the same height share
+    /// the entry keys, so a block that becomes canonical again needs its entries back. Does
+    /// nothing if the header is not stored yet, it's indexed when it's written.
+    fn index_canonical_header(&mut self, id: &GlobalBlockId) -> Result<(), libmdbx::Error> {
+        let header = match self.header_cursor.seek_exact(id)? {
+            None => return Ok(()),
+            Some((_, header)) => header,
+        };
+        self.index_sequencer(id, &header)?;
+        self.index_timestamp(id, &header)?;
+        Ok(())
+    }
+
+    /// Adds the block to the sequencer index.
+    fn index_sequencer(
+        &mut self,
+        id: &GlobalBlockId,
+        header: &v1alpha2::BlockHeader,
+    ) -> Result<(), libmdbx::Error> {
+        if let Some(key) = SequencerKey::from_header(header) {
+            let sequencer_block = SequencerBlock {
+                block_hash: Some(id.hash().into()),
+            };
+            self.sequencer_cursor.seek_exact(&key)?;
+            self.sequencer_cursor.put(&key, &sequencer_block)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the l1 handlers index entry of the block.
+    fn index_l1_handlers(
+        &mut self,
+        id: &GlobalBlockId,
+        transactions: &[v1alpha2::Transaction],
+    ) -> Result<(), libmdbx::Error> {
+        // index l1 handlers by block. since the index is keyed by block id, rejected blocks
+        // are never returned by readers walking the canonical chain.
+        let handlers = BlockL1Handlers {
+            handlers: transactions
+                .iter()
+                .filter_map(L1Handler::from_transaction)
+                .collect(),
+        };
+        self.l1_handler_cursor.seek_exact(id)?;
+        self.l1_handler_cursor.put(id, &handlers)?;
+        Ok(())
+    }
+
+    /// Points the hashes of the given transactions to the block.
+    fn index_transaction_hashes(
+        &mut self,
+        id: &GlobalBlockId,
+        transactions: &[v1alpha2::Transaction],
+    ) -> Result<(), libmdbx::Error> {
+        // a transaction included again after a reorg points to the latest block written.
+        for (index, transaction) in transactions.iter().enumerate() {
+            let hash = match transaction.meta.as_ref().and_then(|m| m.hash.clone()) {
+                None => continue,
+                Some(hash) => hash,
+            };
+            let key = TransactionHashKey(hash);
+            let entry = TransactionIndexEntry {
+                block_number: id.number(),
+                block_hash: Some(id.hash().into()),
+                index: index as u64,
+            };
+            self.transaction_by_hash_cursor.seek_exact(&key)?;
+            self.transaction_by_hash_cursor.put(&key, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the events by selector index entries of the block.
+    fn index_events_by_selector(
+        &mut self,
+        id: &GlobalBlockId,
+        receipts: &[v1alpha2::TransactionReceipt],
+    ) -> Result<(), libmdbx::Error> {
+        for (selector, events) in group_events_by_selector(receipts) {
+            let key = EventBySelectorKey::new(selector, id.number());
+            let block_events = BlockEvents {
+                block_hash: Some(id.hash().into()),
+                events,
+            };
+            self.event_by_selector_cursor.seek_exact(&key)?;
+            self.event_by_selector_cursor.put(&key, &block_events)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the events by selector index entries written by the block for the given receipts.
+    ///
+    /// Entries written by another block at the same height are kept.
+    fn delete_events_by_selector(
+        &mut self,
+        id: &GlobalBlockId,
+        receipts: &[v1alpha2::TransactionReceipt],
+    ) -> Result<(), libmdbx::Error> {
+        let hash: v1alpha2::FieldElement = id.hash().into();
+        for (selector, _) in group_events_by_selector(receipts) {
+            let key = EventBySelectorKey::new(selector, id.number());
+            if let Some((_, block_events)) = self.event_by_selector_cursor.seek_exact(&key)? {
+                if block_events.block_hash.as_ref() == Some(&hash) {
+                    self.event_by_selector_cursor.del()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the contracts deployed in the block to the contract class index.
+    fn index_contract_classes(
+        &mut self,
+        id: &GlobalBlockId,
+        state_update: &v1alpha2::StateUpdate,
+    ) -> Result<(), libmdbx::Error> {
+        let deployed_contracts = state_update
+            .state_diff
+            .iter()
+            .flat_map(|diff| diff.deployed_contracts.iter());
+        for deployed in deployed_contracts {
+            let contract_address = match &deployed.contract_address {
+                None => continue,
+                Some(address) => address.clone(),
+            };
+            let key = ContractClassKey::new(contract_address, id.number());
+            let class = ContractClass {
+                class_hash: deployed.class_hash.clone(),
+                block_hash: Some(id.hash().into()),
+            };
+            self.contract_class_cursor.seek_exact(&key)?;
+            self.contract_class_cursor.put(&key, &class)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the nonces updated in the block to the nonce index.
+    fn index_nonces(
+        &mut self,
+        id: &GlobalBlockId,
+        state_update: &v1alpha2::StateUpdate,
+    ) -> Result<(), libmdbx::Error> {
+        let nonces = state_update
+            .state_diff
+            .iter()
+            .flat_map(|diff| diff.nonces.iter());
+        for update in nonces {
+            let contract_address = match &update.contract_address {
+                None => continue,
+                Some(address) => address.clone(),
+            };
+            let key = NonceKey::new(contract_address, id.number());
+            let nonce = ContractNonce {
+                nonce: update.nonce.clone(),
+                block_hash: Some(id.hash().into()),
+            };
+            self.nonce_cursor.seek_exact(&key)?;
+            self.nonce_cursor.put(&key, &nonce)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the timestamp index entry at `key`, if it belongs to the given block.
+    fn delete_timestamp_entry(
+        &mut self,
+        key: &HeaderByTimestampKey,
+        id: &GlobalBlockId,
+    ) -> Result<(), StorageError> {
+        let block_hash = Some(id.hash().into());
+        if let Some((_, block_timestamp)) = self.header_by_timestamp_cursor.seek_exact(key)? {
+            if block_timestamp.block_hash == block_hash {
+                self.header_by_timestamp_cursor.del()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the sequencer index entry at `key`, if it belongs to the given block.
+    fn delete_sequencer_entry(
+        &mut self,
+        key: &SequencerKey,
+        id: &GlobalBlockId,
+    ) -> Result<(), StorageError> {
+        let block_hash = Some(id.hash().into());
+        if let Some((_, sequencer_block)) = self.sequencer_cursor.seek_exact(key)? {
+            if sequencer_block.block_hash == block_hash {
+                self.sequencer_cursor.del()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the data stored for the given block.
+    fn delete_block_data(&mut self, id: &GlobalBlockId) -> Result<(), StorageError> {
+        if self.header_cursor.seek_exact(id)?.is_some() {
+            self.header_cursor.del()?;
+        }
+        if self.body_cursor.seek_exact(id)?.is_some() {
+            self.body_cursor.del()?;
+        }
+        if self.receipts_cursor.seek_exact(id)?.is_some() {
+            self.receipts_cursor.del()?;
+        }
+        if self.state_update_cursor.seek_exact(id)?.is_some() {
+            self.state_update_cursor.del()?;
+        }
+        if self.l1_handler_cursor.seek_exact(id)?.is_some() {
+            self.l1_handler_cursor.del()?;
+        }
+        if self.user_meta_cursor.seek_exact(id)?.is_some() {
+            self.user_meta_cursor.del()?;
+        }
+        if self.block_stats_cursor.seek_exact(id)?.is_some() {
+            self.block_stats_cursor.del()?;
+        }
+        if let Some(history_cursor) = &mut self.status_history_cursor {
+            if history_cursor.seek_exact(id)?.is_some() {
+                history_cursor.del()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the stats stored for the given block, positioning the stats cursor on it.
+    fn read_stats(&mut self, id: &GlobalBlockId) -> Result<BlockStats, libmdbx::Error> {
+        let stats = self
+            .block_stats_cursor
+            .seek_exact(id)?
+            .map(|t| t.1)
+            .unwrap_or_default();
+        Ok(stats)
+    }
+
+    /// Same as [StorageWriter::extend_canonical_chain], but checks that the block extends
+    /// the current tip.
+    ///
+    /// Fails with [StorageError::BrokenChain] if the block number doesn't follow the tip, or
+    /// if `parent_hash` (the parent hash from the block header) isn't the tip hash. Use it
+    /// while syncing to catch skipped or misordered blocks. Extending an empty chain is
+    /// always accepted.
+    #[tracing::instrument(level = "trace", skip(self))]
+    pub fn extend_canonical_chain_checked(
+        &mut self,
+        id: &GlobalBlockId,
+        parent_hash: &v1alpha2::FieldElement,
+    ) -> Result<(), StorageError> {
+        if let Some((tip_number, tip_hash)) = self.canonical_chain_cursor.last()? {
+            if tip_number + 1 != id.number() || tip_hash != *parent_hash {
+                let tip_hash = (&tip_hash)
+                    .try_into()
+                    .map_err(libmdbx::Error::decode_error)?;
+                return Err(StorageError::BrokenChain {
+                    id: *id,
+                    tip: GlobalBlockId::new(tip_number, tip_hash),
+                });
+            }
+        }
+        self.extend_canonical_chain(id)
+    }
+
+    /// Writes the status, header, transactions, receipts and state update of `block`.
+    ///
+    /// The block is not added to the canonical chain.
+    pub fn write_block(
+        &mut self,
+        id: &GlobalBlockId,
+        block: v1alpha2::Block,
+    ) -> Result<(), StorageError> {
+        let status = block.status();
+        let (transactions, receipts) = block
+            .transactions
+            .into_iter()
+            .map(|t| (t.transaction, t.receipt))
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        self.write_status(id, status)?;
+        if let Some(header) = block.header {
+            self.write_header(id, header)?;
+        }
+        let body = BlockBody {
+            transactions: transactions.into_iter().flatten().collect(),
+        };
+        self.write_body(id, body)?;
+        self.write_receipts(id, receipts.into_iter().flatten().collect())?;
+        if let Some(state_update) = block.state_update {
+            self.write_state_update(id, state_update)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the given receipts to the receipts already stored for the block.
+    ///
+    /// The block bloom filter is updated incrementally with the new receipts only.
+    /// Receipts already stored (same transaction hash) are skipped.
+    #[tracing::instrument(level = "trace", skip(self, extra))]
+    pub fn append_receipts(
+        &mut self,
+        id: &GlobalBlockId,
+        extra: Vec<v1alpha2::TransactionReceipt>,
+    ) -> Result<(), StorageError> {
+        let mut block_receipts = self
+            .receipts_cursor
+            .seek_exact(id)?
+            .map(|t| t.1)
+            .unwrap_or_default();
+
+        let extra: Vec<_> = extra
+            .into_iter()
+            .filter(|receipt| {
+                !block_receipts
+                    .receipts
+                    .iter()
+                    .any(|existing| existing.transaction_hash == receipt.transaction_hash)
+            })
+            .collect();
+
+        if extra.is_empty() {
+            return Ok(());
+        }
+
+        // a bloom is only kept if it contains all receipts, so drop it if blooms are disabled.
+        let existing_bloom: Option<Bloom> = block_receipts.bloom.take().and_then(|b| b.into());
+        let bloom = match existing_bloom {
+            _ if !self.bloom_enabled => None,
+            Some(mut bloom) => {
+                set_receipts_bloom(&mut bloom, &extra, &self.bloom_config);
+                Some(bloom)
+            }
+            None => {
+                // the bloomfilter crate expects a positive bitmapsize and items count.
+                let items_per_receipt = 2 + self.bloom_config.data_positions.len();
+                let estimate_items =
+                    (block_receipts.receipts.len() + extra.len()) * items_per_receipt + 1;
+                let mut bloom = Bloom::new(256, estimate_items);
+                set_receipts_bloom(&mut bloom, &block_receipts.receipts, &self.bloom_config);
+                set_receipts_bloom(&mut bloom, &extra, &self.bloom_config);
+                Some(bloom)
+            }
+        };
+
+        for (selector, events) in group_events_by_selector(&extra) {
+            let key = EventBySelectorKey::new(selector, id.number());
+            let mut block_events = self
+                .event_by_selector_cursor
+                .seek_exact(&key)?
+                .map(|t| t.1)
+                .unwrap_or_default();
+            let hash: v1alpha2::FieldElement = id.hash().into();
+            if block_events.block_hash.as_ref() == Some(&hash) {
+                block_events.events.extend(events);
+            } else {
+                // the entry was written by another block at the same height, replace it.
+                block_events.block_hash = Some(hash);
+                block_events.events = events;
+            }
+            self.event_by_selector_cursor.put(&key, &block_events)?;
+        }
+
+        let mut stats = self.read_stats(id)?;
+        stats.add_receipts(&extra);
+        self.block_stats_cursor.put(id, &stats)?;
+
+        block_receipts.receipts.extend(extra);
+        block_receipts.bloom = bloom.map(|bloom| bloom.into());
+        self.receipts_cursor.put(id, &block_receipts)?;
+        Ok(())
+    }
+
+    /// Same as [DatabaseStorage::rebuild_index], inside this transaction.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn rebuild_index(
+        &mut self,
+        which: IndexKind,
+        from: u64,
+        to: u64,
+    ) -> Result<(), StorageError> {
+        self.clear_index(which, from, to)?;
+        match which {
+            IndexKind::L1Handlers => {
+                for (id, body) in blocks_in_range(&mut self.body_cursor, from, to)? {
+                    self.index_l1_handlers(&id, &body.transactions)?;
+                }
+            }
+            IndexKind::TransactionByHash => {
+                let bodies = blocks_in_range(&mut self.body_cursor, from, to)?;
+                for (id, body) in self.indexable_blocks(bodies)? {
+                    self.index_transaction_hashes(&id, &body.transactions)?;
+                }
+            }
+            IndexKind::EventBySelector => {
+                let receipts = blocks_in_range(&mut self.receipts_cursor, from, to)?;
+                for (id, receipts) in self.indexable_blocks(receipts)? {
+                    self.index_events_by_selector(&id, &receipts.receipts)?;
+                }
+            }
+            IndexKind::HeaderByTimestamp => {
+                let headers = blocks_in_range(&mut self.header_cursor, from, to)?;
+                for (id, header) in self.indexable_blocks(headers)? {
+                    self.index_timestamp(&id, &header)?;
+                }
+            }
+            IndexKind::Sequencer => {
+                let headers = blocks_in_range(&mut self.header_cursor, from, to)?;
+                for (id, header) in self.indexable_blocks(headers)? {
+                    self.index_sequencer(&id, &header)?;
+                }
+            }
+            IndexKind::ContractClass => {
+                let state_updates = blocks_in_range(&mut self.state_update_cursor, from, to)?;
+                for (id, state_update) in self.indexable_blocks(state_updates)? {
+                    self.index_contract_classes(&id, &state_update)?;
+                }
+            }
+            IndexKind::Nonce => {
+                let state_updates = blocks_in_range(&mut self.state_update_cursor, from, to)?;
+                for (id, state_update) in self.indexable_blocks(state_updates)? {
+                    self.index_nonces(&id, &state_update)?;
+                }
+            }
+            IndexKind::BlockStats => {
+                // same as writing the body, then the receipts.
+                for (id, body) in blocks_in_range(&mut self.body_cursor, from, to)? {
+                    let mut stats = self.read_stats(&id)?;
+                    stats.transaction_count = body.transactions.len() as u64;
+                    self.block_stats_cursor.put(&id, &stats)?;
+                }
+                for (id, receipts) in blocks_in_range(&mut self.receipts_cursor, from, to)? {
+                    let mut stats = self.read_stats(&id)?;
+                    stats.add_receipts(&receipts.receipts);
+                    self.block_stats_cursor.put(&id, &stats)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the entries of the given index for the blocks between `from` and `to`
+    /// (inclusive).
+    fn clear_index(&mut self, which: IndexKind, from: u64, to: u64) -> Result<(), libmdbx::Error> {
+        let in_range = |number: u64| from <= number && number <= to;
+        match which {
+            IndexKind::L1Handlers => {
+                delete_entries(&mut self.l1_handler_cursor, |id, _| in_range(id.number()))
+            }
+            IndexKind::TransactionByHash => {
+                delete_entries(&mut self.transaction_by_hash_cursor, |_, entry| {
+                    in_range(entry.block_number)
+                })
+            }
+            IndexKind::EventBySelector => {
+                delete_entries(&mut self.event_by_selector_cursor, |key, _| {
+                    in_range(key.block_number)
+                })
+            }
+            IndexKind::HeaderByTimestamp => {
+                delete_entries(&mut self.header_by_timestamp_cursor, |key, _| {
+                    in_range(key.block_number)
+                })
+            }
+            IndexKind::Sequencer => delete_entries(&mut self.sequencer_cursor, |key, _| {
+                in_range(key.block_number)
+            }),
+            IndexKind::ContractClass => {
+                delete_entries(&mut self.contract_class_cursor, |key, _| {
+                    in_range(key.block_number)
+                })
+            }
+            IndexKind::Nonce => {
+                delete_entries(&mut self.nonce_cursor, |key, _| in_range(key.block_number))
+            }
+            IndexKind::BlockStats => {
+                delete_entries(&mut self.block_stats_cursor, |id, _| in_range(id.number()))
+            }
+        }
+    }
+
+    /// Returns the blocks that have entries in indexes shared by all blocks at the same height
+    /// or containing the same transaction.
+    ///
+    /// Rejected blocks are skipped, and the canonical block comes last among the blocks at
+    /// the same height, so that its entries replace the entries of the other blocks.
+    fn indexable_blocks<V>(
+        &mut self,
+        blocks: Vec<(GlobalBlockId, V)>,
+    ) -> Result<Vec<(GlobalBlockId, V)>, libmdbx::Error> {
+        let rejected = v1alpha2::BlockStatus::Rejected as i32;
+        let mut indexable = Vec::with_capacity(blocks.len());
+        for (id, value) in blocks {
+            let status = self.status_cursor.seek_exact(&id)?.map(|t| t.1.status);
+            if status == Some(rejected) {
+                continue;
+            }
+            let hash: v1alpha2::FieldElement = id.hash().into();
+            let is_canonical = match self.canonical_chain_cursor.seek_exact(&id.number())? {
+                Some((_, canonical_hash)) => canonical_hash == hash,
+                None => false,
+            };
+            indexable.push((id, is_canonical, value));
+        }
+        // the blocks are sorted by number already, and the sort is stable.
+        indexable.sort_by_key(|(id, is_canonical, _)| (id.number(), *is_canonical));
+        Ok(indexable
+            .into_iter()
+            .map(|(id, _, value)| (id, value))
+            .collect())
+    }
+}
+
+/// Returns true if the stored status of the block is finalized.
+///
+/// Blocks without a stored status, for example canonical blocks loaded with
+/// [StorageWriter::load_canonical_snapshot], are not finalized.
+fn is_finalized(
+    status_cursor: &mut TableCursor<'_, tables::BlockStatusTable, RO>,
+    id: &GlobalBlockId,
+) -> Result<bool, libmdbx::Error> {
+    let status = status_cursor.seek_exact(id)?.map(|t| t.1);
+    Ok(status.map(|s| s.status().is_finalized()).unwrap_or(false))
+}
+
+/// Returns the entries of a table keyed by block id for the blocks between `from` and `to`
+/// (inclusive), sorted by block number.
+fn blocks_in_range<T>(
+    cursor: &mut TableCursor<'_, T, RW>,
+    from: u64,
+    to: u64,
+) -> Result<Vec<(GlobalBlockId, T::Value)>, libmdbx::Error>
+where
+    T: Table<Key = GlobalBlockId>,
+{
+    let mut blocks = Vec::default();
+    let mut maybe_entry = cursor.seek_range(&GlobalBlockId::new(from, BlockHash::zero()))?;
+    while let Some((id, value)) = maybe_entry {
+        if id.number() > to {
+            break;
+        }
+        blocks.push((id, value));
+        maybe_entry = cursor.next()?;
+    }
+    Ok(blocks)
+}
+
+/// Deletes the entries of the table for which `matches` returns true.
+fn delete_entries<T: Table>(
+    cursor: &mut TableCursor<'_, T, RW>,
+    mut matches: impl FnMut(&T::Key, &T::Value) -> bool,
+) -> Result<(), libmdbx::Error> {
+    let mut keys = Vec::default();
+    let mut maybe_entry = cursor.first()?;
+    while let Some((key, value)) = maybe_entry {
+        if matches(&key, &value) {
+            keys.push(key);
+        }
+        maybe_entry = cursor.next()?;
+    }
+    // delete after scanning, so the cursor position after a delete doesn't matter.
+    for key in keys {
+        if cursor.seek_exact(&key)?.is_some() {
+            cursor.del()?;
+        }
+    }
+    Ok(())
+}
+
+/// Groups the events in the given receipts by their selector (first key).
+///
+/// Events without keys are skipped.
+fn group_events_by_selector(
+    receipts: &[v1alpha2::TransactionReceipt],
+) -> Vec<(v1alpha2::FieldElement, Vec<v1alpha2::Event>)> {
+    let mut groups = BTreeMap::<[u8; 32], (v1alpha2::FieldElement, Vec<v1alpha2::Event>)>::new();
+    for event in receipts.iter().flat_map(|receipt| receipt.events.iter()) {
+        if let Some(selector) = event.keys.first() {
+            groups
+                .entry(selector.to_bytes())
+                .or_insert_with(|| (selector.clone(), Vec::default()))
+                .1
+                .push(event.clone());
+        }
+    }
+    groups.into_values().collect()
+}
+
+/// Returns the smallest selector greater than `selector`, or `None` if it's the largest.
+fn next_selector(selector: &v1alpha2::FieldElement) -> Option<v1alpha2::FieldElement> {
+    let mut bytes = selector.to_bytes();
+    for byte in bytes.iter_mut().rev() {
+        if *byte == u8::MAX {
+            *byte = 0;
+        } else {
+            *byte += 1;
+            return Some(v1alpha2::FieldElement::from_bytes(&bytes));
+        }
+    }
+    None
+}
+
+/// Adds the events addresses, keys and the data selected by `config` of the given receipts
+/// to the bloom filter.
+fn set_receipts_bloom(
+    bloom: &mut Bloom,
+    receipts: &[v1alpha2::TransactionReceipt],
+    config: &BloomConfig,
+) {
+    for receipt in receipts {
+        for event in &receipt.events {
+            if let Some(addr) = &event.from_address {
+                bloom.set(addr);
+            }
+            for key in event.keys.iter() {
+                bloom.set(key);
+            }
+            for position in config.data_positions.iter() {
+                if let Some(data) = event.data.get(*position) {
+                    bloom.set(data);
+                }
+            }
+        }
+    }
+}
+
+impl From<RawBloom> for Option<Bloom> {
+    fn from(raw: RawBloom) -> Self {
+        if raw.bytes.is_empty() {
+            return None;
+        }
+        let hasher_keys = raw.hasher_keys?;
+        let sip_keys = [
+            (hasher_keys.hash0_0, hasher_keys.hash0_1),
+            (hasher_keys.hash1_0, hasher_keys.hash1_1),
+        ];
+        let bloom = Bloom::from_existing(
+            &raw.bytes,
+            raw.bitmap_bits,
+            raw.number_of_hash_functions,
+            sip_keys,
+        );
+        Some(bloom)
+    }
+}
+
+impl From<Bloom> for RawBloom {
+    fn from(bloom: Bloom) -> Self {
+        let bytes = bloom.bitmap();
+        let bitmap_bits = bloom.number_of_bits();
+        let number_of_hash_functions = bloom.number_of_hash_functions();
+        let sip_keys = bloom.sip_keys();
+
+        let hasher_keys = HasherKeys {
+            hash0_0: sip_keys[0].0,
+            hash0_1: sip_keys[0].1,
+            hash1_0: sip_keys[1].0,
+            hash1_1: sip_keys[1].1,
+        };
+
+        RawBloom {
+            bytes,
+            bitmap_bits,
+            number_of_hash_functions,
+            hasher_keys: Some(hasher_keys),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt, MdbxTransactionExt, Table, TableKey,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::GlobalBlockId,
+        db::{
+            tables,
+            test_utils::{block_hash, block_id, new_environment, new_storage, write_chain},
+            BlockBody, BlockEvents, BlockStats, EventBySelectorKey, L1Handler, StatusHistogram,
+            TransactionHashKey, TransactionLocation,
+        },
+    };
+
+    use super::{
+        bloom_contains, iter_block_events, BloomConfig, BodyReader, ChainReader, DatabaseStorage,
+        HeaderReader, IndexKind, ReceiptReader, StateUpdateReader, StorageError, StorageReader,
+        StorageWriter,
+    };
+
+    /// Returns the entries of the table, with their key encoded.
+    fn table_entries<T: Table>(storage: &DatabaseStorage<NoWriteMap>) -> Vec<(Vec<u8>, T::Value)> {
+        let reader = storage.reader_with_txn().unwrap();
+        let mut cursor = reader.txn.open_cursor_in::<T>(reader.namespace).unwrap();
+        let mut entries = Vec::default();
+        let mut maybe_entry = cursor.first().unwrap();
+        while let Some((key, value)) = maybe_entry {
+            entries.push((key.encode().as_ref().to_vec(), value));
+            maybe_entry = cursor.next().unwrap();
+        }
+        entries
+    }
+
+    #[test]
+    fn test_find_common_ancestor_of_forked_chain() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        // 0 - 1 - 2 - 3
+        //      \
+        //       2' - 3' - 4'
+        write_chain(&storage, 0, 3, 0, 0);
+        write_chain(&storage, 2, 4, 1, 0);
+        // a chain that doesn't connect to the others.
+        write_chain(&storage, 5, 6, 2, 3);
+
+        let ancestor = storage
+            .find_common_ancestor(&block_id(3, 0), &block_id(4, 1))
+            .unwrap();
+        assert_eq!(ancestor, Some(block_id(1, 0)));
+
+        let ancestor = storage
+            .find_common_ancestor(&block_id(3, 0), &block_id(2, 0))
+            .unwrap();
+        assert_eq!(ancestor, Some(block_id(2, 0)));
+
+        let ancestor = storage
+            .find_common_ancestor(&block_id(6, 2), &block_id(3, 0))
+            .unwrap();
+        assert!(ancestor.is_none());
+    }
+
+    #[test]
+    fn test_initialize_twice_fails() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let genesis = v1alpha2::Block {
+            header: Some(v1alpha2::BlockHeader {
+                block_hash: Some(block_hash(0, 0)),
+                block_number: 0,
+                ..v1alpha2::BlockHeader::default()
+            }),
+            ..v1alpha2::Block::default()
+        };
+        storage.initialize(genesis.clone()).unwrap();
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(block_id(0, 0))
+        );
+
+        let err = storage.initialize(genesis).unwrap_err();
+        assert!(matches!(err, StorageError::AlreadyInitialized));
+    }
+
+    #[test]
+    fn test_iter_block_events_order() {
+        let event = |n| v1alpha2::Event {
+            from_address: Some(v1alpha2::FieldElement::from_u64(n)),
+            ..v1alpha2::Event::default()
+        };
+        let receipts = vec![
+            v1alpha2::TransactionReceipt {
+                transaction_index: 1,
+                events: vec![event(2)],
+                ..v1alpha2::TransactionReceipt::default()
+            },
+            v1alpha2::TransactionReceipt {
+                transaction_index: 0,
+                events: vec![event(0), event(1)],
+                ..v1alpha2::TransactionReceipt::default()
+            },
+        ];
+        let events = iter_block_events(&receipts)
+            .map(|(index, event)| (index, event.from_address.as_ref().unwrap().clone()))
+            .collect::<Vec<_>>();
+        let expected = (0..3)
+            .map(|n| (n as usize, v1alpha2::FieldElement::from_u64(n)))
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn test_subscribe_finalized_tip() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let mut finalized_tip = storage.subscribe_finalized_tip();
+        assert!(finalized_tip.borrow_and_update().is_none());
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            let status = if number < 2 {
+                v1alpha2::BlockStatus::AcceptedOnL1
+            } else {
+                v1alpha2::BlockStatus::AcceptedOnL2
+            };
+            txn.write_status(&block_id(number, 0), status).unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+        assert!(finalized_tip.has_changed().unwrap());
+        assert_eq!(*finalized_tip.borrow_and_update(), Some(block_id(1, 0)));
+
+        // finalizing a block that's not canonical doesn't move the tip.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_status(&block_id(2, 1), v1alpha2::BlockStatus::AcceptedOnL1)
+            .unwrap();
+        txn.commit().unwrap();
+        assert!(!finalized_tip.has_changed().unwrap());
+    }
+
+    #[test]
+    fn test_distinct_event_addresses() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let receipt = |addresses: &[u64]| v1alpha2::TransactionReceipt {
+            events: addresses
+                .iter()
+                .map(|n| v1alpha2::Event {
+                    from_address: Some(v1alpha2::FieldElement::from_u64(*n)),
+                    ..v1alpha2::Event::default()
+                })
+                .collect(),
+            ..v1alpha2::TransactionReceipt::default()
+        };
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            let receipts = vec![receipt(&[number, 7]), receipt(&[number + 10])];
+            txn.write_receipts(&block_id(number, 0), receipts).unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        // not canonical.
+        txn.write_receipts(&block_id(1, 1), vec![receipt(&[99])])
+            .unwrap();
+        txn.commit().unwrap();
+
+        let addresses = storage.distinct_event_addresses(1, 5).unwrap();
+        let expected = [1, 2, 7, 11, 12]
+            .into_iter()
+            .map(v1alpha2::FieldElement::from_u64)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(addresses, expected);
+    }
+
+    #[test]
+    fn test_write_header_if_absent() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let header = |block_number| v1alpha2::BlockHeader {
+            block_hash: Some(block_hash(0, 0)),
+            block_number,
+            ..v1alpha2::BlockHeader::default()
+        };
+        let mut txn = storage.begin_txn().unwrap();
+        assert!(txn
+            .write_header_if_absent(&block_id(0, 0), header(0))
+            .unwrap());
+        // only the key is compared, the stored header is kept.
+        assert!(!txn
+            .write_header_if_absent(&block_id(0, 0), header(1))
+            .unwrap());
+        txn.commit().unwrap();
+
+        let stored = storage.read_header(&block_id(0, 0)).unwrap().unwrap();
+        assert_eq!(stored.block_number, 0);
+    }
+
+    #[test]
+    fn test_reject_deletes_status_history() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path())
+            .with_status_history(true)
+            .with_reject_deletes_data(true);
+
+        write_chain(&storage, 0, 1, 0, 0);
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=1 {
+            let id = block_id(number, 0);
+            txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL2)
+                .unwrap();
+            txn.extend_canonical_chain(&id).unwrap();
+        }
+        txn.reject_block_from_canonical_chain(&block_id(1, 0))
+            .unwrap();
+        txn.commit().unwrap();
+
+        assert!(storage.read_header(&block_id(1, 0)).unwrap().is_none());
+        assert!(storage
+            .read_status_history(&block_id(1, 0))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage.read_status(&block_id(1, 0)).unwrap(),
+            Some(v1alpha2::BlockStatus::Rejected)
+        );
+        // the history of canonical blocks is kept.
+        assert_eq!(
+            storage.read_status_history(&block_id(0, 0)).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_events_with_key_prefix() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let selector = |namespace: u8, n: u8| {
+            let mut bytes = [0; 32];
+            bytes[0] = namespace;
+            bytes[31] = n;
+            v1alpha2::FieldElement::from_bytes(&bytes)
+        };
+        let receipt = v1alpha2::TransactionReceipt {
+            events: [selector(1, 0), selector(1, 1), selector(2, 0)]
+                .into_iter()
+                .map(|selector| v1alpha2::Event {
+                    keys: vec![selector],
+                    ..v1alpha2::Event::default()
+                })
+                .collect(),
+            ..v1alpha2::TransactionReceipt::default()
+        };
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            txn.write_receipts(&block_id(number, 0), vec![receipt.clone()])
+                .unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let events = storage.events_with_key_prefix(&[1], 1, 1).unwrap();
+        let selectors = events
+            .iter()
+            .map(|(block_id, event)| (block_id.number(), event.keys[0].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(selectors, vec![(1, selector(1, 0)), (1, selector(1, 1))]);
+    }
+
+    #[test]
+    fn test_blocks_by_sequencer() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let sequencer = v1alpha2::FieldElement::from_u64(1);
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..4 {
+            let header = v1alpha2::BlockHeader {
+                block_hash: Some(block_hash(number, 0)),
+                block_number: number,
+                sequencer_address: Some(v1alpha2::FieldElement::from_u64(number % 2)),
+                ..v1alpha2::BlockHeader::default()
+            };
+            txn.write_header(&block_id(number, 0), header).unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.reject_block_from_canonical_chain(&block_id(3, 0))
+            .unwrap();
+        txn.commit().unwrap();
+
+        let blocks = storage.blocks_by_sequencer(&sequencer, 0, 10).unwrap();
+        assert_eq!(blocks, vec![block_id(1, 0)]);
+    }
+
+    #[test]
+    fn test_time_and_sequencer_index_after_reorg() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        // both branches have the same timestamp and sequencer at block 1.
+        let sequencer = v1alpha2::FieldElement::from_u64(1);
+        let mut txn = storage.begin_txn().unwrap();
+        for (number, branch) in [(0, 0), (1, 0), (1, 1)] {
+            let header = v1alpha2::BlockHeader {
+                block_hash: Some(block_hash(number, branch)),
+                block_number: number,
+                sequencer_address: Some(sequencer.clone()),
+                timestamp: Some(pbjson_types::Timestamp {
+                    seconds: 100 + number as i64,
+                    nanos: 0,
+                }),
+                ..v1alpha2::BlockHeader::default()
+            };
+            txn.write_header(&block_id(number, branch), header).unwrap();
+        }
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let expect_tip = |tip: Option<GlobalBlockId>| {
+            let expected = std::iter::once(block_id(0, 0))
+                .chain(tip)
+                .collect::<Vec<_>>();
+            let by_sequencer = storage.blocks_by_sequencer(&sequencer, 0, 10).unwrap();
+            assert_eq!(by_sequencer, expected);
+            let by_time = storage.blocks_in_time_range(0, 200).unwrap();
+            assert_eq!(by_time, expected);
+        };
+        expect_tip(Some(block_id(1, 0)));
+
+        // reorg to branch 1, then back to branch 0.
+        for (rejected, extended) in [(0, 1), (1, 0)] {
+            let mut txn = storage.begin_txn().unwrap();
+            txn.reject_block_from_canonical_chain(&block_id(1, rejected))
+                .unwrap();
+            txn.commit().unwrap();
+            expect_tip(None);
+
+            let mut txn = storage.begin_txn().unwrap();
+            txn.extend_canonical_chain(&block_id(1, extended)).unwrap();
+            txn.commit().unwrap();
+            expect_tip(Some(block_id(1, extended)));
+        }
+
+        // snapshots index the headers of their blocks too.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.reject_block_from_canonical_chain(&block_id(1, 0))
+            .unwrap();
+        txn.load_canonical_snapshot(&[block_id(0, 0), block_id(1, 1)])
+            .unwrap();
+        txn.commit().unwrap();
+        expect_tip(Some(block_id(1, 1)));
+    }
+
+    #[test]
+    fn test_stream_storage_diffs() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let storage_diff = |n| v1alpha2::StorageDiff {
+            contract_address: Some(v1alpha2::FieldElement::from_u64(n)),
+            ..v1alpha2::StorageDiff::default()
+        };
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            let state_update = v1alpha2::StateUpdate {
+                state_diff: Some(v1alpha2::StateDiff {
+                    storage_diffs: vec![storage_diff(2 * number), storage_diff(2 * number + 1)],
+                    ..v1alpha2::StateDiff::default()
+                }),
+                ..v1alpha2::StateUpdate::default()
+            };
+            txn.write_state_update(&block_id(number, 0), state_update)
+                .unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let diffs = storage
+            .stream_storage_diffs(1, 10)
+            .map(|diff| diff.unwrap())
+            .collect::<Vec<_>>();
+        let expected = (2..6).map(|n| (n / 2, storage_diff(n))).collect::<Vec<_>>();
+        assert_eq!(diffs, expected);
+    }
+
+    #[test]
+    fn test_rebuild_index() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            let header = v1alpha2::BlockHeader {
+                block_hash: Some(block_hash(number, 0)),
+                block_number: number,
+                sequencer_address: Some(felt(1)),
+                timestamp: Some(pbjson_types::Timestamp {
+                    seconds: 100 + number as i64,
+                    nanos: 0,
+                }),
+                ..v1alpha2::BlockHeader::default()
+            };
+            let transaction = v1alpha2::TransactionWithReceipt {
+                transaction: Some(v1alpha2::Transaction {
+                    meta: Some(v1alpha2::TransactionMeta {
+                        hash: Some(felt(10 + number)),
+                        ..v1alpha2::TransactionMeta::default()
+                    }),
+                    ..v1alpha2::Transaction::default()
+                }),
+                receipt: Some(v1alpha2::TransactionReceipt {
+                    actual_fee: Some(felt(1)),
+                    events: vec![v1alpha2::Event {
+                        keys: vec![felt(number % 2)],
+                        ..v1alpha2::Event::default()
+                    }],
+                    ..v1alpha2::TransactionReceipt::default()
+                }),
+            };
+            let state_update = v1alpha2::StateUpdate {
+                state_diff: Some(v1alpha2::StateDiff {
+                    deployed_contracts: vec![v1alpha2::DeployedContract {
+                        contract_address: Some(felt(20 + number)),
+                        class_hash: Some(felt(30)),
+                    }],
+                    nonces: vec![v1alpha2::NonceUpdate {
+                        contract_address: Some(felt(20)),
+                        nonce: Some(felt(number)),
+                    }],
+                    ..v1alpha2::StateDiff::default()
+                }),
+                ..v1alpha2::StateUpdate::default()
+            };
+            let block = v1alpha2::Block {
+                status: v1alpha2::BlockStatus::AcceptedOnL2 as i32,
+                header: Some(header),
+                transactions: vec![transaction],
+                state_update: Some(state_update),
+                ..v1alpha2::Block::default()
+            };
+            txn.write_block(&block_id(number, 0), block).unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let snapshot = || {
+            (
+                table_entries::<tables::L1HandlerIndexTable>(&storage),
+                table_entries::<tables::TransactionByHashTable>(&storage),
+                table_entries::<tables::EventBySelectorTable>(&storage),
+                table_entries::<tables::HeaderByTimestampTable>(&storage),
+                table_entries::<tables::SequencerIndexTable>(&storage),
+                table_entries::<tables::ContractClassTable>(&storage),
+                table_entries::<tables::NonceTable>(&storage),
+                table_entries::<tables::BlockStatsTable>(&storage),
+            )
+        };
+        let expected = snapshot();
+
+        let mut txn = storage.begin_txn().unwrap();
+        let key = TransactionHashKey(felt(11));
+        txn.transaction_by_hash_cursor.seek_exact(&key).unwrap();
+        txn.transaction_by_hash_cursor.del().unwrap();
+        let key = EventBySelectorKey::new(felt(7), 1);
+        txn.event_by_selector_cursor
+            .put(&key, &BlockEvents::default())
+            .unwrap();
+        txn.block_stats_cursor
+            .put(&block_id(2, 0), &BlockStats::default())
+            .unwrap();
+        txn.commit().unwrap();
+        assert_ne!(snapshot(), expected);
+
+        // rebuilding an index leaves the other indexes as they are.
+        storage
+            .rebuild_index(IndexKind::TransactionByHash, 1, 2)
+            .unwrap();
+        assert_eq!(snapshot().1, expected.1);
+        assert_ne!(snapshot().2, expected.2);
+
+        for which in IndexKind::ALL {
+            storage.rebuild_index(which, 0, 2).unwrap();
+        }
+        assert_eq!(snapshot(), expected);
+    }
+
+    #[test]
+    fn test_write_receipts_replaces_events_by_selector() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = |hash: u64, selectors: &[u64]| v1alpha2::TransactionReceipt {
+            transaction_hash: Some(felt(hash)),
+            events: selectors
+                .iter()
+                .map(|selector| v1alpha2::Event {
+                    keys: vec![felt(*selector)],
+                    ..v1alpha2::Event::default()
+                })
+                .collect(),
+            ..v1alpha2::TransactionReceipt::default()
+        };
+        let selectors = |storage: &DatabaseStorage<NoWriteMap>| {
+            table_entries::<tables::EventBySelectorTable>(storage)
+                .into_iter()
+                .map(|(_, block_events)| {
+                    (
+                        block_events.block_hash.unwrap(),
+                        block_events
+                            .events
+                            .into_iter()
+                            .map(|event| event.keys[0].clone())
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_receipts(&block_id(1, 0), vec![receipt(10, &[1, 2])])
+            .unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        // the new receipts of the block don't emit selector 2 anymore.
+        txn.write_receipts(&block_id(1, 0), vec![receipt(10, &[1])])
+            .unwrap();
+        txn.commit().unwrap();
+        assert_eq!(selectors(&storage), vec![(block_hash(1, 0), vec![felt(1)])]);
+
+        // appending to a block replaces the entries written by another block at the same height.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_receipts(&block_id(1, 1), vec![receipt(11, &[1])])
+            .unwrap();
+        txn.append_receipts(&block_id(1, 0), vec![receipt(12, &[1])])
+            .unwrap();
+        txn.commit().unwrap();
+        assert_eq!(selectors(&storage), vec![(block_hash(1, 0), vec![felt(1)])]);
+        let events = storage.read_events_by_selector(&felt(1), 1, 1).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_block_may_contain_backfills_bloom() {
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = v1alpha2::TransactionReceipt {
+            events: vec![v1alpha2::Event {
+                from_address: Some(felt(1)),
+                keys: vec![felt(2)],
+                data: Vec::default(),
+            }],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+
+        for backfill in [false, true] {
+            let path = tempdir().unwrap();
+            let storage = new_storage(path.path())
+                .with_bloom_enabled(false)
+                .with_backfill_bloom_on_read(backfill);
+
+            let mut txn = storage.begin_txn().unwrap();
+            txn.write_receipts(&block_id(0, 0), vec![receipt.clone()])
+                .unwrap();
+            txn.commit().unwrap();
+
+            let has_bloom = || {
+                table_entries::<tables::BlockReceiptsTable>(&storage)[0]
+                    .1
+                    .bloom
+                    .is_some()
+            };
+            assert!(!has_bloom());
+            // the second round of queries uses the bloom, if stored.
+            for _ in 0..2 {
+                for n in 1..=2 {
+                    assert!(storage
+                        .block_may_contain(&block_id(0, 0), &felt(n))
+                        .unwrap());
+                }
+                assert!(!storage
+                    .block_may_contain(&block_id(1, 0), &felt(1))
+                    .unwrap());
+                assert_eq!(has_bloom(), backfill);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finality_of_snapshot_blocks() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        // snapshot blocks have no status.
+        let blocks: Vec<_> = (0..4).map(|number| block_id(number, 0)).collect();
+        let mut txn = storage.begin_txn().unwrap();
+        txn.load_canonical_snapshot(&blocks).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.finality_lag().unwrap(), None);
+        assert_eq!(storage.highest_finalized_block().unwrap(), None);
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_status(&block_id(1, 0), v1alpha2::BlockStatus::AcceptedOnL1)
+            .unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.finality_lag().unwrap(), Some(2));
+        assert_eq!(
+            storage.highest_finalized_block().unwrap(),
+            Some(block_id(1, 0))
+        );
+        assert_eq!(
+            *storage.subscribe_finalized_tip().borrow(),
+            Some(block_id(1, 0))
+        );
+    }
+
+    #[test]
+    fn test_append_receipts_updates_bloom() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = |index: u64, address: u64| v1alpha2::TransactionReceipt {
+            transaction_hash: Some(felt(10 + index)),
+            transaction_index: index,
+            events: vec![v1alpha2::Event {
+                from_address: Some(felt(address)),
+                ..v1alpha2::Event::default()
+            }],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_receipts(&block_id(0, 0), vec![receipt(0, 20)])
+            .unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        // the receipt already stored is skipped.
+        txn.append_receipts(&block_id(0, 0), vec![receipt(0, 20), receipt(1, 21)])
+            .unwrap();
+        txn.commit().unwrap();
+
+        let (receipts, bloom) = storage.read_receipts(&block_id(0, 0)).unwrap();
+        let hashes = receipts
+            .into_iter()
+            .map(|receipt| receipt.transaction_hash.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, vec![felt(10), felt(11)]);
+        assert!(bloom.is_some());
+        for address in [20, 21] {
+            let blocks = storage.blocks_matching_bloom(&felt(address), 0, 0).unwrap();
+            assert_eq!(blocks, vec![0]);
+        }
+    }
+
+    #[test]
+    fn test_read_l1_handlers() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let meta = |hash: u64| v1alpha2::TransactionMeta {
+            hash: Some(felt(hash)),
+            ..v1alpha2::TransactionMeta::default()
+        };
+        let l1_handler = |hash: u64| v1alpha2::Transaction {
+            meta: Some(meta(hash)),
+            transaction: Some(v1alpha2::transaction::Transaction::L1Handler(
+                v1alpha2::L1HandlerTransaction {
+                    contract_address: Some(felt(20)),
+                    entry_point_selector: Some(felt(30)),
+                    calldata: vec![felt(40), felt(41)],
+                },
+            )),
+        };
+        let invoke = v1alpha2::Transaction {
+            meta: Some(meta(10)),
+            ..v1alpha2::Transaction::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        let body = BlockBody {
+            transactions: vec![invoke, l1_handler(11)],
+        };
+        txn.write_body(&block_id(1, 0), body).unwrap();
+        let body = BlockBody {
+            transactions: vec![l1_handler(12)],
+        };
+        txn.write_body(&block_id(1, 1), body).unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        // the handler of the block that is not canonical is skipped.
+        let handlers = storage.read_l1_handlers(0, 1).unwrap();
+        let expected = L1Handler {
+            transaction_hash: Some(felt(11)),
+            from_address: Some(felt(40)),
+            to_address: Some(felt(20)),
+            entry_point_selector: Some(felt(30)),
+            payload: vec![felt(41)],
+        };
+        assert_eq!(handlers, vec![(block_id(1, 0), expected)]);
+    }
+
+    #[test]
+    fn test_load_canonical_snapshot() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=2 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.load_canonical_snapshot(&[block_id(0, 0), block_id(1, 1), block_id(2, 1)])
+            .unwrap();
+        txn.commit().unwrap();
+
+        assert!(!storage.is_canonical(&block_id(1, 0)).unwrap());
+        assert_eq!(storage.canonical_block_id(1).unwrap(), Some(block_id(1, 1)));
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(block_id(2, 1))
+        );
+
+        // the whole chain is replaced, even by a shorter snapshot.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.load_canonical_snapshot(&[block_id(0, 0)]).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.canonical_block_id(1).unwrap(), None);
+
+        let mut txn = storage.begin_txn().unwrap();
+        let err = txn
+            .load_canonical_snapshot(&[block_id(0, 0), block_id(2, 0)])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            StorageError::NonContiguousSnapshot {
+                expected: 1,
+                actual: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn test_class_hash_at() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let deploy = |class_hash: u64| v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                deployed_contracts: vec![v1alpha2::DeployedContract {
+                    contract_address: Some(felt(20)),
+                    class_hash: Some(felt(class_hash)),
+                }],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_state_update(&block_id(1, 0), deploy(30)).unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(storage.class_hash_at(&felt(20), 0).unwrap(), None);
+        assert_eq!(storage.class_hash_at(&felt(20), 1).unwrap(), Some(felt(30)));
+        assert_eq!(storage.class_hash_at(&felt(20), 5).unwrap(), Some(felt(30)));
+        assert_eq!(storage.class_hash_at(&felt(21), 5).unwrap(), None);
+        assert_eq!(
+            storage.contract_deployment_block(&felt(20)).unwrap(),
+            Some(block_id(1, 0))
+        );
+
+        // after a reorg, the class deployed by the new canonical block is returned.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.reject_block_from_canonical_chain(&block_id(1, 0))
+            .unwrap();
+        txn.write_state_update(&block_id(1, 1), deploy(31)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 1)).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.class_hash_at(&felt(20), 1).unwrap(), Some(felt(31)));
+    }
+
+    #[test]
+    fn test_bloom_disabled() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path()).with_bloom_enabled(false);
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = v1alpha2::TransactionReceipt {
+            events: vec![v1alpha2::Event {
+                from_address: Some(felt(20)),
+                ..v1alpha2::Event::default()
+            }],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_receipts(&block_id(0, 0), vec![receipt]).unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let (receipts, bloom) = storage.read_receipts(&block_id(0, 0)).unwrap();
+        assert_eq!(receipts.len(), 1);
+        assert!(bloom.is_none());
+        // blocks without bloom may contain any address.
+        let blocks = storage.blocks_matching_bloom(&felt(21), 0, 0).unwrap();
+        assert_eq!(blocks, vec![0]);
+        // the exact scan is used instead.
+        assert!(storage
+            .block_may_contain(&block_id(0, 0), &felt(20))
+            .unwrap());
+        assert!(!storage
+            .block_may_contain(&block_id(0, 0), &felt(21))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_write_validation() {
+        let path = tempdir().unwrap();
+        let db = new_environment(path.path());
+
+        let header = |number: u64, hash: Option<v1alpha2::FieldElement>| v1alpha2::BlockHeader {
+            block_hash: hash,
+            block_number: number,
+            ..v1alpha2::BlockHeader::default()
+        };
+
+        let storage = DatabaseStorage::new(db.clone()).with_write_validation(true);
+        let mut txn = storage.begin_txn().unwrap();
+        let wrong_number = txn.write_header(&block_id(1, 0), header(2, Some(block_hash(1, 0))));
+        assert!(matches!(
+            wrong_number,
+            Err(StorageError::HeaderMismatch { id }) if id == block_id(1, 0)
+        ));
+        let wrong_hash = txn.write_header(&block_id(1, 0), header(1, Some(block_hash(1, 1))));
+        assert!(matches!(
+            wrong_hash,
+            Err(StorageError::HeaderMismatch { id }) if id == block_id(1, 0)
+        ));
+        // headers without hash are only checked by number.
+        txn.write_header(&block_id(1, 0), header(1, None)).unwrap();
+        txn.write_header(&block_id(2, 0), header(2, Some(block_hash(2, 0))))
+            .unwrap();
+        txn.commit().unwrap();
+
+        // without validation, mismatched headers are written as they are.
+        let storage = DatabaseStorage::new(db);
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_header(&block_id(3, 0), header(4, None)).unwrap();
+        txn.commit().unwrap();
+        let stored = storage.read_header(&block_id(3, 0)).unwrap().unwrap();
+        assert_eq!(stored.block_number, 4);
+    }
+
+    #[test]
+    fn test_reject_deletes_data() {
+        let path = tempdir().unwrap();
+        let db = new_environment(path.path());
+
+        let write_and_reject = |storage: &DatabaseStorage<NoWriteMap>| {
+            write_chain(storage, 0, 1, 0, 0);
+            let mut txn = storage.begin_txn().unwrap();
+            let body = BlockBody {
+                transactions: vec![v1alpha2::Transaction::default()],
+            };
+            txn.write_body(&block_id(1, 0), body).unwrap();
+            let receipts = vec![v1alpha2::TransactionReceipt::default()];
+            txn.write_receipts(&block_id(1, 0), receipts).unwrap();
+            let state_update = v1alpha2::StateUpdate::default();
+            txn.write_state_update(&block_id(1, 0), state_update)
+                .unwrap();
+            txn.write_user_meta(&block_id(1, 0), vec![1]).unwrap();
+            txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+            txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+            txn.reject_block_from_canonical_chain(&block_id(1, 0))
+                .unwrap();
+            txn.commit().unwrap();
+        };
+
+        // by default, the data is kept.
+        let storage = DatabaseStorage::new(db.clone());
+        write_and_reject(&storage);
+        let id = block_id(1, 0);
+        assert!(!storage.is_canonical(&id).unwrap());
+        assert!(storage.read_header(&id).unwrap().is_some());
+        assert_eq!(storage.read_body(&id).unwrap().len(), 1);
+        assert_eq!(storage.read_receipts(&id).unwrap().0.len(), 1);
+        assert!(storage.read_state_update(&id).unwrap().is_some());
+        assert!(storage.read_user_meta(&id).unwrap().is_some());
+
+        let storage = DatabaseStorage::new(db).with_reject_deletes_data(true);
+        write_and_reject(&storage);
+        assert!(storage.read_header(&id).unwrap().is_none());
+        assert!(storage.read_body(&id).unwrap().is_empty());
+        assert!(storage.read_receipts(&id).unwrap().0.is_empty());
+        assert!(storage.read_state_update(&id).unwrap().is_none());
+        assert!(storage.read_user_meta(&id).unwrap().is_none());
+        assert!(storage.read_block_stats(&id).unwrap().is_none());
+        // the status records the rejection, and the parent is not touched.
+        assert_eq!(
+            storage.read_status(&id).unwrap(),
+            Some(v1alpha2::BlockStatus::Rejected)
+        );
+        assert!(storage.read_header(&block_id(0, 0)).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_read_events() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = |events: &[(u64, u64)]| v1alpha2::TransactionReceipt {
+            events: events
+                .iter()
+                .map(|(address, selector)| v1alpha2::Event {
+                    from_address: Some(felt(*address)),
+                    keys: vec![felt(*selector)],
+                    ..v1alpha2::Event::default()
+                })
+                .collect(),
+            ..v1alpha2::TransactionReceipt::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        let receipts = vec![receipt(&[(10, 1), (11, 1), (10, 2)])];
+        txn.write_receipts(&block_id(0, 0), receipts).unwrap();
+        // the block that is not canonical was written first, like a reorged block.
+        txn.write_receipts(&block_id(1, 1), vec![receipt(&[(10, 1)])])
+            .unwrap();
+        txn.write_receipts(&block_id(1, 0), vec![receipt(&[(10, 1)])])
+            .unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let read_events = |address: Option<u64>, selector: Option<u64>, from, to| {
+            storage
+                .read_events(
+                    address.map(felt).as_ref(),
+                    selector.map(felt).as_ref(),
+                    from,
+                    to,
+                )
+                .unwrap()
+                .into_iter()
+                .map(|(block_id, event)| {
+                    (block_id, event.from_address.unwrap(), event.keys[0].clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // events of the block that is not canonical are never returned.
+        assert_eq!(
+            read_events(Some(10), Some(1), 0, 1),
+            vec![
+                (block_id(0, 0), felt(10), felt(1)),
+                (block_id(1, 0), felt(10), felt(1)),
+            ]
+        );
+        assert_eq!(
+            read_events(Some(10), None, 0, 0),
+            vec![
+                (block_id(0, 0), felt(10), felt(1)),
+                (block_id(0, 0), felt(10), felt(2)),
+            ]
+        );
+        assert_eq!(
+            read_events(None, Some(1), 0, 0),
+            vec![
+                (block_id(0, 0), felt(10), felt(1)),
+                (block_id(0, 0), felt(11), felt(1)),
+            ]
+        );
+        assert_eq!(read_events(None, None, 0, 1).len(), 4);
+        assert!(read_events(Some(12), None, 0, 1).is_empty());
+    }
+
+    #[test]
+    fn test_nonce_at() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let nonce = |nonce: u64| v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                nonces: vec![v1alpha2::NonceUpdate {
+                    contract_address: Some(felt(20)),
+                    nonce: Some(felt(nonce)),
+                }],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_state_update(&block_id(0, 0), nonce(1)).unwrap();
+        txn.write_state_update(&block_id(2, 0), nonce(2)).unwrap();
+        for number in 0..=2 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        // block 1 doesn't change the nonce.
+        assert_eq!(storage.nonce_at(&felt(20), 0).unwrap(), Some(felt(1)));
+        assert_eq!(storage.nonce_at(&felt(20), 1).unwrap(), Some(felt(1)));
+        assert_eq!(storage.nonce_at(&felt(20), 2).unwrap(), Some(felt(2)));
+        assert_eq!(storage.nonce_at(&felt(21), 2).unwrap(), None);
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.reject_block_from_canonical_chain(&block_id(2, 0))
+            .unwrap();
+        txn.write_state_update(&block_id(2, 1), nonce(5)).unwrap();
+        txn.extend_canonical_chain(&block_id(2, 1)).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.nonce_at(&felt(20), 2).unwrap(), Some(felt(5)));
+    }
+
+    #[test]
+    fn test_namespaces_share_environment() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::builder()
+            .with_size_gib(1, 2)
+            .with_max_dbs(64)
+            .open(path.path())
+            .unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure_in(&txn, "mainnet").unwrap();
+        tables::ensure_in(&txn, "testnet").unwrap();
+        txn.commit().unwrap();
+        let db = Arc::new(db);
+        let mainnet = DatabaseStorage::new(db.clone()).with_namespace("mainnet");
+        let testnet = DatabaseStorage::new(db).with_namespace("testnet");
+
+        write_chain(&mainnet, 0, 1, 0, 0);
+        write_chain(&testnet, 0, 0, 1, 1);
+        let mut txn = mainnet.begin_txn().unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+        let mut txn = testnet.begin_txn().unwrap();
+        txn.extend_canonical_chain(&block_id(0, 1)).unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(
+            mainnet.highest_accepted_block().unwrap(),
+            Some(block_id(1, 0))
+        );
+        assert_eq!(
+            testnet.highest_accepted_block().unwrap(),
+            Some(block_id(0, 1))
+        );
+        assert!(mainnet.read_header(&block_id(0, 1)).unwrap().is_none());
+        assert!(testnet.read_header(&block_id(0, 0)).unwrap().is_none());
+        assert!(testnet.read_header(&block_id(0, 1)).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_block_stats() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = |fee: u64, events: usize| v1alpha2::TransactionReceipt {
+            actual_fee: Some(felt(fee)),
+            events: vec![v1alpha2::Event::default(); events],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        let body = BlockBody {
+            transactions: vec![v1alpha2::Transaction::default(); 2],
+        };
+        txn.write_body(&block_id(0, 0), body).unwrap();
+        txn.write_receipts(&block_id(0, 0), vec![receipt(1, 1), receipt(2, 2)])
+            .unwrap();
+        txn.commit().unwrap();
+
+        let stats = storage.read_block_stats(&block_id(0, 0)).unwrap();
+        let expected = BlockStats {
+            total_fee: Some(felt(3)),
+            transaction_count: 2,
+            event_count: 3,
+        };
+        assert_eq!(stats, Some(expected));
+        assert_eq!(storage.read_block_stats(&block_id(1, 0)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_transaction_location() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let body = |hashes: &[u64]| BlockBody {
+            transactions: hashes
+                .iter()
+                .map(|hash| v1alpha2::Transaction {
+                    meta: Some(v1alpha2::TransactionMeta {
+                        hash: Some(felt(*hash)),
+                        ..v1alpha2::TransactionMeta::default()
+                    }),
+                    ..v1alpha2::Transaction::default()
+                })
+                .collect(),
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_body(&block_id(1, 0), body(&[10, 11])).unwrap();
+        txn.write_body(&block_id(1, 1), body(&[12])).unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let location = storage.transaction_location(&felt(11)).unwrap();
+        let expected = TransactionLocation {
+            block: block_id(1, 0),
+            index: 1,
+        };
+        assert_eq!(location, Some(expected));
+        // transactions in blocks that are not canonical, and unknown transactions.
+        assert_eq!(storage.transaction_location(&felt(12)).unwrap(), None);
+        assert_eq!(storage.transaction_location(&felt(13)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_blocks_touching_class() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let felt = v1alpha2::FieldElement::from_u64;
+        let emit = |address: u64| v1alpha2::TransactionReceipt {
+            events: vec![v1alpha2::Event {
+                from_address: Some(felt(address)),
+                ..v1alpha2::Event::default()
+            }],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+        let deploy = v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                deployed_contracts: vec![
+                    v1alpha2::DeployedContract {
+                        contract_address: Some(felt(20)),
+                        class_hash: Some(felt(30)),
+                    },
+                    v1alpha2::DeployedContract {
+                        contract_address: Some(felt(21)),
+                        class_hash: Some(felt(31)),
+                    },
+                ],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        };
+        let storage_diff = v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                storage_diffs: vec![v1alpha2::StorageDiff {
+                    contract_address: Some(felt(21)),
+                    ..v1alpha2::StorageDiff::default()
+                }],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        };
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_state_update(&block_id(0, 0), deploy).unwrap();
+        txn.write_receipts(&block_id(1, 0), vec![emit(20)]).unwrap();
+        txn.write_state_update(&block_id(2, 0), storage_diff)
+            .unwrap();
+        txn.write_receipts(&block_id(3, 0), vec![emit(21)]).unwrap();
+        for number in 0..=3 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let numbers = |class_hash: u64, from: u64, to: u64| {
+            storage
+                .blocks_touching_class(&felt(class_hash), from, to)
+                .unwrap()
+                .into_iter()
+                .map(|id| id.number())
+                .collect::<Vec<_>>()
+        };
+        // deployments are state changes too.
+        assert_eq!(numbers(30, 0, 3), vec![0, 1]);
+        assert_eq!(numbers(31, 0, 3), vec![0, 2, 3]);
+        assert_eq!(numbers(31, 1, 2), vec![2]);
+        assert!(numbers(32, 0, 3).is_empty());
+    }
+
+    #[test]
+    fn test_read_block_at_cursor() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        write_chain(&storage, 0, 1, 0, 0);
+        write_chain(&storage, 1, 1, 1, 0);
+        let mut txn = storage.begin_txn().unwrap();
+        txn.extend_canonical_chain(&block_id(0, 0)).unwrap();
+        txn.extend_canonical_chain(&block_id(1, 0)).unwrap();
+        txn.commit().unwrap();
+
+        let block = storage
+            .read_block_at_cursor(&block_id(1, 0).to_cursor())
+            .unwrap()
+            .unwrap();
+        let header = block.header.unwrap();
+        assert_eq!(header.block_hash, Some(block_hash(1, 0)));
+        assert_eq!(header.block_number, 1);
+
+        // the block at the cursor was reorged out.
+        let cursor = block_id(1, 1).to_cursor();
+        assert!(storage.read_block_at_cursor(&cursor).unwrap().is_none());
+        // the cursor has no block hash.
+        let cursor = Cursor {
+            order_key: 1,
+            unique_key: Vec::default(),
+        };
+        assert!(storage.read_block_at_cursor(&cursor).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_extend_canonical_chain_checked() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        let mut txn = storage.begin_txn().unwrap();
+        // any block extends an empty chain.
+        txn.extend_canonical_chain_checked(&block_id(5, 0), &block_hash(0, 1))
+            .unwrap();
+        txn.extend_canonical_chain_checked(&block_id(6, 0), &block_hash(5, 0))
+            .unwrap();
+
+        let wrong_number = txn.extend_canonical_chain_checked(&block_id(8, 0), &block_hash(6, 0));
+        assert!(matches!(
+            wrong_number,
+            Err(StorageError::BrokenChain { id, tip }) if id == block_id(8, 0) && tip == block_id(6, 0)
+        ));
+        let wrong_parent = txn.extend_canonical_chain_checked(&block_id(7, 0), &block_hash(6, 1));
+        assert!(matches!(
+            wrong_parent,
+            Err(StorageError::BrokenChain { id, tip }) if id == block_id(7, 0) && tip == block_id(6, 0)
+        ));
+        txn.commit().unwrap();
+
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(block_id(6, 0))
+        );
+        assert!(storage.canonical_block_id(7).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_recent_blocks() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        write_chain(&storage, 0, 3, 0, 0);
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=4 {
+            // block 4 has no header.
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let numbers = |n| {
+            storage
+                .read_recent_blocks(n)
+                .unwrap()
+                .into_iter()
+                .map(|(id, header)| {
+                    assert_eq!(id.number(), header.block_number);
+                    id.number()
+                })
+                .collect::<Vec<_>>()
+        };
+        // the block without header is skipped and doesn't count.
+        assert_eq!(numbers(2), vec![3, 2]);
+        // the chain is shorter than `n`.
+        assert_eq!(numbers(10), vec![3, 2, 1, 0]);
+        assert!(numbers(0).is_empty());
+    }
+
+    #[test]
+    fn test_read_recent_transactions() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        // block `number` has `number` transactions, the nonce is the transaction position.
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..4 {
+            let transactions = (0..number)
+                .map(|index| v1alpha2::Transaction {
+                    meta: Some(v1alpha2::TransactionMeta {
+                        nonce: Some(v1alpha2::FieldElement::from_u64(number * 10 + index)),
+                        ..v1alpha2::TransactionMeta::default()
+                    }),
+                    ..v1alpha2::Transaction::default()
+                })
+                .collect();
+            txn.write_body(&block_id(number, 0), BlockBody { transactions })
+                .unwrap();
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let recent = |n| {
+            storage
+                .read_recent_transactions(n)
+                .unwrap()
+                .into_iter()
+                .map(|(id, transaction)| {
+                    let nonce = transaction.meta.unwrap().nonce.unwrap();
+                    (id.number(), nonce.hi_hi)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(recent(4), vec![(3, 32), (3, 31), (3, 30), (2, 21)]);
+        assert_eq!(
+            recent(10),
+            vec![(3, 32), (3, 31), (3, 30), (2, 21), (2, 20), (1, 10)]
+        );
+    }
+
+    #[test]
+    fn test_canonical_gaps() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        assert_eq!(storage.canonical_gaps(0, 3).unwrap(), vec![(0, 3)]);
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in [2, 3, 5, 6, 9] {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        // holes at the start, in the middle, and after the tip.
+        assert_eq!(
+            storage.canonical_gaps(0, 12).unwrap(),
+            vec![(0, 1), (4, 4), (7, 8), (10, 12)]
+        );
+        assert_eq!(storage.canonical_gaps(3, 6).unwrap(), vec![(4, 4)]);
+        assert_eq!(storage.canonical_gaps(5, 6).unwrap(), vec![]);
+        assert_eq!(storage.canonical_gaps(10, 11).unwrap(), vec![(10, 11)]);
+    }
+
+    #[test]
+    fn test_total_by_status() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        assert_eq!(
+            storage.total_by_status().unwrap(),
+            StatusHistogram::default()
+        );
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..3 {
+            txn.write_status(&block_id(number, 0), v1alpha2::BlockStatus::AcceptedOnL2)
+                .unwrap();
+        }
+        txn.write_status(&block_id(3, 0), v1alpha2::BlockStatus::Pending)
+            .unwrap();
+        txn.write_status(&block_id(3, 1), v1alpha2::BlockStatus::Rejected)
+            .unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(
+            storage.total_by_status().unwrap(),
+            StatusHistogram {
+                pending: 1,
+                accepted_on_l2: 3,
+                rejected: 1,
+                ..StatusHistogram::default()
+            }
+        );
+
+        // a status change moves the block to the new status.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_status(&block_id(0, 0), v1alpha2::BlockStatus::AcceptedOnL1)
+            .unwrap();
+        txn.write_status(&block_id(3, 0), v1alpha2::BlockStatus::AcceptedOnL2)
+            .unwrap();
+        txn.commit().unwrap();
+
+        let histogram = storage.total_by_status().unwrap();
+        assert_eq!(
+            histogram,
+            StatusHistogram {
+                accepted_on_l2: 3,
+                accepted_on_l1: 1,
+                rejected: 1,
+                ..StatusHistogram::default()
+            }
+        );
+        assert_eq!(histogram.total(), 5);
+    }
+
+    #[test]
+    fn test_tip_lag() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        assert_eq!(storage.tip_lag().unwrap(), None);
+        storage.record_chain_tip(10).unwrap();
+        assert_eq!(storage.tip_lag().unwrap(), None);
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=3 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        txn.commit().unwrap();
+        assert_eq!(storage.tip_lag().unwrap(), Some(7));
+
+        // the recorded tip is replaced, even if it's behind the canonical chain.
+        storage.record_chain_tip(2).unwrap();
+        assert_eq!(storage.tip_lag().unwrap(), Some(0));
+        storage.record_chain_tip(5).unwrap();
+        assert_eq!(storage.tip_lag().unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_bloom_data_positions() {
+        let felt = v1alpha2::FieldElement::from_u64;
+        let receipt = v1alpha2::TransactionReceipt {
+            events: vec![v1alpha2::Event {
+                from_address: Some(felt(1)),
+                keys: vec![felt(2)],
+                data: vec![felt(100), felt(101), felt(102)],
+            }],
+            ..v1alpha2::TransactionReceipt::default()
+        };
+
+        for data_positions in [vec![], vec![1, 5]] {
+            let path = tempdir().unwrap();
+            let config = BloomConfig {
+                data_positions: data_positions.clone(),
+            };
+            let storage = new_storage(path.path()).with_bloom_config(config);
+
+            let mut txn = storage.begin_txn().unwrap();
+            txn.write_receipts(&block_id(0, 0), vec![receipt.clone()])
+                .unwrap();
+            txn.commit().unwrap();
+
+            let (_, bloom) = storage.read_receipts(&block_id(0, 0)).unwrap();
+            let bloom = bloom.unwrap();
+            assert!(bloom_contains(&bloom, &felt(1)));
+            assert!(bloom_contains(&bloom, &felt(2)));
+            // only the indexed data position is added, position 5 is past the event data.
+            assert_eq!(
+                bloom_contains(&bloom, &felt(101)),
+                !data_positions.is_empty()
+            );
+            assert!(!bloom_contains(&bloom, &felt(100)));
+            assert!(!bloom_contains(&bloom, &felt(102)));
         }
     }
 }
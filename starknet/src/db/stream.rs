@@ -0,0 +1,146 @@
+//! Stream stored blocks.
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use futures::Stream;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::core::GlobalBlockId;
+
+use super::{
+    BodyReader, ChainReader, DatabaseStorage, HeaderReader, ReceiptReader, ScopedReader,
+    StateUpdateReader, StorageReader,
+};
+
+/// Number of blocks read ahead of the consumer by [DatabaseStorage::stream_blocks].
+const STREAM_READ_AHEAD: usize = 16;
+
+impl<E: EnvironmentKind> DatabaseStorage<E> {
+    /// Returns a stream of the canonical blocks between `from` and `to` (inclusive).
+    ///
+    /// Blocks contain the status, header, transactions with their receipts and state
+    /// update. Events and messages are only stored inside receipts, so `events` and
+    /// `l2_to_l1_messages` are left empty. The stream ends early at the end of the
+    /// canonical chain.
+    ///
+    /// Blocks are read by a single blocking task, which stays at most a few blocks ahead
+    /// of the consumer and stops when the stream is dropped. Each block is read in its own
+    /// transaction.
+    pub fn stream_blocks(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> impl Stream<Item = Result<v1alpha2::Block, libmdbx::Error>> {
+        let (tx, rx) = mpsc::channel(STREAM_READ_AHEAD);
+        let storage = self.clone();
+        tokio::task::spawn_blocking(move || {
+            for number in from..=to {
+                let block = match storage.read_canonical_block(number).transpose() {
+                    None => break,
+                    Some(block) => block,
+                };
+                let is_err = block.is_err();
+                // the consumer dropped the stream.
+                if tx.blocking_send(block).is_err() || is_err {
+                    break;
+                }
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
+    /// Reads the canonical block at the given height.
+    fn read_canonical_block(&self, number: u64) -> Result<Option<v1alpha2::Block>, libmdbx::Error> {
+        let reader = self.reader_with_txn()?;
+        let block_id = match reader.canonical_block_id(number)? {
+            None => return Ok(None),
+            Some(block_id) => block_id,
+        };
+        let block = reader.read_full_block(&block_id)?;
+        Ok(Some(block))
+    }
+}
+
+impl<'env, E: EnvironmentKind> ScopedReader<'env, E> {
+    /// Reads the block with the given id, as returned by [DatabaseStorage::stream_blocks].
+    pub(super) fn read_full_block(
+        &self,
+        block_id: &GlobalBlockId,
+    ) -> Result<v1alpha2::Block, libmdbx::Error> {
+        let status = self
+            .read_status(block_id)?
+            .unwrap_or(v1alpha2::BlockStatus::Unspecified);
+        let header = self.read_header(block_id)?;
+        let body = self.read_body(block_id)?;
+        let (mut receipts, _) = self.read_receipts(block_id)?;
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        let transactions = body
+            .into_iter()
+            .zip(receipts)
+            .map(|(transaction, receipt)| v1alpha2::TransactionWithReceipt {
+                transaction: Some(transaction),
+                receipt: Some(receipt),
+            })
+            .collect();
+        let state_update = self.read_state_update(block_id)?;
+        let block = v1alpha2::Block {
+            status: status as i32,
+            header,
+            state_update,
+            transactions,
+            ..v1alpha2::Block::default()
+        };
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        test_utils::{block_hash, block_id, new_storage, write_chain},
+        StorageWriter,
+    };
+
+    #[tokio::test]
+    async fn test_stream_blocks_after_reorg() {
+        let path = tempdir().unwrap();
+        let storage = new_storage(path.path());
+
+        // blocks 3 to 5 on branch 0 are replaced by blocks 3 to 6 on branch 1.
+        write_chain(&storage, 0, 5, 0, 0);
+        write_chain(&storage, 3, 6, 1, 0);
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..=5 {
+            txn.extend_canonical_chain(&block_id(number, 0)).unwrap();
+        }
+        for number in (3..=5).rev() {
+            txn.reject_block_from_canonical_chain(&block_id(number, 0))
+                .unwrap();
+        }
+        for number in 3..=6 {
+            txn.extend_canonical_chain(&block_id(number, 1)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let blocks: Vec<v1alpha2::Block> =
+            storage.stream_blocks(1, 10).try_collect().await.unwrap();
+        let hashes: Vec<_> = blocks
+            .into_iter()
+            .map(|block| block.header.unwrap().block_hash.unwrap())
+            .collect();
+        let expected = vec![
+            block_hash(1, 0),
+            block_hash(2, 0),
+            block_hash(3, 1),
+            block_hash(4, 1),
+            block_hash(5, 1),
+            block_hash(6, 1),
+        ];
+        assert_eq!(hashes, expected);
+    }
+}
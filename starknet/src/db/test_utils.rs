@@ -0,0 +1,62 @@
+//! Helpers shared by the storage tests.
+
+use std::{path::Path, sync::Arc};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{
+    libmdbx::{Environment, NoWriteMap},
+    MdbxEnvironmentExt,
+};
+
+use crate::core::GlobalBlockId;
+
+use super::{tables, DatabaseStorage, StorageWriter};
+
+/// Opens an environment at `path` with all tables created.
+pub(crate) fn new_environment(path: &Path) -> Arc<Environment<NoWriteMap>> {
+    let db = Environment::<NoWriteMap>::open(path).unwrap();
+    let txn = db.begin_rw_txn().unwrap();
+    tables::ensure(&txn).unwrap();
+    txn.commit().unwrap();
+    Arc::new(db)
+}
+
+/// Creates a storage in a new environment at `path`.
+pub(crate) fn new_storage(path: &Path) -> DatabaseStorage<NoWriteMap> {
+    DatabaseStorage::new(new_environment(path))
+}
+
+pub(crate) fn block_hash(number: u64, branch: u64) -> v1alpha2::FieldElement {
+    v1alpha2::FieldElement::from_u64(number << 8 | branch)
+}
+
+pub(crate) fn block_id(number: u64, branch: u64) -> GlobalBlockId {
+    GlobalBlockId::new(number, block_hash(number, branch).into())
+}
+
+/// Writes the headers of blocks `from..=to` on `branch`, the first block's parent is on
+/// `parent_branch`.
+pub(crate) fn write_chain(
+    storage: &DatabaseStorage<NoWriteMap>,
+    from: u64,
+    to: u64,
+    branch: u64,
+    parent_branch: u64,
+) {
+    let mut txn = storage.begin_txn().unwrap();
+    for number in from..=to {
+        let parent = if number == from {
+            parent_branch
+        } else {
+            branch
+        };
+        let header = v1alpha2::BlockHeader {
+            block_hash: Some(block_hash(number, branch)),
+            parent_block_hash: number.checked_sub(1).map(|n| block_hash(n, parent)),
+            block_number: number,
+            ..v1alpha2::BlockHeader::default()
+        };
+        txn.write_header(&block_id(number, branch), header).unwrap();
+    }
+    txn.commit().unwrap();
+}
@@ -1,6 +1,8 @@
 //! Transaction data.
 
-use apibara_node::db::Table;
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{KeyDecodeError, ProstCodec, Table, TableKey};
+use prost::Message;
 
 use super::block::{BlockBody, BlockReceipts};
 use crate::core::GlobalBlockId;
@@ -13,9 +15,108 @@ pub struct BlockBodyTable {}
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BlockReceiptsTable {}
 
+/// Store the l1 handler transactions in a block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct L1HandlerIndexTable {}
+
+/// Store the block and index of transactions, by their hash.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransactionByHashTable {}
+
+/// A transaction hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionHashKey(pub v1alpha2::FieldElement);
+
+/// Where a transaction is stored.
+#[derive(Clone, PartialEq, Message)]
+pub struct TransactionIndexEntry {
+    #[prost(uint64, tag = "1")]
+    pub block_number: u64,
+    /// Hash of the block, used to ignore blocks that are not canonical.
+    #[prost(message, tag = "2")]
+    pub block_hash: Option<v1alpha2::FieldElement>,
+    /// Index of the transaction in the block body.
+    #[prost(uint64, tag = "3")]
+    pub index: u64,
+}
+
+/// The position of a transaction in the canonical chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransactionLocation {
+    /// The block that contains the transaction.
+    pub block: GlobalBlockId,
+    /// The index of the transaction in the block.
+    pub index: usize,
+}
+
+/// A transaction handling a message from L1.
+#[derive(Clone, PartialEq, Message)]
+pub struct L1Handler {
+    #[prost(message, tag = "1")]
+    pub transaction_hash: Option<v1alpha2::FieldElement>,
+    /// The L1 address sending the message.
+    #[prost(message, tag = "2")]
+    pub from_address: Option<v1alpha2::FieldElement>,
+    /// The L2 contract receiving the message.
+    #[prost(message, tag = "3")]
+    pub to_address: Option<v1alpha2::FieldElement>,
+    #[prost(message, tag = "4")]
+    pub entry_point_selector: Option<v1alpha2::FieldElement>,
+    /// The message payload.
+    #[prost(message, repeated, tag = "5")]
+    pub payload: prost::alloc::vec::Vec<v1alpha2::FieldElement>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockL1Handlers {
+    #[prost(message, repeated, tag = "1")]
+    pub handlers: prost::alloc::vec::Vec<L1Handler>,
+}
+
+impl L1Handler {
+    /// Returns the l1 handler data of the transaction, if it's an l1 handler transaction.
+    pub fn from_transaction(tx: &v1alpha2::Transaction) -> Option<Self> {
+        let handler = match tx.transaction.as_ref()? {
+            v1alpha2::transaction::Transaction::L1Handler(handler) => handler,
+            _ => return None,
+        };
+        // the first calldata element is the address of the L1 sender.
+        let (from_address, payload) = match handler.calldata.split_first() {
+            None => (None, Vec::default()),
+            Some((from_address, payload)) => (Some(from_address.clone()), payload.to_vec()),
+        };
+        Some(L1Handler {
+            transaction_hash: tx.meta.as_ref().and_then(|m| m.hash.clone()),
+            from_address,
+            to_address: handler.contract_address.clone(),
+            entry_point_selector: handler.entry_point_selector.clone(),
+            payload,
+        })
+    }
+}
+
+impl TableKey for TransactionHashKey {
+    type Encoded = [u8; 32];
+
+    fn encode(&self) -> Self::Encoded {
+        self.0.to_bytes()
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        let hash: [u8; 32] = b.try_into().map_err(|_| KeyDecodeError::InvalidByteSize {
+            expected: 32,
+            actual: b.len(),
+        })?;
+        Ok(TransactionHashKey(v1alpha2::FieldElement::from_bytes(
+            &hash,
+        )))
+    }
+}
+
 impl Table for BlockBodyTable {
     type Key = GlobalBlockId;
     type Value = BlockBody;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "BlockBody"
@@ -25,8 +126,29 @@ impl Table for BlockBodyTable {
 impl Table for BlockReceiptsTable {
     type Key = GlobalBlockId;
     type Value = BlockReceipts;
+    type Codec = ProstCodec;
 
     fn db_name() -> &'static str {
         "BlockReceipts"
     }
 }
+
+impl Table for L1HandlerIndexTable {
+    type Key = GlobalBlockId;
+    type Value = BlockL1Handlers;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "L1HandlerIndex"
+    }
+}
+
+impl Table for TransactionByHashTable {
+    type Key = TransactionHashKey;
+    type Value = TransactionIndexEntry;
+    type Codec = ProstCodec;
+
+    fn db_name() -> &'static str {
+        "TransactionByHash"
+    }
+}
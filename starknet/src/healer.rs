@@ -8,7 +8,7 @@ use tracing::{info, warn};
 
 use crate::{
     core::GlobalBlockId,
-    db::{DatabaseStorage, StorageWriter},
+    db::{DatabaseStorage, StorageError, StorageWriter},
     provider::Provider,
 };
 
@@ -18,6 +18,8 @@ pub enum HealerError {
     ChannelClosed,
     #[error("database error")]
     Database(#[from] MdxError),
+    #[error("storage error")]
+    Storage(#[from] StorageError),
 }
 
 #[derive(Debug, Clone)]
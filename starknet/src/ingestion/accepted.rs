@@ -7,7 +7,7 @@ use tracing::{debug, info, warn};
 
 use crate::{
     core::GlobalBlockId,
-    db::{DatabaseStorage, StorageReader, StorageWriter},
+    db::{ChainReader, DatabaseStorage, HeaderReader, StorageReader, StorageWriter},
     provider::{BlockId, Provider, ProviderError},
 };
 
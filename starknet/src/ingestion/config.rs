@@ -8,6 +8,14 @@ pub struct BlockIngestionConfig {
     pub rpc_concurrency: usize,
     /// How often to refresh head block.
     pub head_refresh_interval: Duration,
+    /// Record the history of block status changes.
+    pub track_status_history: bool,
+    /// Compute a bloom filter of the events in each block.
+    pub bloom_enabled: bool,
+    /// Check that headers match the block they're written to.
+    pub write_validation: bool,
+    /// Delete the data of blocks rejected from the canonical chain.
+    pub reject_deletes_data: bool,
 }
 
 impl Default for BlockIngestionConfig {
@@ -15,6 +23,10 @@ impl Default for BlockIngestionConfig {
         BlockIngestionConfig {
             rpc_concurrency: 16,
             head_refresh_interval: Duration::from_secs(3),
+            track_status_history: false,
+            bloom_enabled: true,
+            write_validation: false,
+            reject_deletes_data: false,
         }
     }
 }
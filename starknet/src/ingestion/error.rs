@@ -2,7 +2,10 @@
 use apibara_node::db::libmdbx;
 use std::error::Error;
 
-use crate::core::{InvalidBlock, InvalidBlockHashSize};
+use crate::{
+    core::{InvalidBlock, InvalidBlockHashSize},
+    db::StorageError,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum BlockIngestionError {
@@ -10,6 +13,8 @@ pub enum BlockIngestionError {
     Provider(#[from] Box<dyn Error + Send + Sync + 'static>),
     #[error("failed to perform database operation")]
     Database(#[from] libmdbx::Error),
+    #[error("failed to perform storage operation")]
+    Storage(#[from] StorageError),
     #[error("block does not contain header")]
     MissingBlockHeader,
     #[error("block doesn't have hash")]
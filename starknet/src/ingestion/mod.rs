@@ -55,7 +55,11 @@ where
     /// Start ingesting blocks.
     pub async fn start(self, ct: CancellationToken) -> Result<(), BlockIngestionError> {
         loop {
-            let storage = DatabaseStorage::new(self.db.clone());
+            let storage = DatabaseStorage::new(self.db.clone())
+                .with_status_history(self.config.track_status_history)
+                .with_bloom_enabled(self.config.bloom_enabled)
+                .with_write_validation(self.config.write_validation)
+                .with_reject_deletes_data(self.config.reject_deletes_data);
             let result = StartedBlockIngestion::new(
                 self.provider.clone(),
                 storage,
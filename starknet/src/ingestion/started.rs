@@ -8,7 +8,7 @@ use tracing::{debug, info, warn};
 
 use crate::{
     core::{BlockHash, GlobalBlockId},
-    db::{DatabaseStorage, StorageReader, StorageWriter},
+    db::{ChainReader, DatabaseStorage, StorageWriter},
     ingestion::finalized::FinalizedBlockIngestion,
     provider::{BlockId, Provider, ProviderError},
 };
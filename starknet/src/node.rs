@@ -10,7 +10,7 @@ use std::{
 use apibara_node::db::{
     default_data_dir,
     libmdbx::{self, Environment, EnvironmentKind},
-    MdbxEnvironmentExt,
+    Durability, MdbxEnvironmentExt,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
@@ -175,6 +175,7 @@ pub struct StarkNetNodeBuilder<O: RequestObserver, E: EnvironmentKind> {
     datadir: PathBuf,
     provider: HttpProvider,
     poll_interval: Duration,
+    durability: Durability,
     request_observer: O,
     _phantom: PhantomData<E>,
 }
@@ -210,6 +211,7 @@ where
             datadir,
             provider: sequencer,
             poll_interval,
+            durability: Durability::default(),
             request_observer,
             _phantom: Default::default(),
         };
@@ -224,6 +226,14 @@ where
         self.poll_interval = poll_interval;
     }
 
+    /// Change how database commits are flushed to disk, see [Durability].
+    ///
+    /// Use [Durability::SafeNoSync] to speed up the initial sync, the blocks lost on a
+    /// system crash are ingested again on restart.
+    pub fn with_durability(&mut self, durability: Durability) {
+        self.durability = durability;
+    }
+
     pub fn with_request_observer<N: RequestObserver>(
         self,
         request_observer: N,
@@ -232,6 +242,7 @@ where
             datadir: self.datadir,
             provider: self.provider,
             poll_interval: self.poll_interval,
+            durability: self.durability,
             request_observer,
             _phantom: self._phantom,
         }
@@ -243,6 +254,7 @@ where
         let db = Environment::<E>::builder()
             .with_size_gib(10, 100)
             .with_growth_step_gib(2)
+            .with_durability(self.durability)
             .open(&self.datadir)
             .map_err(StarkNetNodeBuilderError::DatabaseOpen)?;
 
@@ -5,7 +5,11 @@ use std::sync::Arc;
 use apibara_core::starknet::v1alpha2;
 use tracing::trace;
 
-use crate::{core::GlobalBlockId, db::StorageReader, server::RequestMeter};
+use crate::{
+    core::GlobalBlockId,
+    db::{bloom_contains, StorageReader},
+    server::RequestMeter,
+};
 
 pub trait BlockDataFilter {
     type Error: std::error::Error + Send + Sync + 'static;
@@ -142,14 +146,14 @@ where
                         break;
                     }
                     Some(address) => {
-                        if bloom.check(address) {
+                        if bloom_contains(&bloom, address) {
                             has_match = true;
                             break;
                         }
                     }
                 }
                 for key in &filter.keys {
-                    if bloom.check(key) {
+                    if bloom_contains(&bloom, key) {
                         has_match = true;
                         break;
                     }